@@ -0,0 +1,612 @@
+//! Local admin REST API. Requests run the same worker commands as the desktop UI and
+//! answer with the events the worker sends back.
+//!
+//! The API is off until enabled in the app, which creates an owner-only token file.
+//! Requests need that token as `Authorization: Bearer` and must come from a loopback
+//! address or the Unix socket.
+
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{ConnectInfo, Path as UrlPath, Query, Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::app_state::{AppCommand, AppEvent};
+use crate::atomic_file;
+use crate::client_keys;
+use crate::server::{self, QuotaStatus, RouterStatus};
+use crate::shared::SharedState;
+
+const TOKEN_PREFIX: &str = "cra-";
+
+/// Events kept for admin requests that have not read them yet
+const EVENT_BUFFER: usize = 256;
+
+/// How long a request waits for the worker's answer
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminError {
+    /// The worker is not running
+    Unavailable,
+    Failed(String),
+    TimedOut,
+}
+
+/// Connects admin requests to the worker: commands go to the worker's channel and its
+/// events are published to waiting requests on their way to the UI. The proxy also
+/// sends its fire-and-forget commands through here.
+#[derive(Debug)]
+pub struct AdminBridge {
+    commands: Mutex<Option<Sender<AppCommand>>>,
+    events: broadcast::Sender<AppEvent>,
+    /// Hash of the admin token; `None` while the admin API is disabled
+    token_hash: RwLock<Option<String>>,
+    /// Id of the next `AppCommand::Tracked`
+    next_id: AtomicU64,
+}
+
+impl Default for AdminBridge {
+    fn default() -> Self {
+        Self {
+            commands: Mutex::new(None),
+            events: broadcast::channel(EVENT_BUFFER).0,
+            token_hash: RwLock::new(None),
+            next_id: AtomicU64::new(0),
+        }
+    }
+}
+
+impl AdminBridge {
+    pub fn connect(&self, commands: Sender<AppCommand>) {
+        if let Ok(mut lock) = self.commands.lock() {
+            *lock = Some(commands);
+        }
+    }
+
+    /// Enable the API with `token`, or disable it with `None`
+    pub fn set_token(&self, token: Option<&str>) {
+        if let Ok(mut lock) = self.token_hash.write() {
+            *lock = token.map(client_keys::hash_key);
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.token_hash.read().is_ok_and(|lock| lock.is_some())
+    }
+
+    fn accepts(&self, presented: &str) -> bool {
+        let presented = client_keys::hash_key(presented);
+        self.token_hash.read().is_ok_and(|lock| {
+            lock.as_deref().is_some_and(|hash| {
+                client_keys::constant_time_eq(hash.as_bytes(), presented.as_bytes())
+            })
+        })
+    }
+
+    pub fn publish(&self, event: &AppEvent) {
+        // Fails only when no request is waiting
+        let _ = self.events.send(event.clone());
+    }
+
+    /// Forward worker events to the UI, publishing each one first
+    pub fn relay_events(
+        self: Arc<Self>,
+        from: Receiver<AppEvent>,
+        to: Sender<AppEvent>,
+    ) -> JoinHandle<()> {
+        std::thread::spawn(move || {
+            for event in from {
+                self.publish(&event);
+                if matches!(
+                    event,
+                    AppEvent::CommandStarted(_) | AppEvent::CommandFinished(_)
+                ) {
+                    continue;
+                }
+                if to.send(event).is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    pub fn send(&self, command: AppCommand) -> Result<(), AdminError> {
+        let lock = self.commands.lock().map_err(|_| AdminError::Unavailable)?;
+        let commands = lock.as_ref().ok_or(AdminError::Unavailable)?;
+        commands.send(command).map_err(|_| AdminError::Unavailable)
+    }
+
+    /// Run `command` and wait for the first of its events that `result` maps to an
+    /// outcome. Fails if the command finishes without one.
+    pub async fn dispatch<T>(
+        &self,
+        command: AppCommand,
+        result: impl FnMut(AppEvent) -> Option<Result<T, String>>,
+    ) -> Result<T, AdminError> {
+        self.dispatch_until_done(command, result)
+            .await?
+            .ok_or_else(|| AdminError::Failed("The worker finished without an answer.".into()))
+    }
+
+    /// Like `dispatch`, but answers `None` when the command finishes without an
+    /// outcome. Only events the worker sent while running `command` reach `result`.
+    pub async fn dispatch_until_done<T>(
+        &self,
+        command: AppCommand,
+        mut result: impl FnMut(AppEvent) -> Option<Result<T, String>>,
+    ) -> Result<Option<T>, AdminError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        // Subscribe before sending so a fast answer cannot be missed
+        let mut events = self.events.subscribe();
+        self.send(AppCommand::Tracked {
+            id,
+            command: Box::new(command),
+        })?;
+        let wait = async {
+            let mut started = false;
+            loop {
+                match events.recv().await {
+                    Ok(AppEvent::CommandStarted(started_id)) => started |= started_id == id,
+                    Ok(AppEvent::CommandFinished(finished_id)) if finished_id == id => {
+                        return Ok(None);
+                    }
+                    Ok(event) if started => {
+                        if let Some(outcome) = result(event) {
+                            return outcome.map(Some).map_err(AdminError::Failed);
+                        }
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return Err(AdminError::Unavailable),
+                }
+            }
+        };
+        tokio::time::timeout(COMMAND_TIMEOUT, wait)
+            .await
+            .unwrap_or(Err(AdminError::TimedOut))
+    }
+}
+
+/// The token in `path`, creating the file if it is missing or empty
+pub fn load_or_create_token(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(token) if !token.trim().is_empty() => Ok(token.trim().to_string()),
+        Ok(_) => rotate_token(path),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => rotate_token(path),
+        Err(err) => Err(err).with_context(|| format!("Failed to read {:?}", path)),
+    }
+}
+
+/// Write a new token to `path`, invalidating the old one
+pub fn rotate_token(path: &Path) -> Result<String> {
+    let mut secret = [0u8; 32];
+    rand::rng().fill_bytes(&mut secret);
+    let token = format!("{TOKEN_PREFIX}{}", URL_SAFE_NO_PAD.encode(secret));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    atomic_file::write_atomic(path, token.as_bytes())?;
+    Ok(token)
+}
+
+pub fn routes(state: Arc<SharedState>) -> Router<Arc<SharedState>> {
+    Router::new()
+        .route("/admin/profiles", get(list_profiles))
+        .route("/admin/profiles/{name}", delete(delete_profile))
+        .route("/admin/profiles/{name}/switch", post(switch_profile))
+        .route("/admin/quota/refresh", post(refresh_quota))
+        .route("/admin/login", post(start_login).delete(cancel_login))
+        .route_layer(middleware::from_fn_with_state(state, require_admin_token))
+}
+
+async fn require_admin_token(
+    State(state): State<Arc<SharedState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.admin.is_enabled() {
+        return server::openai_error(
+            StatusCode::NOT_FOUND,
+            "invalid_request_error",
+            "admin_api_disabled",
+            "The admin API is disabled. Enable it in the Codex Router app.",
+        );
+    }
+    // Requests over the Unix socket carry no address and are already owner-only
+    if let Some(ConnectInfo(addr)) = request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        if !addr.ip().is_loopback() {
+            return server::openai_error(
+                StatusCode::FORBIDDEN,
+                "invalid_request_error",
+                "admin_not_local",
+                "The admin API only accepts requests from this machine.",
+            );
+        }
+    }
+    let authorized =
+        server::bearer_token(request.headers()).is_some_and(|token| state.admin.accepts(token));
+    if !authorized {
+        return server::openai_error(
+            StatusCode::UNAUTHORIZED,
+            "invalid_request_error",
+            "invalid_admin_token",
+            "Missing or invalid admin token.",
+        );
+    }
+    next.run(request).await
+}
+
+fn admin_error(err: AdminError) -> Response {
+    let (status, code, message) = match err {
+        AdminError::Unavailable => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "worker_unavailable",
+            "The app's worker is not running.".to_string(),
+        ),
+        AdminError::Failed(message) => {
+            (StatusCode::INTERNAL_SERVER_ERROR, "command_failed", message)
+        }
+        AdminError::TimedOut => (
+            StatusCode::GATEWAY_TIMEOUT,
+            "command_timeout",
+            "The worker did not answer in time.".to_string(),
+        ),
+    };
+    server::openai_error(status, "admin_error", code, &message)
+}
+
+/// Checked against the profiles the server knows, so typos get a 404 rather than a
+/// worker error
+fn unknown_profile(state: &SharedState, name: &str) -> Option<Response> {
+    let known = state
+        .profiles
+        .read()
+        .is_ok_and(|profiles| profiles.iter().any(|p| p.name == name));
+    (!known).then(|| {
+        server::openai_error(
+            StatusCode::NOT_FOUND,
+            "invalid_request_error",
+            "profile_not_found",
+            &format!("No profile named '{name}'."),
+        )
+    })
+}
+
+fn status_response(
+    state: &SharedState,
+    result: Result<Vec<crate::profile::ProfileSummary>, AdminError>,
+) -> Response {
+    match result {
+        Ok(profiles) => Json(RouterStatus::new(
+            profiles,
+            state.allow_credit_spending(),
+            state.allow_api_key_overflow(),
+        ))
+        .into_response(),
+        Err(err) => admin_error(err),
+    }
+}
+
+async fn list_profiles(State(state): State<Arc<SharedState>>) -> Response {
+    let result = state
+        .admin
+        .dispatch(AppCommand::LoadProfiles, |event| match event {
+            AppEvent::ProfilesLoaded(profiles) => Some(Ok(profiles)),
+            AppEvent::Error(message) => Some(Err(message)),
+            _ => None,
+        })
+        .await;
+    status_response(&state, result)
+}
+
+async fn switch_profile(
+    State(state): State<Arc<SharedState>>,
+    UrlPath(name): UrlPath<String>,
+) -> Response {
+    if let Some(response) = unknown_profile(&state, &name) {
+        return response;
+    }
+    let result = state
+        .admin
+        .dispatch(
+            AppCommand::SwitchProfile(name.clone()),
+            |event| match event {
+                AppEvent::ProfilesLoaded(profiles)
+                    if profiles.iter().any(|p| p.is_current && p.name == name) =>
+                {
+                    Some(Ok(()))
+                }
+                AppEvent::Error(message) => Some(Err(message)),
+                _ => None,
+            },
+        )
+        .await;
+    match result {
+        Ok(()) => Json(serde_json::json!({"current": name})).into_response(),
+        Err(err) => admin_error(err),
+    }
+}
+
+/// Moves the profile to the trash, from which the app can restore it
+async fn delete_profile(
+    State(state): State<Arc<SharedState>>,
+    UrlPath(name): UrlPath<String>,
+) -> Response {
+    if let Some(response) = unknown_profile(&state, &name) {
+        return response;
+    }
+    let result = state
+        .admin
+        .dispatch(
+            AppCommand::DeleteProfile(name.clone()),
+            |event| match event {
+                AppEvent::ProfileTrashed(entry) if entry.profile_name == name => Some(Ok(entry)),
+                AppEvent::Error(message) => Some(Err(message)),
+                _ => None,
+            },
+        )
+        .await;
+    match result {
+        Ok(entry) => Json(serde_json::json!({
+            "trashed": entry.profile_name,
+            "trash_id": entry.id,
+            "expires_at": entry.expires_at(),
+        }))
+        .into_response(),
+        Err(err) => admin_error(err),
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct RefreshQuery {
+    profile: Option<String>,
+}
+
+/// Refresh every profile's quota, or only `?profile=<name>`
+async fn refresh_quota(
+    State(state): State<Arc<SharedState>>,
+    Query(query): Query<RefreshQuery>,
+) -> Response {
+    let Some(name) = query.profile else {
+        let result = state
+            .admin
+            .dispatch(AppCommand::FetchQuota, |event| match event {
+                AppEvent::ProfilesLoaded(profiles) => Some(Ok(profiles)),
+                AppEvent::Error(message) => Some(Err(message)),
+                _ => None,
+            })
+            .await;
+        return status_response(&state, result);
+    };
+
+    if let Some(response) = unknown_profile(&state, &name) {
+        return response;
+    }
+    let result = state
+        .admin
+        .dispatch(
+            AppCommand::FetchProfileQuota {
+                name: name.clone(),
+                force: true,
+            },
+            |event| match event {
+                AppEvent::ProfileQuotaLoaded {
+                    name: loaded,
+                    quota,
+                    fetched_at,
+                    stale,
+                } if loaded == name => Some(Ok(QuotaStatus {
+                    plan_type: quota.plan_type,
+                    primary: quota.primary,
+                    secondary: quota.secondary,
+                    fetched_at: Some(fetched_at),
+                    stale,
+                })),
+                AppEvent::ProfileQuotaFailed {
+                    name: failed,
+                    error,
+                    ..
+                } if failed == name => Some(Err(error)),
+                // Sent instead when the login has expired
+                AppEvent::ProfilesLoaded(profiles)
+                    if profiles.iter().any(|p| p.name == name && !p.is_valid) =>
+                {
+                    Some(Err(format!("The login of '{name}' has expired.")))
+                }
+                AppEvent::Error(message) => Some(Err(message)),
+                _ => None,
+            },
+        )
+        .await;
+    match result {
+        Ok(quota) => Json(serde_json::json!({"profile": name, "quota": quota})).into_response(),
+        Err(err) => admin_error(err),
+    }
+}
+
+/// Start a browser login on this machine. Answers once the worker has started it; the
+/// new profile shows up in `/admin/profiles` when the login completes.
+async fn start_login(State(state): State<Arc<SharedState>>) -> Response {
+    let result = state
+        .admin
+        .dispatch_until_done(AppCommand::RunLogin, |event| match event {
+            AppEvent::Error(message) => Some(Err::<(), _>(message)),
+            _ => None,
+        })
+        .await;
+    match result {
+        Ok(_) => (
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({"status": "started"})),
+        )
+            .into_response(),
+        Err(err) => admin_error(err),
+    }
+}
+
+async fn cancel_login(State(state): State<Arc<SharedState>>) -> Response {
+    match state.admin.send(AppCommand::CancelLogin) {
+        Ok(()) => (
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({"status": "cancelling"})),
+        )
+            .into_response(),
+        Err(err) => admin_error(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::ProfileSummary;
+    use crate::server::{spawn_server, BindConfig, ServerHandle, ServerStatus};
+
+    fn profile(name: &str, is_current: bool) -> ProfileSummary {
+        ProfileSummary {
+            name: name.to_string(),
+            email: None,
+            is_current,
+            is_valid: true,
+            quota: None,
+            quota_fetched_at: None,
+            quota_stale: false,
+            meta: Default::default(),
+            health: Default::default(),
+            auth_kind: Default::default(),
+        }
+    }
+
+    async fn wait_for_status(handle: &ServerHandle) -> ServerStatus {
+        loop {
+            let status = handle.status();
+            if status != ServerStatus::Starting {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Answers commands the way the worker would, through the bridge
+    fn fake_worker(state: &Arc<SharedState>) {
+        let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
+        state.admin.connect(cmd_tx);
+        let bridge = state.admin.clone();
+        std::thread::spawn(move || {
+            for command in cmd_rx {
+                let AppCommand::Tracked { id, command } = command else {
+                    continue;
+                };
+                // Events of other commands, which requests must not take as theirs
+                bridge.publish(&AppEvent::Error("background".to_string()));
+                bridge.publish(&AppEvent::CommandStarted(id));
+                let event = match *command {
+                    AppCommand::SwitchProfile(name) => {
+                        AppEvent::ProfilesLoaded(vec![profile(&name, true)])
+                    }
+                    AppCommand::RunLogin => {
+                        bridge.publish(&AppEvent::CommandFinished(id));
+                        continue;
+                    }
+                    _ => AppEvent::Error("unsupported".to_string()),
+                };
+                bridge.publish(&event);
+                bridge.publish(&AppEvent::CommandFinished(id));
+            }
+        });
+    }
+
+    #[test]
+    fn tokens_are_created_once_and_rotated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("router").join("admin.token");
+
+        let token = load_or_create_token(&path).unwrap();
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(load_or_create_token(&path).unwrap(), token);
+
+        let rotated = rotate_token(&path).unwrap();
+        assert_ne!(rotated, token);
+        assert_eq!(load_or_create_token(&path).unwrap(), rotated);
+    }
+
+    #[tokio::test]
+    async fn admin_requests_need_the_token_and_return_worker_results() {
+        let state = Arc::new(SharedState::new());
+        let handle = spawn_server(
+            state.clone(),
+            BindConfig {
+                host: "127.0.0.1".to_string(),
+                port: 0,
+                ..BindConfig::default()
+            },
+        );
+        let ServerStatus::Listening(addr) = wait_for_status(&handle).await else {
+            panic!("server did not start");
+        };
+        fake_worker(&state);
+        state.update_profiles(vec![profile("work", false)]);
+        let client = reqwest::Client::new();
+        let url = format!("http://{addr}/admin/profiles/work/switch");
+
+        let disabled = client.post(&url).send().await.unwrap();
+        assert_eq!(disabled.status(), reqwest::StatusCode::NOT_FOUND);
+
+        state.admin.set_token(Some("cra-secret"));
+        let missing = client.post(&url).send().await.unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let switched = client
+            .post(&url)
+            .bearer_auth("cra-secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(switched.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = switched.json().await.unwrap();
+        assert_eq!(body["current"], "work");
+
+        let unknown = client
+            .post(format!("http://{addr}/admin/profiles/other/switch"))
+            .bearer_auth("cra-secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unknown.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let failed = client
+            .get(format!("http://{addr}/admin/profiles"))
+            .bearer_auth("cra-secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(failed.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+        let body: serde_json::Value = failed.json().await.unwrap();
+        assert_eq!(body["error"]["message"], "unsupported");
+
+        let login = client
+            .post(format!("http://{addr}/admin/login"))
+            .bearer_auth("cra-secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(login.status(), reqwest::StatusCode::ACCEPTED);
+    }
+}
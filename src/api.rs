@@ -3,33 +3,77 @@ use chrono::{DateTime, SecondsFormat, Utc};
 use reqwest::Client;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::time::Duration;
 
+use crate::auth::AuthKind;
 use crate::{auth, config};
 
 const DEFAULT_USAGE_URL: &str = "https://api.openai.com/v1/usage";
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const OPENAI_MODELS_PATH: &str = "/models";
 const DEFAULT_CHATGPT_BASE_URL: &str = "https://chatgpt.com/backend-api";
 const CODEX_USAGE_PATH: &str = "/api/codex/usage";
 const CODEX_USAGE_FALLBACK_PATH: &str = "/codex/usage";
 const DEFAULT_CHATGPT_FALLBACK_BASE_URL: &str = "https://chat.openai.com/backend-api";
 const DEFAULT_ORIGINATOR: &str = "codex_cli_rs";
+const ACCOUNTS_CHECK_PATH: &str = "/accounts/check/v4-2023-04-27";
+
+/// `plan_type` reported for API-key profiles, which are billed per token instead of
+/// having rate-limit windows
+pub const API_KEY_PLAN_TYPE: &str = "api_key";
 
 // OAuth token refresh constants
 const CLIENT_ID: &str = "app_EMoamEEZ73f0CkXaXp7hrann";
 
 /// Quota information
+///
+/// Serializes with both the structured fields and the legacy flat fields
+/// (`used_requests`, `used_tokens`, `reset_date`, ...), and deserializes from either shape.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(from = "QuotaInfoRepr", into = "QuotaInfoRepr")]
 pub struct QuotaInfo {
     pub account_id: String,
     pub email: String,
     pub plan_type: String,
-    pub used_requests: Option<u64>,
-    pub total_requests: Option<u64>,
-    pub used_tokens: Option<u64>,
-    pub total_tokens: Option<u64>,
-    pub reset_date: Option<String>,
-    pub secondary_reset_date: Option<String>,
+    /// Short rate-limit window (e.g. 5 hours)
+    pub primary: Option<RateLimitWindow>,
+    /// Long rate-limit window (e.g. weekly)
+    pub secondary: Option<RateLimitWindow>,
+    pub credits: Option<CreditsInfo>,
+    /// Raw counters from the platform usage API (API-key profiles only)
+    pub usage: Option<UsageCounts>,
+}
+
+/// A single rate-limit window as reported by the Codex usage endpoint
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RateLimitWindow {
+    pub used_percent: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_seconds: Option<i64>,
+    /// RFC 3339 timestamp of the next reset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resets_at: Option<String>,
+}
+
+/// Credit balance attached to a ChatGPT account
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CreditsInfo {
+    #[serde(default)]
+    pub has_credits: bool,
+    #[serde(default)]
+    pub unlimited: bool,
+    /// Balance as reported upstream (a decimal string such as "12.34")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<String>,
+}
+
+/// Request and token counters from the platform usage API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UsageCounts {
+    pub requests: Option<u64>,
+    pub tokens: Option<u64>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -38,7 +82,157 @@ pub enum AuthError {
     Expired,
 }
 
-impl QuotaInfo {}
+impl RateLimitWindow {
+    /// Used percent rounded and clamped to 0..=100
+    pub fn used_percent_rounded(&self) -> u64 {
+        self.used_percent.clamp(0.0, 100.0).round() as u64
+    }
+
+    pub fn remaining_percent(&self) -> u64 {
+        100 - self.used_percent_rounded()
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.used_percent >= 100.0
+    }
+}
+
+impl CreditsInfo {
+    pub fn balance_value(&self) -> Option<f64> {
+        self.balance.as_deref()?.trim().parse().ok()
+    }
+
+    /// Whether the account can keep serving requests on credits
+    pub fn is_available(&self) -> bool {
+        self.unlimited || self.balance_value().is_some_and(|balance| balance > 0.0)
+    }
+}
+
+impl QuotaInfo {
+    pub fn primary_used_percent(&self) -> Option<u64> {
+        self.primary
+            .as_ref()
+            .map(RateLimitWindow::used_percent_rounded)
+    }
+
+    pub fn secondary_used_percent(&self) -> Option<u64> {
+        self.secondary
+            .as_ref()
+            .map(RateLimitWindow::used_percent_rounded)
+    }
+
+    pub fn primary_resets_at(&self) -> Option<&str> {
+        self.primary.as_ref()?.resets_at.as_deref()
+    }
+
+    pub fn secondary_resets_at(&self) -> Option<&str> {
+        self.secondary.as_ref()?.resets_at.as_deref()
+    }
+}
+
+/// On-disk/wire shape of [`QuotaInfo`], including the legacy flat fields
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct QuotaInfoRepr {
+    #[serde(default)]
+    account_id: String,
+    #[serde(default)]
+    email: String,
+    #[serde(default)]
+    plan_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    primary_window: Option<RateLimitWindow>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secondary_window: Option<RateLimitWindow>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    credits: Option<CreditsInfo>,
+    #[serde(default)]
+    used_requests: Option<u64>,
+    #[serde(default)]
+    total_requests: Option<u64>,
+    #[serde(default)]
+    used_tokens: Option<u64>,
+    #[serde(default)]
+    total_tokens: Option<u64>,
+    #[serde(default)]
+    reset_date: Option<String>,
+    #[serde(default)]
+    secondary_reset_date: Option<String>,
+}
+
+impl From<QuotaInfo> for QuotaInfoRepr {
+    fn from(quota: QuotaInfo) -> Self {
+        let (used_requests, total_requests, used_tokens, total_tokens) = match quota.usage {
+            Some(usage) => (usage.requests, None, usage.tokens, None),
+            None => (
+                quota.primary_used_percent(),
+                quota.primary.as_ref().map(|_| 100),
+                quota.secondary_used_percent(),
+                quota.secondary.as_ref().map(|_| 100),
+            ),
+        };
+        Self {
+            reset_date: quota.primary_resets_at().map(str::to_string),
+            secondary_reset_date: quota.secondary_resets_at().map(str::to_string),
+            account_id: quota.account_id,
+            email: quota.email,
+            plan_type: quota.plan_type,
+            primary_window: quota.primary,
+            secondary_window: quota.secondary,
+            credits: quota.credits,
+            used_requests,
+            total_requests,
+            used_tokens,
+            total_tokens,
+        }
+    }
+}
+
+impl From<QuotaInfoRepr> for QuotaInfo {
+    fn from(repr: QuotaInfoRepr) -> Self {
+        let has_windows = repr.primary_window.is_some() || repr.secondary_window.is_some();
+        // Legacy payloads marked percent-based windows with a total of 100.
+        let legacy_window =
+            |used: Option<u64>, total: Option<u64>, reset: Option<String>| match (used, total) {
+                (Some(used), Some(100)) => Some(RateLimitWindow {
+                    used_percent: used as f64,
+                    window_seconds: None,
+                    resets_at: reset,
+                }),
+                _ => None,
+            };
+        let is_legacy_percent =
+            !has_windows && (repr.total_requests == Some(100) || repr.total_tokens == Some(100));
+        let is_legacy_counts = !has_windows
+            && !is_legacy_percent
+            && (repr.used_requests.is_some() || repr.used_tokens.is_some());
+
+        let (primary, secondary) = if has_windows {
+            (repr.primary_window, repr.secondary_window)
+        } else {
+            (
+                legacy_window(repr.used_requests, repr.total_requests, repr.reset_date),
+                legacy_window(
+                    repr.used_tokens,
+                    repr.total_tokens,
+                    repr.secondary_reset_date,
+                ),
+            )
+        };
+
+        Self {
+            account_id: repr.account_id,
+            email: repr.email,
+            plan_type: repr.plan_type,
+            primary,
+            secondary,
+            credits: repr.credits,
+            usage: is_legacy_counts.then_some(UsageCounts {
+                requests: repr.used_requests,
+                tokens: repr.used_tokens,
+            }),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Deserialize)]
 struct CodexUsagePayload {
@@ -78,6 +272,8 @@ struct CodexRateLimitWindowSnapshot {
     #[serde(default)]
     used_percent: Option<f64>,
     #[serde(default)]
+    limit_window_seconds: Option<i64>,
+    #[serde(default)]
     reset_at: Option<CodexResetAt>,
     #[serde(default)]
     reset_after_seconds: Option<i64>,
@@ -85,6 +281,8 @@ struct CodexRateLimitWindowSnapshot {
 
 #[derive(Debug, Clone, Deserialize)]
 struct CodexCreditStatus {
+    #[serde(default)]
+    has_credits: Option<bool>,
     #[serde(default)]
     unlimited: Option<bool>,
     #[serde(default)]
@@ -108,6 +306,10 @@ pub async fn fetch_quota(auth: &auth::AuthDotJson) -> Result<QuotaInfo> {
         .context("No valid token found")?;
     let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
 
+    if AuthKind::of(auth) == AuthKind::ApiKey {
+        return fetch_api_key_quota_with_client(&client, auth, &openai_base_url()).await;
+    }
+
     if auth.tokens.is_some() && auth.openai_api_key.is_none() {
         let mut failures: Vec<anyhow::Error> = Vec::new();
         for url in codex_usage_urls() {
@@ -233,64 +435,226 @@ async fn fetch_quota_with_client(
     }
 }
 
+/// API keys have no rate-limit windows, so their "quota" only confirms the key is
+/// accepted, using the model list as the cheapest authenticated endpoint.
+async fn fetch_api_key_quota_with_client(
+    client: &Client,
+    auth: &auth::AuthDotJson,
+    base_url: &str,
+) -> Result<QuotaInfo> {
+    let api_key = auth
+        .openai_api_key
+        .as_deref()
+        .context("No valid token found")?;
+    let url = join_url(base_url, OPENAI_MODELS_PATH);
+    let resp = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await
+        .with_context(|| format!("API request failed for {}", url))?;
+
+    let status = resp.status();
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(AuthError::Expired.into());
+    }
+    if !status.is_success() {
+        let error = resp.text().await.unwrap_or_default();
+        anyhow::bail!("API returned status {} for {}: {}", status, url, error);
+    }
+
+    Ok(QuotaInfo {
+        account_id: String::new(),
+        email: String::new(),
+        plan_type: API_KEY_PLAN_TYPE.to_string(),
+        primary: None,
+        secondary: None,
+        credits: None,
+        usage: None,
+    })
+}
+
 fn codex_payload_to_quota_info(auth: &auth::AuthDotJson, payload: CodexUsagePayload) -> QuotaInfo {
-    let primary_used = payload
+    let primary = payload
         .rate_limit
         .as_ref()
         .and_then(|limit| limit.primary_window.as_ref())
-        .and_then(|window| window.used_percent)
-        .map(|value| value.clamp(0.0, 100.0).round() as u64);
-    let secondary_used = payload
+        .and_then(codex_window_to_rate_limit_window);
+    let secondary = payload
         .rate_limit
         .as_ref()
         .and_then(|limit| limit.secondary_window.as_ref())
-        .and_then(|window| window.used_percent)
-        .map(|value| value.clamp(0.0, 100.0).round() as u64);
-    let reset_date = payload
-        .rate_limit
-        .as_ref()
-        .and_then(|limit| limit.primary_window.as_ref())
-        .and_then(|window| {
-            calculate_reset_time(window.reset_at.as_ref(), window.reset_after_seconds)
-        });
-    let secondary_reset_date = payload
-        .rate_limit
-        .as_ref()
-        .and_then(|limit| limit.secondary_window.as_ref())
-        .and_then(|window| {
-            calculate_reset_time(window.reset_at.as_ref(), window.reset_after_seconds)
-        });
+        .and_then(codex_window_to_rate_limit_window);
 
     let plan_type = payload
         .plan_type
         .or_else(|| auth::get_plan_type(auth))
         .unwrap_or_else(|| "Unknown".to_string());
-    let plan_type = format_plan_type_with_credits(plan_type, payload.credits.as_ref());
 
     QuotaInfo {
         account_id: auth::get_account_id(auth).unwrap_or_default(),
         email: auth::get_email(auth).unwrap_or_default(),
         plan_type,
-        used_requests: primary_used,
-        total_requests: primary_used.map(|_| 100),
-        used_tokens: secondary_used,
-        total_tokens: secondary_used.map(|_| 100),
-        reset_date,
-        secondary_reset_date,
+        primary,
+        secondary,
+        credits: payload.credits.map(|credits| CreditsInfo {
+            has_credits: credits
+                .has_credits
+                .unwrap_or(credits.unlimited.unwrap_or(false) || credits.balance.is_some()),
+            unlimited: credits.unlimited.unwrap_or(false),
+            balance: credits.balance,
+        }),
+        usage: None,
     }
 }
 
-fn format_plan_type_with_credits(plan_type: String, credits: Option<&CodexCreditStatus>) -> String {
-    let Some(credits) = credits else {
-        return plan_type;
-    };
-    if credits.unlimited.unwrap_or(false) {
-        return format!("{plan_type} (credits: unlimited)");
+fn codex_window_to_rate_limit_window(
+    window: &CodexRateLimitWindowSnapshot,
+) -> Option<RateLimitWindow> {
+    Some(RateLimitWindow {
+        used_percent: window.used_percent?.clamp(0.0, 100.0),
+        window_seconds: window.limit_window_seconds,
+        resets_at: calculate_reset_time(window.reset_at.as_ref(), window.reset_after_seconds),
+    })
+}
+
+/// A ChatGPT account reachable with one login: the personal account or a team workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub account_id: String,
+    pub name: Option<String>,
+    pub plan_type: Option<String>,
+    pub is_personal: bool,
+}
+
+impl Workspace {
+    pub fn label(&self) -> String {
+        match (&self.name, self.is_personal) {
+            (Some(name), _) => name.clone(),
+            (None, true) => "Personal".to_string(),
+            (None, false) => self.account_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountsCheckPayload {
+    #[serde(default)]
+    accounts: HashMap<String, AccountsCheckEntry>,
+    #[serde(default)]
+    account_ordering: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountsCheckEntry {
+    account: Option<AccountsCheckAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountsCheckAccount {
+    account_id: Option<String>,
+    name: Option<String>,
+    structure: Option<String>,
+    plan_type: Option<String>,
+}
+
+/// List the accounts (personal and workspaces) available to a ChatGPT login
+pub async fn fetch_workspaces(auth: &auth::AuthDotJson) -> Result<Vec<Workspace>> {
+    let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+    let url = join_url(&chatgpt_base_url(), ACCOUNTS_CHECK_PATH);
+    fetch_workspaces_with_client(&client, auth, &url).await
+}
+
+async fn fetch_workspaces_with_client(
+    client: &Client,
+    auth: &auth::AuthDotJson,
+    url: &str,
+) -> Result<Vec<Workspace>> {
+    let tokens = auth
+        .tokens
+        .as_ref()
+        .context("Workspaces are only available for ChatGPT logins")?;
+
+    // No ChatGPT-Account-Id header: the response should cover every account of the login
+    let resp = client
+        .get(url)
+        .header("Authorization", format!("Bearer {}", tokens.access_token))
+        .header("originator", DEFAULT_ORIGINATOR)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .header(reqwest::header::USER_AGENT, config::default_user_agent())
+        .send()
+        .await
+        .with_context(|| format!("API request failed for {}", url))?;
+
+    let status = resp.status();
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(AuthError::Expired.into());
+    }
+    if !status.is_success() {
+        let error = resp.text().await.unwrap_or_default();
+        anyhow::bail!("API returned status {} for {}: {}", status, url, error);
+    }
+    let payload: AccountsCheckPayload = resp
+        .json()
+        .await
+        .with_context(|| format!("Failed to parse accounts from {}", url))?;
+    Ok(workspaces_from_payload(payload))
+}
+
+/// Flatten the accounts map in `account_ordering` order, skipping the `default` alias
+fn workspaces_from_payload(payload: AccountsCheckPayload) -> Vec<Workspace> {
+    let mut workspaces: Vec<Workspace> = Vec::new();
+    for (key, entry) in payload.accounts {
+        let Some(account) = entry.account else {
+            continue;
+        };
+        let Some(account_id) = account
+            .account_id
+            .or_else(|| (key != "default").then_some(key))
+        else {
+            continue;
+        };
+        if workspaces.iter().any(|w| w.account_id == account_id) {
+            continue;
+        }
+        workspaces.push(Workspace {
+            account_id,
+            name: account.name.filter(|name| !name.trim().is_empty()),
+            plan_type: account.plan_type,
+            is_personal: account.structure.as_deref() == Some("personal"),
+        });
     }
-    let Some(balance) = credits.balance.as_deref() else {
-        return plan_type;
+
+    let position = |id: &str| {
+        payload
+            .account_ordering
+            .iter()
+            .position(|ordered| ordered == id)
+            .unwrap_or(usize::MAX)
     };
-    format!("{plan_type} (credits: {balance})")
+    workspaces.sort_by(|a, b| {
+        position(&a.account_id)
+            .cmp(&position(&b.account_id))
+            .then_with(|| a.account_id.cmp(&b.account_id))
+    });
+    workspaces
+}
+
+fn chatgpt_base_url() -> String {
+    env::var("CODEX_ROUTER_CHATGPT_BASE_URL")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_CHATGPT_BASE_URL.to_string())
+}
+
+/// Base URL of the platform API that API-key profiles are routed to
+pub fn openai_base_url() -> String {
+    env::var("CODEX_ROUTER_OPENAI_BASE_URL")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_OPENAI_BASE_URL.to_string())
 }
 
 fn codex_usage_urls() -> Vec<String> {
@@ -372,12 +736,13 @@ fn parse_quota_response(auth: &auth::AuthDotJson, data: &serde_json::Value) -> R
         account_id: auth::get_account_id(auth).unwrap_or_default(),
         email: auth::get_email(auth).unwrap_or_default(),
         plan_type: auth::get_plan_type(auth).unwrap_or_else(|| "Unknown".to_string()),
-        used_requests: data["data"]["usage"][0]["n_requests"].as_u64(),
-        total_requests: None, // API may not provide this
-        used_tokens: data["data"]["usage"][0]["n_tokens"].as_u64(),
-        total_tokens: None,
-        reset_date: None,
-        secondary_reset_date: None,
+        primary: None,
+        secondary: None,
+        credits: None,
+        usage: Some(UsageCounts {
+            requests: data["data"]["usage"][0]["n_requests"].as_u64(),
+            tokens: data["data"]["usage"][0]["n_tokens"].as_u64(),
+        }),
     })
 }
 
@@ -387,12 +752,10 @@ fn get_fallback_quota(auth: &auth::AuthDotJson) -> Result<QuotaInfo> {
         account_id: auth::get_account_id(auth).unwrap_or_default(),
         email: auth::get_email(auth).unwrap_or_default(),
         plan_type: auth::get_plan_type(auth).unwrap_or_else(|| "Unknown".to_string()),
-        used_requests: None,
-        total_requests: None,
-        used_tokens: None,
-        total_tokens: None,
-        reset_date: None,
-        secondary_reset_date: None,
+        primary: None,
+        secondary: None,
+        credits: None,
+        usage: None,
     })
 }
 
@@ -498,8 +861,14 @@ mod tests {
             "data": { "usage": [ { "n_requests": 5, "n_tokens": 10 } ] }
         });
         let info = parse_quota_response(&auth_stub(), &data).unwrap();
-        assert_eq!(info.used_requests, Some(5));
-        assert_eq!(info.used_tokens, Some(10));
+        assert_eq!(
+            info.usage,
+            Some(UsageCounts {
+                requests: Some(5),
+                tokens: Some(10),
+            })
+        );
+        assert!(info.primary.is_none());
     }
 
     #[tokio::test]
@@ -543,8 +912,9 @@ mod tests {
 
         let quota = fetch_quota_with_client(&client, &auth, &url).await.unwrap();
         assert_eq!(quota.account_id, "acct_123");
-        assert!(quota.used_requests.is_none());
-        assert!(quota.used_tokens.is_none());
+        assert!(quota.primary.is_none());
+        assert!(quota.secondary.is_none());
+        assert!(quota.usage.is_none());
 
         server.join().unwrap();
     }
@@ -615,16 +985,70 @@ mod tests {
         assert!(request.contains("user-agent: codex-cli"));
 
         assert_eq!(quota.account_id, "acct_123");
-        assert_eq!(quota.plan_type, "pro (credits: 12.34)");
-        assert_eq!(quota.used_requests, Some(25));
-        assert_eq!(quota.total_requests, Some(100));
-        assert_eq!(quota.used_tokens, Some(10));
-        assert_eq!(quota.total_tokens, Some(100));
-        assert_eq!(quota.reset_date.as_deref(), Some("2026-01-13T00:00:00Z"));
+        assert_eq!(quota.plan_type, "pro");
+        assert_eq!(
+            quota.primary,
+            Some(RateLimitWindow {
+                used_percent: 25.0,
+                window_seconds: Some(3600),
+                resets_at: Some("2026-01-13T00:00:00Z".to_string()),
+            })
+        );
+        assert_eq!(quota.secondary_used_percent(), Some(10));
+        assert_eq!(
+            quota.secondary.as_ref().and_then(|w| w.window_seconds),
+            Some(86400)
+        );
+        assert_eq!(
+            quota.credits,
+            Some(CreditsInfo {
+                has_credits: true,
+                unlimited: false,
+                balance: Some("12.34".to_string()),
+            })
+        );
+        assert_eq!(
+            quota.credits.as_ref().and_then(CreditsInfo::balance_value),
+            Some(12.34)
+        );
 
         server.join().unwrap();
     }
 
+    #[test]
+    fn parses_workspaces_in_account_order() {
+        let json = r#"{
+            "accounts": {
+                "default": {"account": {"account_id": "acct_personal", "structure": "personal", "plan_type": "plus"}},
+                "acct_personal": {"account": {"account_id": "acct_personal", "structure": "personal", "plan_type": "plus"}},
+                "acct_team": {"account": {"account_id": "acct_team", "name": "Acme", "structure": "workspace", "plan_type": "team"}}
+            },
+            "account_ordering": ["acct_team", "acct_personal"]
+        }"#;
+        let payload: AccountsCheckPayload = serde_json::from_str(json).unwrap();
+
+        let workspaces = workspaces_from_payload(payload);
+
+        assert_eq!(
+            workspaces,
+            vec![
+                Workspace {
+                    account_id: "acct_team".to_string(),
+                    name: Some("Acme".to_string()),
+                    plan_type: Some("team".to_string()),
+                    is_personal: false,
+                },
+                Workspace {
+                    account_id: "acct_personal".to_string(),
+                    name: None,
+                    plan_type: Some("plus".to_string()),
+                    is_personal: true,
+                },
+            ]
+        );
+        assert_eq!(workspaces[1].label(), "Personal");
+    }
+
     #[test]
     fn codex_usage_urls_prefers_non_api_path_first() {
         let _lock = ENV_LOCK.lock().unwrap();
@@ -684,6 +1108,56 @@ mod tests {
         server.join().unwrap();
     }
 
+    #[tokio::test]
+    async fn api_key_quota_checks_the_key_against_the_platform_api() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in ["200 OK", "401 Unauthorized"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 1024];
+                let n = stream.read(&mut buf).unwrap();
+                requests.push(String::from_utf8_lossy(&buf[..n]).to_string());
+                let body = r#"{"object":"list","data":[]}"#;
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        let base_url = format!("http://{}/v1", addr);
+        let auth = auth::api_key_auth("sk-test");
+
+        let quota = fetch_api_key_quota_with_client(&client, &auth, &base_url)
+            .await
+            .unwrap();
+        assert_eq!(quota.plan_type, API_KEY_PLAN_TYPE);
+        assert!(quota.primary.is_none() && quota.secondary.is_none());
+
+        let err = fetch_api_key_quota_with_client(&client, &auth, &base_url)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<AuthError>(),
+            Some(AuthError::Expired)
+        ));
+
+        let requests = server.join().unwrap();
+        let first = requests[0].to_lowercase();
+        assert!(first.starts_with("get /v1/models "), "{first}");
+        assert!(first.contains("authorization: bearer sk-test"), "{first}");
+        assert!(!first.contains("chatgpt-account-id"), "{first}");
+    }
+
     #[tokio::test]
     async fn fetch_quota_includes_url_when_json_decode_fails() {
         let listener = TcpListener::bind("127.0.0.1:0").unwrap();
@@ -763,12 +1237,55 @@ mod tests {
 
         let quota = fetch_quota_with_client(&client, &auth, &url).await.unwrap();
         assert_eq!(quota.plan_type, "team");
-        assert_eq!(quota.used_requests, Some(19));
-        assert_eq!(quota.reset_date.as_deref(), Some("2025-01-01T00:00:00Z"));
+        assert_eq!(quota.primary_used_percent(), Some(19));
+        assert_eq!(quota.primary_resets_at(), Some("2025-01-01T00:00:00Z"));
 
         server.join().unwrap();
     }
 
+    #[test]
+    fn quota_info_deserializes_legacy_flat_fields() {
+        let json = r#"{"account_id":"acct_123","email":"a@b.c","plan_type":"pro","used_requests":40,"total_requests":100,"used_tokens":7,"total_tokens":100,"reset_date":"2026-01-13T00:00:00Z","secondary_reset_date":null}"#;
+
+        let quota: QuotaInfo = serde_json::from_str(json).unwrap();
+
+        assert_eq!(quota.primary_used_percent(), Some(40));
+        assert_eq!(quota.primary_resets_at(), Some("2026-01-13T00:00:00Z"));
+        assert_eq!(quota.secondary_used_percent(), Some(7));
+        assert!(quota.secondary_resets_at().is_none());
+        assert!(quota.usage.is_none());
+    }
+
+    #[test]
+    fn quota_info_round_trips_with_legacy_fields() {
+        let quota = QuotaInfo {
+            account_id: "acct_123".to_string(),
+            email: "a@b.c".to_string(),
+            plan_type: "plus".to_string(),
+            primary: Some(RateLimitWindow {
+                used_percent: 12.5,
+                window_seconds: Some(18000),
+                resets_at: Some("2026-01-13T00:00:00Z".to_string()),
+            }),
+            secondary: None,
+            credits: Some(CreditsInfo {
+                has_credits: true,
+                unlimited: true,
+                balance: None,
+            }),
+            usage: None,
+        };
+
+        let value = serde_json::to_value(&quota).unwrap();
+        assert_eq!(value["used_requests"], 13);
+        assert_eq!(value["total_requests"], 100);
+        assert_eq!(value["reset_date"], "2026-01-13T00:00:00Z");
+        assert_eq!(value["primary_window"]["window_seconds"], 18000);
+
+        let parsed: QuotaInfo = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, quota);
+    }
+
     #[test]
     fn calculates_reset_time_from_reset_after_seconds() {
         let reset_at = Some(CodexResetAt::EpochSeconds(1000));
@@ -1,18 +1,41 @@
+use std::path::PathBuf;
 use std::sync::mpsc::{Receiver, Sender};
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, Utc};
 use eframe::egui;
+use egui_plot::{Legend, Line, Plot, PlotPoints};
 
-use crate::app_state::{AppCommand, AppEvent, AppState};
+use crate::admin;
+use crate::api::{CreditsInfo, RateLimitWindow, Workspace};
+use crate::app_state::{
+    AppCommand, AppEvent, AppState, OfficialAuthChange, ProfileMetaDraft, ProfileRenameDraft,
+    WorkspacePicker,
+};
+use crate::auth::AuthKind;
+use crate::auth_watch;
+use crate::client_keys;
+use crate::forecast::{PoolForecast, ProfileForecast};
+use crate::history::QuotaSample;
+use crate::profile::{AuthMatch, ProfileHealth, ProfileSummary};
+use crate::profile_meta::{format_hex_color, parse_tags};
+use crate::rate_limit::RateLimits;
 use crate::refresh::RefreshSchedule;
+use crate::request_log::PromptLogging;
+use crate::secret_store::{Passphrase, StorageMode};
+use crate::server::{self, BindConfig, BindOverride, ServerHandle, ServerStatus};
 use crate::shared::SharedState;
 use crate::state::{self, RouterState};
+use crate::tls::TlsSettings;
+use crate::trash::TRASH_RETENTION_DAYS;
 use crate::tray::{self, TrayEvent, TrayHandle};
 use crate::worker;
 use std::sync::Arc;
 
+/// Initial swatch when a color is first assigned to a profile
+const DEFAULT_PROFILE_COLOR: [u8; 3] = [100, 149, 237];
+
 pub struct RouterApp {
     state: AppState,
     router_state: RouterState,
@@ -24,33 +47,33 @@ pub struct RouterApp {
     tray_handle: Option<TrayHandle>,
     worker_handle: Option<JoinHandle<()>>,
     shared_state: Arc<SharedState>,
+    bind_override: BindOverride,
+    server: Option<ServerHandle>,
 }
 
 impl RouterApp {
-    pub fn new() -> Self {
+    pub fn new(bind_override: BindOverride) -> Self {
         let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
         let (evt_tx, evt_rx) = std::sync::mpsc::channel();
         let (tray_tx, tray_rx) = std::sync::mpsc::channel();
-        let worker_handle = worker::start_worker(cmd_rx, evt_tx);
+        let shared_state = Arc::new(SharedState::new());
+        let (worker_evt_tx, worker_evt_rx) = std::sync::mpsc::channel();
+        let worker_handle = worker::start_worker(cmd_rx, worker_evt_tx);
+        // Admin API requests see the worker's events on their way to the UI
+        shared_state
+            .admin
+            .clone()
+            .relay_events(worker_evt_rx, evt_tx);
+        shared_state.admin.connect(cmd_tx.clone());
         let tray_handle = if cfg!(test) {
             None
         } else {
             let tx = tray_tx.clone();
             crate::dock::start_dock_observer(tx);
+            let _ = auth_watch::start_official_auth_watcher(cmd_tx.clone());
             Some(tray::start_tray(tray_tx))
         };
 
-        let shared_state = Arc::new(SharedState::new());
-
-        // Start API server
-        #[cfg(not(test))]
-        {
-            let server_state = shared_state.clone();
-            tokio::spawn(async move {
-                crate::server::start_server(server_state).await;
-            });
-        }
-
         let mut state = AppState::default();
         let router_state = match state::load_state() {
             Ok(router_state) => {
@@ -62,8 +85,32 @@ impl RouterApp {
                 RouterState::default()
             }
         };
+        shared_state.set_allow_credit_spending(state.allow_credit_spending);
+        shared_state.set_allow_api_key_overflow(state.allow_api_key_overflow);
+        shared_state.set_client_keys(router_state.client_keys.clone());
+        shared_state.set_ip_rate_limits(router_state.ip_rate_limits);
+        shared_state.set_cors_origins(router_state.cors_origins.clone());
+        shared_state.set_prompt_logging(router_state.prompt_logging);
+        if let Err(err) = crate::config::get_request_log_file()
+            .and_then(|path| shared_state.request_log.attach_file(path))
+        {
+            state.error = Some(format!("Failed to load the request log: {err}"));
+        }
+        if router_state.admin_api_enabled {
+            if let Err(err) = load_admin_token(&shared_state, false) {
+                state.error = Some(format!("Failed to load the admin token: {err}"));
+            }
+        }
         let _ = cmd_tx.send(AppCommand::LoadProfiles);
 
+        // Start API server
+        let server = if cfg!(test) {
+            None
+        } else {
+            let config = BindConfig::resolve(&router_state, &bind_override);
+            Some(server::spawn_server(shared_state.clone(), config))
+        };
+
         Self {
             state,
             router_state,
@@ -75,9 +122,627 @@ impl RouterApp {
             tray_handle,
             worker_handle: Some(worker_handle),
             shared_state,
+            bind_override,
+            server,
+        }
+    }
+
+    /// Listening address and bind errors of the API server, with host/port settings
+    fn show_server_settings(&mut self, ui: &mut egui::Ui) {
+        let Some(server) = &self.server else {
+            return;
+        };
+        let config = server.config().clone();
+        let status = server.status();
+
+        ui.horizontal(|ui| {
+            ui.label("API Server");
+            match &status {
+                ServerStatus::Starting => {
+                    ui.label(format!("Starting on {config}…"));
+                    ui.ctx().request_repaint_after(Duration::from_millis(200));
+                }
+                ServerStatus::Listening(addr) => {
+                    ui.label(format!("Listening on {}://{addr}/v1", config.scheme()));
+                    if let Some(path) = &config.unix_socket {
+                        ui.label(format!("and unix:{}", path.display()));
+                    }
+                }
+                ServerStatus::ListeningOnSocket(path) => {
+                    ui.label(format!("Listening on unix:{} only", path.display()));
+                }
+                ServerStatus::Failed(err) => {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
+            }
+        });
+        if config.tcp_enabled && !config.is_loopback() {
+            ui.colored_label(
+                egui::Color32::from_rgb(255, 165, 0),
+                "⚠ Reachable from other machines: anyone on the network can use your accounts.",
+            );
+        }
+        if !self.bind_override.is_empty() {
+            ui.weak("Address overridden by --host/--port or CODEX_ROUTER_HOST/CODEX_ROUTER_PORT.");
+        }
+        if let Some(fingerprint) = server.tls_fingerprint() {
+            let fingerprint = fingerprint.to_string();
+            ui.horizontal(|ui| {
+                ui.label("Certificate SHA-256");
+                ui.monospace(&fingerprint);
+                if ui.small_button("Copy").clicked() {
+                    ui.ctx().copy_text(fingerprint.clone());
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.state.tcp_enabled_input, "TCP");
+            ui.add_enabled_ui(self.state.tcp_enabled_input, |ui| {
+                ui.label("Host");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.state.server_host_input)
+                        .desired_width(120.0),
+                );
+                ui.label("Port");
+                ui.add(egui::DragValue::new(&mut self.state.server_port_input).range(1..=65535));
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(self.state.tcp_enabled_input, |ui| {
+                ui.checkbox(&mut self.state.tls_enabled_input, "HTTPS");
+            });
+            ui.add_enabled_ui(
+                self.state.tcp_enabled_input && self.state.tls_enabled_input,
+                |ui| {
+                    ui.label("Cert");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.state.tls_cert_path_input)
+                            .hint_text("self-signed")
+                            .desired_width(140.0),
+                    );
+                    ui.label("Key");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.state.tls_key_path_input)
+                            .hint_text("self-signed")
+                            .desired_width(140.0),
+                    );
+                },
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.state.unix_socket_enabled_input, "Unix socket")
+                .on_hover_text("Only processes of your user can connect");
+            ui.add_enabled(
+                self.state.unix_socket_enabled_input,
+                egui::TextEdit::singleline(&mut self.state.unix_socket_path_input)
+                    .hint_text("~/.codex/router/api.sock")
+                    .desired_width(220.0),
+            );
+        });
+
+        let input = ServerSettingsInput::read(&self.state);
+        let failed = matches!(status, ServerStatus::Failed(_));
+        let apply = ui
+            .add_enabled(
+                input.is_valid() && (input.differs_from(&self.router_state) || failed),
+                egui::Button::new("Apply & Restart"),
+            )
+            .clicked();
+        if apply {
+            if input.apply(&mut self.router_state) {
+                self.persist_router_state();
+            }
+            let config = BindConfig::resolve(&self.router_state, &self.bind_override);
+            self.server = self
+                .server
+                .take()
+                .map(|server| server.restart(self.shared_state.clone(), config));
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Browser origins");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.state.cors_origins_input)
+                    .hint_text("none")
+                    .desired_width(220.0),
+            )
+            .on_hover_text("Web pages allowed to call the API, e.g. https://app.example.com");
+            if ui.button("Save").clicked() {
+                match update_cors_origins(&mut self.router_state, &self.state.cors_origins_input) {
+                    Ok(changed) => {
+                        self.state.cors_origins_input = self.router_state.cors_origins.join(", ");
+                        if changed {
+                            self.persist_router_state();
+                            self.shared_state
+                                .set_cors_origins(self.router_state.cors_origins.clone());
+                        }
+                    }
+                    Err(err) => self.state.error = Some(err.to_string()),
+                }
+            }
+        });
+    }
+
+    /// Token-authenticated REST API for scripts, served on the API server's addresses
+    fn show_admin_api(&mut self, ui: &mut egui::Ui) {
+        let mut enabled = self.router_state.admin_api_enabled;
+        let mut result = None;
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut enabled, "Admin API")
+                .on_hover_text(
+                    "Local /admin/ endpoints, authenticated with the token in \
+                     ~/.codex/router/admin.token",
+                )
+                .changed()
+            {
+                result = Some(if enabled {
+                    load_admin_token(&self.shared_state, false).map(|_| ())
+                } else {
+                    self.shared_state.admin.set_token(None);
+                    Ok(())
+                });
+            }
+            if enabled {
+                if ui.button("Copy Token").clicked() {
+                    result = Some(
+                        load_admin_token(&self.shared_state, false)
+                            .map(|token| ui.ctx().copy_text(token)),
+                    );
+                }
+                if ui.button("Rotate Token").clicked() {
+                    result = Some(load_admin_token(&self.shared_state, true).map(|_| ()));
+                }
+            }
+        });
+
+        match result {
+            Some(Ok(())) if enabled != self.router_state.admin_api_enabled => {
+                self.router_state.admin_api_enabled = enabled;
+                self.persist_router_state();
+            }
+            Some(Err(err)) => self.state.error = Some(err.to_string()),
+            _ => {}
+        }
+    }
+
+    /// Recent proxied requests, newest first, filtered by the search field
+    fn show_request_log(&mut self, ui: &mut egui::Ui) {
+        let entries = self.shared_state.request_log.recent();
+        let mut prompt_logging = self.router_state.prompt_logging;
+
+        egui::CollapsingHeader::new(format!("Request Log ({})", entries.len())).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Search");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.state.request_log_filter)
+                        .hint_text("client, model, profile, status")
+                        .desired_width(200.0),
+                );
+                ui.label("Prompts");
+                egui::ComboBox::from_id_salt("prompt_logging")
+                    .selected_text(prompt_logging.label())
+                    .show_ui(ui, |ui| {
+                        for option in PromptLogging::ALL {
+                            ui.selectable_value(&mut prompt_logging, option, option.label());
+                        }
+                    });
+            });
+
+            let query = self.state.request_log_filter.trim();
+            egui::ScrollArea::vertical()
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("request_log")
+                        .striped(true)
+                        .num_columns(8)
+                        .show(ui, |ui| {
+                            for title in [
+                                "Time", "Client", "Model", "Profile", "Tries", "Status", "Latency",
+                                "Tokens",
+                            ] {
+                                ui.strong(title);
+                            }
+                            ui.end_row();
+                            for entry in entries.iter().rev().filter(|entry| entry.matches(query)) {
+                                let time = ui.label(
+                                    entry
+                                        .timestamp
+                                        .with_timezone(&Local)
+                                        .format("%m-%d %H:%M:%S")
+                                        .to_string(),
+                                );
+                                let details: Vec<&str> = [&entry.error, &entry.prompt]
+                                    .into_iter()
+                                    .flatten()
+                                    .map(String::as_str)
+                                    .collect();
+                                if !details.is_empty() {
+                                    time.on_hover_text(details.join("\n\n"));
+                                }
+                                ui.label(&entry.client);
+                                ui.label(entry.model.as_deref().unwrap_or("-"));
+                                ui.label(entry.profile.as_deref().unwrap_or("-"));
+                                ui.label(entry.attempts.to_string());
+                                let status = entry.status.to_string();
+                                if entry.status < 400 {
+                                    ui.label(status);
+                                } else {
+                                    ui.colored_label(ui.visuals().error_fg_color, status);
+                                }
+                                ui.label(format!("{} ms", entry.latency_ms));
+                                ui.label(
+                                    entry
+                                        .usage
+                                        .map(|usage| {
+                                            format!(
+                                                "{} / {}",
+                                                usage.input_tokens, usage.output_tokens
+                                            )
+                                        })
+                                        .unwrap_or_else(|| "-".to_string()),
+                                );
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+
+        if prompt_logging != self.router_state.prompt_logging {
+            self.router_state.prompt_logging = prompt_logging;
+            self.persist_router_state();
+            self.shared_state.set_prompt_logging(prompt_logging);
+        }
+    }
+
+    /// Keys clients must send as `Authorization: Bearer`, with usage since startup
+    fn show_client_keys(&mut self, ui: &mut egui::Ui) {
+        let usage = self.shared_state.client_key_usage();
+        let mut revoke = None;
+        let mut limits_changed = false;
+
+        egui::CollapsingHeader::new(format!(
+            "Client API Keys ({})",
+            self.router_state
+                .client_keys
+                .iter()
+                .filter(|key| key.is_active())
+                .count()
+        ))
+        .show(ui, |ui| {
+            if self.router_state.client_keys.is_empty() {
+                ui.weak("No keys yet: any client that can reach the server may use it.");
+                ui.horizontal(|ui| {
+                    ui.label("Limits per IP");
+                    limits_changed |= edit_rate_limits(ui, &mut self.router_state.ip_rate_limits);
+                });
+            }
+            for key in &mut self.router_state.client_keys {
+                ui.horizontal(|ui| {
+                    ui.strong(&key.label);
+                    ui.monospace(format!("{}…", key.display_prefix));
+                    ui.label(format!("created {}", format_local_time(key.created_at)));
+                    let key_usage = usage.get(&key.id).cloned().unwrap_or_default();
+                    ui.label(format!(
+                        "{} requests, {} failed",
+                        key_usage.requests, key_usage.failed_requests
+                    ));
+                    if let Some(last_used_at) = key_usage.last_used_at {
+                        ui.label(format!("last used {}", format_local_time(last_used_at)));
+                    }
+                    match key.revoked_at {
+                        Some(revoked_at) => {
+                            ui.weak(format!("revoked {}", format_local_time(revoked_at)));
+                        }
+                        None => {
+                            if ui.button("Revoke").clicked() {
+                                revoke = Some(key.id.clone());
+                            }
+                        }
+                    }
+                });
+                ui.indent(key.id.clone(), |ui| {
+                    if !key.allowed_models.is_empty() {
+                        ui.weak(format!("models: {}", key.allowed_models.join(", ")));
+                    }
+                    if !key.pool_tags.is_empty() {
+                        ui.weak(format!("profiles tagged: {}", key.pool_tags.join(", ")));
+                    }
+                    if key.is_active() {
+                        ui.horizontal(|ui| {
+                            ui.label("Limits");
+                            limits_changed |= edit_rate_limits(ui, &mut key.limits);
+                        });
+                    }
+                });
+            }
+
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.label("Label");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.state.client_key_label_input)
+                        .desired_width(100.0),
+                );
+                ui.label("Models");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.state.client_key_models_input)
+                        .hint_text("all")
+                        .desired_width(120.0),
+                );
+                ui.label("Profile tags");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.state.client_key_tags_input)
+                        .hint_text("all")
+                        .desired_width(100.0),
+                );
+                let label = self.state.client_key_label_input.trim();
+                if ui
+                    .add_enabled(!label.is_empty(), egui::Button::new("Create Key"))
+                    .clicked()
+                {
+                    let key = create_client_key(
+                        &mut self.router_state,
+                        label,
+                        &self.state.client_key_models_input,
+                        &self.state.client_key_tags_input,
+                    );
+                    self.state.new_client_key = Some(key);
+                    self.state.client_key_label_input.clear();
+                    self.state.client_key_models_input.clear();
+                    self.state.client_key_tags_input.clear();
+                    self.persist_router_state();
+                    self.shared_state
+                        .set_client_keys(self.router_state.client_keys.clone());
+                }
+            });
+
+            if let Some(key) = &self.state.new_client_key {
+                let mut dismiss = false;
+                ui.group(|ui| {
+                    ui.label("Copy the key now; it is not shown again.");
+                    ui.horizontal(|ui| {
+                        ui.monospace(key);
+                        if ui.button("Copy").clicked() {
+                            ui.ctx().copy_text(key.clone());
+                        }
+                        dismiss = ui.button("Dismiss").clicked();
+                    });
+                });
+                if dismiss {
+                    self.state.new_client_key = None;
+                }
+            }
+        });
+
+        if let Some(id) = revoke {
+            if revoke_client_key(&mut self.router_state, &id, Utc::now()) {
+                self.persist_router_state();
+                self.shared_state
+                    .set_client_keys(self.router_state.client_keys.clone());
+            }
+        }
+        if limits_changed {
+            self.persist_router_state();
+            self.shared_state
+                .set_client_keys(self.router_state.client_keys.clone());
+            self.shared_state
+                .set_ip_rate_limits(self.router_state.ip_rate_limits);
+        }
+    }
+
+    fn show_official_auth_change(&mut self, ui: &mut egui::Ui) {
+        let Some(change) = &self.state.official_auth_change else {
+            return;
+        };
+        let message = official_auth_change_message(change);
+
+        ui.group(|ui| {
+            ui.set_min_width(ui.available_width());
+            ui.label(message);
+            ui.horizontal(|ui| {
+                if ui.button("Import as Profile").clicked() {
+                    let _ = self.cmd_tx.send(AppCommand::ImportOfficialAuth);
+                }
+                if ui.button("Dismiss").clicked() {
+                    self.state.official_auth_change = None;
+                }
+            });
+        });
+    }
+
+    fn show_credential_storage(&mut self, ui: &mut egui::Ui) {
+        let Some(status) = self.state.vault_status else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Credentials");
+            ui.label(match (status.mode, status.unlocked) {
+                (StorageMode::Plaintext, _) => "Plaintext",
+                (StorageMode::Vault, true) => "Encrypted (unlocked)",
+                (StorageMode::Vault, false) => "Encrypted (locked)",
+            });
+        });
+
+        ui.horizontal(|ui| {
+            if status.mode == StorageMode::Vault && status.unlocked {
+                if ui.button("Lock").clicked() {
+                    let _ = self.cmd_tx.send(AppCommand::LockVault);
+                }
+                if ui.button("Decrypt Profiles").clicked() {
+                    let _ = self.cmd_tx.send(AppCommand::DisableVault);
+                }
+                return;
+            }
+
+            ui.label("Passphrase");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.state.vault_passphrase_input).password(true),
+            );
+            let has_input = !self.state.vault_passphrase_input.is_empty();
+            let label = match status.mode {
+                StorageMode::Plaintext => "Encrypt Profiles",
+                StorageMode::Vault => "Unlock",
+            };
+            if ui
+                .add_enabled(has_input, egui::Button::new(label))
+                .clicked()
+            {
+                let passphrase =
+                    Passphrase::new(std::mem::take(&mut self.state.vault_passphrase_input));
+                let command = match status.mode {
+                    StorageMode::Plaintext => AppCommand::EnableVault(passphrase),
+                    StorageMode::Vault => AppCommand::UnlockVault(passphrase),
+                };
+                let _ = self.cmd_tx.send(command);
+            }
+        });
+
+        if let Some(message) = &self.state.vault_message {
+            ui.label(message);
+        }
+    }
+
+    /// Export selected profiles to, or import them from, an encrypted bundle file
+    fn show_profile_transfer(&mut self, ui: &mut egui::Ui) {
+        ui.label("Transfer Profiles");
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Export:");
+            for profile in &self.state.profiles {
+                let mut selected = self.state.export_selection.contains(&profile.name);
+                if ui
+                    .checkbox(&mut selected, profile.meta.label(&profile.name))
+                    .changed()
+                {
+                    if selected {
+                        self.state.export_selection.insert(profile.name.clone());
+                    } else {
+                        self.state.export_selection.remove(&profile.name);
+                    }
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Bundle file");
+            ui.text_edit_singleline(&mut self.state.bundle_path_input);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Passphrase");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.state.bundle_passphrase_input).password(true),
+            );
+        });
+
+        let path = self.state.bundle_path_input.trim();
+        let ready = !path.is_empty() && !self.state.bundle_passphrase_input.is_empty();
+        ui.horizontal(|ui| {
+            let can_export = ready && !self.state.export_selection.is_empty();
+            if ui
+                .add_enabled(can_export, egui::Button::new("Export"))
+                .clicked()
+            {
+                let _ = self.cmd_tx.send(AppCommand::ExportProfiles {
+                    names: self.state.export_selection.iter().cloned().collect(),
+                    path: PathBuf::from(path),
+                    passphrase: Passphrase::new(self.state.bundle_passphrase_input.clone()),
+                });
+            }
+            if ui.add_enabled(ready, egui::Button::new("Import")).clicked() {
+                let _ = self.cmd_tx.send(AppCommand::ImportProfiles {
+                    path: PathBuf::from(path),
+                    passphrase: Passphrase::new(self.state.bundle_passphrase_input.clone()),
+                });
+            }
+        });
+
+        if let Some(message) = &self.state.bundle_message {
+            ui.label(message);
         }
     }
 
+    fn show_delete_confirm(&mut self, ctx: &egui::Context) {
+        let Some(name) = self.state.delete_confirm.clone() else {
+            return;
+        };
+        let label = self
+            .state
+            .profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .map_or(name.as_str(), |profile| profile.meta.label(&profile.name));
+
+        let modal = egui::Modal::new(egui::Id::new("delete_profile_confirm")).show(ctx, |ui| {
+            ui.heading("Delete profile?");
+            ui.label(format!(
+                "{label} will be moved to the trash and permanently deleted after {TRASH_RETENTION_DAYS} days."
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Delete").clicked() {
+                    let _ = self.cmd_tx.send(AppCommand::DeleteProfile(name.clone()));
+                    ui.close();
+                }
+                if ui.button("Cancel").clicked() {
+                    ui.close();
+                }
+            });
+        });
+        if modal.should_close() {
+            self.state.delete_confirm = None;
+        }
+    }
+
+    /// Toast offering to undo the most recent deletion
+    fn show_undo_delete(&mut self, ctx: &egui::Context) {
+        let Some(entry) = self.state.undo_delete.clone() else {
+            return;
+        };
+
+        egui::Area::new(egui::Id::new("undo_delete_toast"))
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -16.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Deleted {}", entry.profile_name));
+                        if ui.button("Undo").clicked() {
+                            let _ = self
+                                .cmd_tx
+                                .send(AppCommand::RestoreProfile(entry.id.clone()));
+                            self.state.undo_delete = None;
+                        }
+                    });
+                });
+            });
+        ctx.request_repaint_after(Duration::from_secs(1));
+    }
+
+    fn show_trash(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(format!("Trash ({})", self.state.trash.len())).show(ui, |ui| {
+            for entry in &self.state.trash {
+                ui.horizontal(|ui| {
+                    ui.label(&entry.profile_name);
+                    ui.label(format!(
+                        "deleted {}, purged {}",
+                        format_local_time(entry.deleted_at),
+                        format_local_time(entry.expires_at())
+                    ));
+                    if ui.button("Restore").clicked() {
+                        let _ = self
+                            .cmd_tx
+                            .send(AppCommand::RestoreProfile(entry.id.clone()));
+                    }
+                    if ui.button("Delete Forever").clicked() {
+                        let _ = self
+                            .cmd_tx
+                            .send(AppCommand::PurgeTrashEntry(entry.id.clone()));
+                    }
+                });
+            }
+        });
+    }
+
     fn persist_router_state(&mut self) {
         if let Err(err) = state::save_state(&self.router_state) {
             self.state.error = Some(err.to_string());
@@ -85,6 +750,12 @@ impl RouterApp {
     }
 }
 
+fn has_duplicate_profiles(profiles: &[ProfileSummary]) -> bool {
+    profiles
+        .iter()
+        .any(|profile| matches!(profile.health, ProfileHealth::DuplicateOf(_)))
+}
+
 fn command_for_tray_event(event: &TrayEvent) -> Option<AppCommand> {
     match event {
         TrayEvent::SwitchProfile(name) => Some(AppCommand::SwitchProfile(name.clone())),
@@ -132,9 +803,475 @@ fn format_reset_time(utc_str: Option<&str>) -> String {
     local_time.format("%Y-%m-%d %H:%M (local)").to_string()
 }
 
+fn format_window_usage(window: Option<&RateLimitWindow>) -> String {
+    let Some(window) = window else {
+        return "-".to_string();
+    };
+    let usage = format!(
+        "{}% used ({}% left)",
+        window.used_percent_rounded(),
+        window.remaining_percent()
+    );
+    match window.window_seconds.and_then(format_window_length) {
+        Some(length) => format!("{usage} / {length}"),
+        None => usage,
+    }
+}
+
+fn format_window_length(seconds: i64) -> Option<String> {
+    const HOUR: i64 = 3600;
+    const DAY: i64 = 24 * HOUR;
+    match seconds {
+        s if s <= 0 => None,
+        s if s % (7 * DAY) == 0 => Some(format!("{}w", s / (7 * DAY))),
+        s if s % DAY == 0 => Some(format!("{}d", s / DAY)),
+        s if s % HOUR == 0 => Some(format!("{}h", s / HOUR)),
+        s => Some(format!("{}m", s / 60)),
+    }
+}
+
+fn format_credits(credits: &CreditsInfo) -> String {
+    if credits.unlimited {
+        return "Unlimited".to_string();
+    }
+    match &credits.balance {
+        Some(balance) => balance.clone(),
+        None if credits.has_credits => "Available".to_string(),
+        None => "None".to_string(),
+    }
+}
+
+/// API keys have no rate-limit windows; show what the router has sent through them instead
+fn show_api_key_usage(ui: &mut egui::Ui, profile: &ProfileSummary, requests_24h: usize) {
+    egui::Grid::new(format!("quota_grid_{}", profile.name))
+        .num_columns(2)
+        .spacing([10.0, 2.0])
+        .show(ui, |ui| {
+            ui.label("Plan");
+            ui.label("API key (pay-as-you-go)");
+            ui.end_row();
+
+            ui.label("Requests (24h)");
+            ui.label(requests_24h.to_string());
+            ui.end_row();
+
+            ui.label("Key checked");
+            ui.label(format_quota_fetched_at(profile.quota_fetched_at));
+            ui.end_row();
+        });
+}
+
+fn format_quota_fetched_at(fetched_at: Option<DateTime<Utc>>) -> String {
+    fetched_at.map_or_else(|| "-".to_string(), format_local_time)
+}
+
+/// Load the admin token, or replace it when `rotate` is set, and enable the admin API
+fn load_admin_token(shared_state: &SharedState, rotate: bool) -> anyhow::Result<String> {
+    let path = crate::config::get_admin_token_file()?;
+    let token = if rotate {
+        admin::rotate_token(&path)?
+    } else {
+        admin::load_or_create_token(&path)?
+    };
+    shared_state.admin.set_token(Some(&token));
+    Ok(token)
+}
+
+fn format_local_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M (local)")
+        .to_string()
+}
+
+enum WorkspacePickerAction {
+    Add(Workspace),
+    Close,
+}
+
+/// Workspaces reachable with a profile's login; ones that already have a profile can't be added again
+fn show_workspace_picker(
+    ui: &mut egui::Ui,
+    picker: &WorkspacePicker,
+    linked_account_ids: &[&str],
+) -> Option<WorkspacePickerAction> {
+    let mut action = None;
+    ui.group(|ui| {
+        match &picker.workspaces {
+            None => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Loading workspaces...");
+                });
+            }
+            Some(workspaces) if workspaces.is_empty() => {
+                ui.weak("No workspaces found for this login.");
+            }
+            Some(workspaces) => {
+                egui::Grid::new(format!("workspaces_{}", picker.profile_name))
+                    .num_columns(3)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        for workspace in workspaces {
+                            ui.label(workspace.label());
+                            ui.weak(workspace.plan_type.as_deref().unwrap_or("-"));
+                            let account_id = workspace.account_id.as_str();
+                            if picker.own_account_id.as_deref() == Some(account_id) {
+                                ui.weak("this profile");
+                            } else if linked_account_ids.contains(&account_id) {
+                                ui.weak("added");
+                            } else if ui.button("Add").clicked() {
+                                action = Some(WorkspacePickerAction::Add(workspace.clone()));
+                            }
+                            ui.end_row();
+                        }
+                    });
+            }
+        }
+        if ui.button("Close").clicked() {
+            action = Some(WorkspacePickerAction::Close);
+        }
+    });
+    action
+}
+
+enum MetaEditorAction {
+    Save,
+    Cancel,
+}
+
+/// Inline form for a profile's display name, color, tags, notes and routing settings
+fn show_profile_meta_editor(
+    ui: &mut egui::Ui,
+    draft: &mut ProfileMetaDraft,
+) -> Option<MetaEditorAction> {
+    egui::Grid::new(format!("meta_editor_{}", draft.profile_name))
+        .num_columns(2)
+        .spacing([10.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Display name");
+            ui.add(
+                egui::TextEdit::singleline(&mut draft.display_name_input)
+                    .hint_text(draft.profile_name.as_str()),
+            );
+            ui.end_row();
+
+            ui.label("Color");
+            ui.horizontal(|ui| {
+                let mut has_color = draft.meta.color.is_some();
+                ui.checkbox(&mut has_color, "");
+                if has_color {
+                    let mut rgb = draft.meta.color_rgb().unwrap_or(DEFAULT_PROFILE_COLOR);
+                    ui.color_edit_button_srgb(&mut rgb);
+                    draft.meta.color = Some(format_hex_color(rgb));
+                } else {
+                    draft.meta.color = None;
+                }
+            });
+            ui.end_row();
+
+            ui.label("Tags");
+            ui.add(egui::TextEdit::singleline(&mut draft.tags_input).hint_text("comma separated"));
+            ui.end_row();
+
+            ui.label("Notes");
+            ui.add(egui::TextEdit::multiline(&mut draft.meta.notes).desired_rows(2));
+            ui.end_row();
+
+            ui.label("Priority");
+            ui.add(egui::DragValue::new(&mut draft.meta.priority_weight).range(0..=100))
+                .on_hover_text("Profiles with higher weights are routed to first");
+            ui.end_row();
+
+            ui.label("Routing");
+            ui.checkbox(&mut draft.meta.enabled, "Enabled");
+            ui.end_row();
+
+            ui.label("Created");
+            ui.label(
+                draft
+                    .meta
+                    .created_at
+                    .map_or_else(|| "-".to_string(), format_local_time),
+            );
+            ui.end_row();
+
+            ui.label("Last used");
+            ui.label(
+                draft
+                    .meta
+                    .last_used_at
+                    .map_or_else(|| "-".to_string(), format_local_time),
+            );
+            ui.end_row();
+        });
+
+    let mut action = None;
+    ui.horizontal(|ui| {
+        if ui.button("Save").clicked() {
+            action = Some(MetaEditorAction::Save);
+        }
+        if ui.button("Cancel").clicked() {
+            action = Some(MetaEditorAction::Cancel);
+        }
+    });
+    action
+}
+
+fn format_profile_forecast(forecast: Option<&ProfileForecast>) -> String {
+    let Some(forecast) = forecast else {
+        return "-".to_string();
+    };
+    if let Some(at) = forecast.exhausts_at() {
+        return format!("Runs out ~{}", format_local_time(at));
+    }
+    if forecast.survives_until_reset() {
+        let rate = forecast
+            .primary_rate
+            .into_iter()
+            .chain(forecast.secondary_rate)
+            .fold(0.0_f64, f64::max);
+        return format!("Lasts until reset ({rate:.1}%/h)");
+    }
+    "Not enough history yet".to_string()
+}
+
+fn format_pool_forecast(pool: &PoolForecast) -> String {
+    match (pool.survives_until_reset, pool.exhausts_at) {
+        (true, _) => match pool.next_reset {
+            Some(reset) => format!("Pool lasts until next reset ({})", format_local_time(reset)),
+            None => "Pool lasts until next reset".to_string(),
+        },
+        (false, Some(at)) => format!(
+            "Pool runs out ~{} before the next reset",
+            format_local_time(at)
+        ),
+        (false, None) => "Pool may run out before the next reset".to_string(),
+    }
+}
+
+/// Convert samples into (hours relative to now, percent used) plot points
+fn history_points(
+    samples: &[QuotaSample],
+    now: DateTime<Utc>,
+    used: impl Fn(&QuotaSample) -> Option<u64>,
+) -> Vec<[f64; 2]> {
+    samples
+        .iter()
+        .filter_map(|sample| {
+            let used = used(sample)?;
+            let hours = (sample.timestamp - now).num_seconds() as f64 / 3600.0;
+            Some([hours, used.min(100) as f64])
+        })
+        .collect()
+}
+
+fn show_quota_history(ui: &mut egui::Ui, profile_name: &str, samples: &[QuotaSample]) {
+    let now = Utc::now();
+    let primary = history_points(samples, now, |sample| sample.primary_used);
+    let secondary = history_points(samples, now, |sample| sample.secondary_used);
+
+    Plot::new(format!("quota_history_{profile_name}"))
+        .height(140.0)
+        .include_y(0.0)
+        .include_y(100.0)
+        .x_axis_label("hours")
+        .y_axis_label("% used")
+        .legend(Legend::default())
+        .allow_scroll(false)
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new("Primary", PlotPoints::from(primary)));
+            plot_ui.line(Line::new("Secondary", PlotPoints::from(secondary)));
+        });
+}
+
 fn apply_router_state(app_state: &mut AppState, router_state: &RouterState) {
     app_state.refresh_interval_seconds = router_state.refresh_interval_seconds;
     app_state.auto_refresh_enabled = router_state.auto_refresh_enabled;
+    app_state.allow_credit_spending = router_state.allow_credit_spending;
+    app_state.allow_api_key_overflow = router_state.allow_api_key_overflow;
+    app_state.server_host_input = router_state.server_host.clone();
+    app_state.server_port_input = router_state.server_port;
+    app_state.cors_origins_input = router_state.cors_origins.join(", ");
+    app_state.tls_enabled_input = router_state.tls.enabled;
+    app_state.tls_cert_path_input = path_input(router_state.tls.cert_path.as_deref());
+    app_state.tls_key_path_input = path_input(router_state.tls.key_path.as_deref());
+    app_state.tcp_enabled_input = router_state.tcp_enabled;
+    app_state.unix_socket_enabled_input = router_state.unix_socket_enabled;
+    app_state.unix_socket_path_input = path_input(router_state.unix_socket_path.as_deref());
+}
+
+fn path_input(path: Option<&std::path::Path>) -> String {
+    path.map(|path| path.display().to_string())
+        .unwrap_or_default()
+}
+
+fn update_credit_spending(
+    router_state: &mut RouterState,
+    app_state: &mut AppState,
+    allowed: bool,
+) -> bool {
+    app_state.allow_credit_spending = allowed;
+    if router_state.allow_credit_spending == allowed {
+        return false;
+    }
+    router_state.allow_credit_spending = allowed;
+    true
+}
+
+fn update_api_key_overflow(
+    router_state: &mut RouterState,
+    app_state: &mut AppState,
+    allowed: bool,
+) -> bool {
+    app_state.allow_api_key_overflow = allowed;
+    if router_state.allow_api_key_overflow == allowed {
+        return false;
+    }
+    router_state.allow_api_key_overflow = allowed;
+    true
+}
+
+/// Replace the CORS allowlist with a comma-separated list of origins
+fn update_cors_origins(router_state: &mut RouterState, input: &str) -> anyhow::Result<bool> {
+    let mut origins = Vec::new();
+    for entry in parse_tags(input) {
+        let origin = server::normalize_cors_origin(&entry).ok_or_else(|| {
+            anyhow::anyhow!("'{entry}' is not an origin like https://app.example.com")
+        })?;
+        if !origins.contains(&origin) {
+            origins.push(origin);
+        }
+    }
+    if router_state.cors_origins == origins {
+        return Ok(false);
+    }
+    router_state.cors_origins = origins;
+    Ok(true)
+}
+
+/// API server settings as entered in the "API Server" section
+#[derive(Debug, Clone, PartialEq)]
+struct ServerSettingsInput {
+    host: String,
+    port: u16,
+    tls: TlsSettings,
+    tcp_enabled: bool,
+    unix_socket_enabled: bool,
+    unix_socket_path: Option<PathBuf>,
+}
+
+impl ServerSettingsInput {
+    /// Blank paths mean the self-signed certificate and the default socket path
+    fn read(app_state: &AppState) -> Self {
+        let path = |input: &str| {
+            Some(input.trim())
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+        };
+        Self {
+            host: app_state.server_host_input.trim().to_string(),
+            port: app_state.server_port_input,
+            tls: TlsSettings {
+                enabled: app_state.tls_enabled_input,
+                cert_path: path(&app_state.tls_cert_path_input),
+                key_path: path(&app_state.tls_key_path_input),
+            },
+            tcp_enabled: app_state.tcp_enabled_input,
+            unix_socket_enabled: app_state.unix_socket_enabled_input,
+            unix_socket_path: path(&app_state.unix_socket_path_input),
+        }
+    }
+
+    fn from_router_state(router_state: &RouterState) -> Self {
+        Self {
+            host: router_state.server_host.clone(),
+            port: router_state.server_port,
+            tls: router_state.tls.clone(),
+            tcp_enabled: router_state.tcp_enabled,
+            unix_socket_enabled: router_state.unix_socket_enabled,
+            unix_socket_path: router_state.unix_socket_path.clone(),
+        }
+    }
+
+    /// Something to listen on, with a host if TCP is on
+    fn is_valid(&self) -> bool {
+        (self.tcp_enabled && !self.host.is_empty())
+            || (!self.tcp_enabled && self.unix_socket_enabled)
+    }
+
+    fn differs_from(&self, router_state: &RouterState) -> bool {
+        *self != Self::from_router_state(router_state)
+    }
+
+    /// Store the settings, returning whether anything changed
+    fn apply(self, router_state: &mut RouterState) -> bool {
+        if !self.differs_from(router_state) {
+            return false;
+        }
+        router_state.server_host = self.host;
+        router_state.server_port = self.port;
+        router_state.tls = self.tls;
+        router_state.tcp_enabled = self.tcp_enabled;
+        router_state.unix_socket_enabled = self.unix_socket_enabled;
+        router_state.unix_socket_path = self.unix_socket_path;
+        true
+    }
+}
+
+/// Inputs for each limit, where 0 means unlimited. Returns whether any changed.
+fn edit_rate_limits(ui: &mut egui::Ui, limits: &mut RateLimits) -> bool {
+    let fields = [
+        (&mut limits.requests_per_minute, " / min"),
+        (&mut limits.concurrent_requests, " at once"),
+        (&mut limits.daily_requests, " / day"),
+    ];
+    let mut changed = false;
+    for (value, suffix) in fields {
+        changed |= ui
+            .add(egui::DragValue::new(value).suffix(suffix))
+            .on_hover_text("0 means unlimited")
+            .changed();
+    }
+    changed
+}
+
+/// Issue a key and store its hash. Returns the plaintext.
+fn create_client_key(
+    router_state: &mut RouterState,
+    label: &str,
+    models: &str,
+    tags: &str,
+) -> String {
+    let (key, record) =
+        client_keys::issue_key(label, parse_tags(models), parse_tags(tags), Utc::now());
+    router_state.client_keys.push(record);
+    key
+}
+
+fn revoke_client_key(router_state: &mut RouterState, id: &str, now: DateTime<Utc>) -> bool {
+    match router_state
+        .client_keys
+        .iter_mut()
+        .find(|key| key.id == id && key.is_active())
+    {
+        Some(key) => {
+            key.revoked_at = Some(now);
+            true
+        }
+        None => false,
+    }
+}
+
+fn rename_last_selected_profile(
+    router_state: &mut RouterState,
+    old_name: &str,
+    new_name: &str,
+) -> bool {
+    if router_state.last_selected_profile.as_deref() != Some(old_name) {
+        return false;
+    }
+    router_state.last_selected_profile = Some(new_name.to_string());
+    true
 }
 
 fn update_router_state_settings(
@@ -157,6 +1294,19 @@ fn update_router_state_settings(
     changed
 }
 
+fn official_auth_change_message(change: &OfficialAuthChange) -> String {
+    let account = change.email.as_deref().unwrap_or("an unknown account");
+    match &change.status {
+        AuthMatch::New { suggested_name } => format!(
+            "Codex CLI is logged in as {account}, which is not saved yet (suggested name: {suggested_name})."
+        ),
+        AuthMatch::Refreshed { name } => {
+            format!("Codex CLI has newer credentials for profile '{name}' ({account}).")
+        }
+        AuthMatch::Unchanged { name } => format!("Codex CLI is using profile '{name}'."),
+    }
+}
+
 fn auto_refresh_tick(
     enabled: bool,
     schedule: &mut RefreshSchedule,
@@ -181,14 +1331,26 @@ impl eframe::App for RouterApp {
             }
         }
 
+        let mut forecast_inputs_changed = false;
         while let Ok(event) = self.evt_rx.try_recv() {
             let prev_profile = self.state.current_profile.clone();
             let is_profiles_loaded = matches!(event, AppEvent::ProfilesLoaded(_));
-            if let AppEvent::ProfilesLoaded(ref profiles) = event {
-                if let Some(tray_handle) = &self.tray_handle {
-                    tray_handle.update_profiles(profiles);
+            forecast_inputs_changed |= matches!(
+                event,
+                AppEvent::ProfilesLoaded(_)
+                    | AppEvent::ProfileQuotaLoaded { .. }
+                    | AppEvent::ProfileQuotaFailed { .. }
+                    | AppEvent::QuotaHistoryLoaded(_)
+            );
+            if let AppEvent::ProfileRenamed { old_name, new_name } = &event {
+                self.shared_state.rename_profile(old_name, new_name);
+                if rename_last_selected_profile(&mut self.router_state, old_name, new_name) {
+                    self.persist_router_state();
                 }
             }
+            if let AppEvent::TokenRefreshed { success, .. } = &event {
+                self.shared_state.metrics.record_token_refresh(*success);
+            }
             let event_for_shared = event.clone();
             self.state.apply_event(event);
             if matches!(
@@ -196,6 +1358,7 @@ impl eframe::App for RouterApp {
                 AppEvent::ProfilesLoaded(_)
                     | AppEvent::QuotaLoaded(_)
                     | AppEvent::ProfileQuotaLoaded { .. }
+                    | AppEvent::ProfileQuotaFailed { .. }
             ) {
                 self.shared_state
                     .update_profiles(self.state.profiles.clone());
@@ -207,12 +1370,22 @@ impl eframe::App for RouterApp {
                     self.router_state.last_selected_profile = next_profile.clone();
                     self.persist_router_state();
                     if let Some(name) = next_profile {
-                        let _ = self.cmd_tx.send(AppCommand::FetchProfileQuota(name));
+                        let _ = self
+                            .cmd_tx
+                            .send(AppCommand::FetchProfileQuota { name, force: false });
                     }
                 }
             }
         }
 
+        if forecast_inputs_changed {
+            self.state
+                .update_forecasts(&self.shared_state.proxied_requests(), Utc::now());
+            if let Some(tray_handle) = &self.tray_handle {
+                tray_handle.update_profiles(&self.state.profiles, &self.state.forecasts);
+            }
+        }
+
         while let Ok(event) = self.tray_rx.try_recv() {
             if let Some(command) = command_for_tray_event(&event) {
                 let _ = self.cmd_tx.send(command);
@@ -233,6 +1406,7 @@ impl eframe::App for RouterApp {
         egui::CentralPanel::default().show(ctx, |ui| {
             egui::ScrollArea::vertical().show(ui, |ui| {
                 ui.heading("Codex Router");
+                self.show_official_auth_change(ui);
 
                 ui.group(|ui| {
                     ui.set_min_width(ui.available_width());
@@ -285,28 +1459,178 @@ impl eframe::App for RouterApp {
                                 self.quota_refresh.clear();
                             }
                         }
+
+                        let mut allow_credit_spending = self.state.allow_credit_spending;
+                        if ui
+                            .checkbox(&mut allow_credit_spending, "Spend credits")
+                            .on_hover_text(
+                                "Route to accounts with credits once their rate limits are exhausted",
+                            )
+                            .changed()
+                            && update_credit_spending(
+                                &mut self.router_state,
+                                &mut self.state,
+                                allow_credit_spending,
+                            )
+                        {
+                            self.shared_state
+                                .set_allow_credit_spending(allow_credit_spending);
+                            self.persist_router_state();
+                        }
+
+                        let mut allow_api_key_overflow = self.state.allow_api_key_overflow;
+                        if ui
+                            .checkbox(&mut allow_api_key_overflow, "Paid API keys")
+                            .on_hover_text(
+                                "Route to API-key profiles, billed per token, once no account has quota left",
+                            )
+                            .changed()
+                            && update_api_key_overflow(
+                                &mut self.router_state,
+                                &mut self.state,
+                                allow_api_key_overflow,
+                            )
+                        {
+                            self.shared_state
+                                .set_allow_api_key_overflow(allow_api_key_overflow);
+                            self.persist_router_state();
+                        }
                     });
 
                     if self.state.profiles.is_empty() {
                         ui.label("No profiles yet. Save current login or run codex login.");
                     }
 
+                    if has_duplicate_profiles(&self.state.profiles) {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 165, 0),
+                                "⚠ Several profiles hold the same account.",
+                            );
+                            if ui
+                                .button("Merge Duplicates")
+                                .on_hover_text(
+                                    "Keep one profile per account with the freshest tokens; \
+                                     the others move to the trash",
+                                )
+                                .clicked()
+                            {
+                                let _ = self.cmd_tx.send(AppCommand::MergeDuplicateProfiles);
+                            }
+                        });
+                    }
+
+                    if let Some(pool) = &self.state.pool_forecast {
+                        let text = format_pool_forecast(pool);
+                        if pool.survives_until_reset {
+                            ui.label(text);
+                        } else {
+                            ui.colored_label(egui::Color32::from_rgb(255, 165, 0), text);
+                        }
+                    }
+
+                    let proxied_requests = self.shared_state.proxied_requests();
+                    let mut meta_action = None;
                     for profile in &self.state.profiles {
                         ui.separator();
                         ui.horizontal(|ui| {
+                            if let Some([r, g, b]) = profile.meta.color_rgb() {
+                                ui.colored_label(egui::Color32::from_rgb(r, g, b), "●");
+                            }
+                            let label = profile.meta.label(&profile.name);
                             let profile_label = match &profile.email {
-                                Some(email) => format!("{} ({})", profile.name, email),
-                                None => profile.name.clone(),
+                                Some(email) => format!("{} ({})", label, email),
+                                None => label.to_string(),
                             };
-                            ui.strong(&profile_label);
+                            let renaming = self
+                                .state
+                                .rename_draft
+                                .as_mut()
+                                .filter(|draft| draft.profile_name == profile.name);
+                            if let Some(draft) = renaming {
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut draft.input)
+                                        .desired_width(140.0),
+                                );
+                                let submitted = response.lost_focus()
+                                    && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                let new_name = draft.input.trim().to_string();
+                                if (ui
+                                    .add_enabled(!new_name.is_empty(), egui::Button::new("Save"))
+                                    .clicked()
+                                    || submitted)
+                                    && !new_name.is_empty()
+                                {
+                                    let _ = self.cmd_tx.send(AppCommand::RenameProfile {
+                                        name: profile.name.clone(),
+                                        new_name,
+                                    });
+                                }
+                                if ui.button("Cancel").clicked() {
+                                    self.state.rename_draft = None;
+                                }
+                            } else {
+                                ui.strong(&profile_label);
+                            }
+                            if !profile.meta.enabled {
+                                ui.weak("disabled");
+                            }
+                            for tag in &profile.meta.tags {
+                                ui.label(egui::RichText::new(format!("#{tag}")).small().weak());
+                            }
+                            if let Some(link) = &profile.meta.workspace {
+                                ui.weak(format!("workspace of {}", link.parent));
+                            }
                             ui.with_layout(
                                 egui::Layout::right_to_left(egui::Align::Center),
                                 |ui| {
                                     if ui.button("⟳").clicked() {
-                                        let _ = self.cmd_tx.send(AppCommand::FetchProfileQuota(
+                                        let _ = self.cmd_tx.send(AppCommand::FetchProfileQuota {
+                                            name: profile.name.clone(),
+                                            force: true,
+                                        });
+                                    }
+                                    if profile.meta.workspace.is_none()
+                                        && profile.auth_kind == AuthKind::ChatGpt
+                                        && ui
+                                            .button("Workspaces")
+                                            .on_hover_text(
+                                                "Add a profile for another workspace of this login",
+                                            )
+                                            .clicked()
+                                    {
+                                        self.state.workspace_picker = Some(WorkspacePicker {
+                                            profile_name: profile.name.clone(),
+                                            own_account_id: None,
+                                            workspaces: None,
+                                        });
+                                        let _ = self.cmd_tx.send(AppCommand::ListWorkspaces(
                                             profile.name.clone(),
                                         ));
                                     }
+                                    if ui.button("Rename").clicked() {
+                                        self.state.rename_draft = Some(ProfileRenameDraft {
+                                            profile_name: profile.name.clone(),
+                                            input: profile.name.clone(),
+                                        });
+                                    }
+                                    if ui.button("Edit").clicked() {
+                                        self.state.meta_editor =
+                                            Some(ProfileMetaDraft::new(profile));
+                                    }
+                                    let mut enabled = profile.meta.enabled;
+                                    if ui
+                                        .checkbox(&mut enabled, "Route")
+                                        .on_hover_text("Include this profile in routing")
+                                        .changed()
+                                    {
+                                        let mut meta = profile.meta.clone();
+                                        meta.enabled = enabled;
+                                        let _ = self.cmd_tx.send(AppCommand::UpdateProfileMeta {
+                                            name: profile.name.clone(),
+                                            meta,
+                                        });
+                                    }
                                     if profile.is_current {
                                         ui.add_enabled(false, egui::Button::new("Current"));
                                     } else {
@@ -316,16 +1640,62 @@ impl eframe::App for RouterApp {
                                             ));
                                         }
                                         if ui.button("Delete").clicked() {
-                                            let _ = self.cmd_tx.send(AppCommand::DeleteProfile(
-                                                profile.name.clone(),
-                                            ));
+                                            self.state.delete_confirm = Some(profile.name.clone());
                                         }
                                     }
                                 },
                             );
                         });
 
-                        if let Some(quota) = &profile.quota {
+                        if let Some(draft) = self
+                            .state
+                            .meta_editor
+                            .as_mut()
+                            .filter(|draft| draft.profile_name == profile.name)
+                        {
+                            meta_action = show_profile_meta_editor(ui, draft).or(meta_action);
+                        }
+
+                        if let Some(picker) = self
+                            .state
+                            .workspace_picker
+                            .as_ref()
+                            .filter(|picker| picker.profile_name == profile.name)
+                        {
+                            let linked: Vec<&str> = self
+                                .state
+                                .profiles
+                                .iter()
+                                .filter_map(|p| p.meta.workspace.as_ref())
+                                .filter(|link| link.parent == profile.name)
+                                .map(|link| link.account_id.as_str())
+                                .collect();
+                            match show_workspace_picker(ui, picker, &linked) {
+                                Some(WorkspacePickerAction::Add(workspace)) => {
+                                    let _ =
+                                        self.cmd_tx.send(AppCommand::AddWorkspaceProfile {
+                                            parent: profile.name.clone(),
+                                            workspace,
+                                        });
+                                }
+                                Some(WorkspacePickerAction::Close) => {
+                                    self.state.workspace_picker = None;
+                                }
+                                None => {}
+                            }
+                        }
+
+                        if let Some(problem) = profile.health.problem() {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 165, 0),
+                                format!("⚠ {problem}"),
+                            );
+                        }
+
+                        if profile.auth_kind == AuthKind::ApiKey && profile.quota.is_some() {
+                            let requests = proxied_requests.get(&profile.name).map_or(0, Vec::len);
+                            show_api_key_usage(ui, profile, requests);
+                        } else if let Some(quota) = &profile.quota {
                             egui::Grid::new(format!("quota_grid_{}", profile.name))
                                 .num_columns(2)
                                 .spacing([10.0, 2.0])
@@ -335,37 +1705,53 @@ impl eframe::App for RouterApp {
                                     ui.end_row();
 
                                     ui.label("Primary");
-                                    ui.label(match quota.used_requests {
-                                        Some(used) => {
-                                            let total = quota.total_requests.unwrap_or(100);
-                                            let left = total.saturating_sub(used.min(total));
-                                            format!("{}% used ({}% left)", used.min(total), left)
-                                        }
-                                        None => "-".to_string(),
-                                    });
+                                    ui.label(format_window_usage(quota.primary.as_ref()));
                                     ui.end_row();
 
                                     ui.label("Secondary");
-                                    ui.label(match quota.used_tokens {
-                                        Some(used) => {
-                                            let total = quota.total_tokens.unwrap_or(100);
-                                            let left = total.saturating_sub(used.min(total));
-                                            format!("{}% used ({}% left)", used.min(total), left)
-                                        }
-                                        None => "-".to_string(),
-                                    });
+                                    ui.label(format_window_usage(quota.secondary.as_ref()));
                                     ui.end_row();
 
                                     ui.label("Reset (Primary)");
-                                    ui.label(format_reset_time(quota.reset_date.as_deref()));
+                                    ui.label(format_reset_time(quota.primary_resets_at()));
                                     ui.end_row();
 
                                     ui.label("Reset (Secondary)");
-                                    ui.label(format_reset_time(
-                                        quota.secondary_reset_date.as_deref(),
+                                    ui.label(format_reset_time(quota.secondary_resets_at()));
+                                    ui.end_row();
+
+                                    ui.label("Updated");
+                                    let updated =
+                                        format_quota_fetched_at(profile.quota_fetched_at);
+                                    if profile.quota_stale {
+                                        ui.colored_label(
+                                            egui::Color32::from_rgb(255, 165, 0),
+                                            format!("{updated} (stale)"),
+                                        );
+                                    } else {
+                                        ui.label(updated);
+                                    }
+                                    ui.end_row();
+
+                                    if let Some(credits) = &quota.credits {
+                                        ui.label("Credits");
+                                        ui.label(format_credits(credits));
+                                        ui.end_row();
+                                    }
+
+                                    ui.label("Forecast");
+                                    ui.label(format_profile_forecast(
+                                        self.state.forecasts.get(&profile.name),
                                     ));
                                     ui.end_row();
                                 });
+                        } else if !profile.health.is_usable() {
+                            // Validation problem shown above
+                        } else if !profile.is_valid && profile.auth_kind == AuthKind::ApiKey {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 165, 0),
+                                "⚠ API key was rejected. Add the profile again with a valid key.",
+                            );
                         } else if !profile.is_valid {
                             ui.colored_label(
                                 egui::Color32::from_rgb(255, 165, 0),
@@ -374,6 +1760,31 @@ impl eframe::App for RouterApp {
                         } else {
                             ui.label("Loading quota...");
                         }
+
+                        let samples = self.state.quota_history.samples(&profile.name);
+                        if samples.len() >= 2 {
+                            egui::CollapsingHeader::new(format!(
+                                "Usage history ({} samples)",
+                                samples.len()
+                            ))
+                            .id_salt(format!("quota_history_header_{}", profile.name))
+                            .show(ui, |ui| {
+                                show_quota_history(ui, &profile.name, samples);
+                            });
+                        }
+                    }
+
+                    match meta_action {
+                        Some(MetaEditorAction::Save) => {
+                            if let Some(draft) = self.state.meta_editor.take() {
+                                let _ = self.cmd_tx.send(AppCommand::UpdateProfileMeta {
+                                    name: draft.profile_name.clone(),
+                                    meta: draft.into_meta(),
+                                });
+                            }
+                        }
+                        Some(MetaEditorAction::Cancel) => self.state.meta_editor = None,
+                        None => {}
                     }
                 });
 
@@ -399,6 +1810,36 @@ impl eframe::App for RouterApp {
                     }
                 });
 
+                ui.horizontal(|ui| {
+                    ui.label("API key");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.state.api_key_name_input)
+                            .hint_text("profile name")
+                            .desired_width(120.0),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.state.api_key_input)
+                            .hint_text("sk-...")
+                            .password(true),
+                    );
+                    let name = self.state.api_key_name_input.trim().to_string();
+                    let api_key = self.state.api_key_input.trim().to_string();
+                    if ui
+                        .add_enabled(
+                            !name.is_empty() && api_key.starts_with("sk-"),
+                            egui::Button::new("Add API Key Profile"),
+                        )
+                        .on_hover_text("Billed per token via the OpenAI API; used as paid overflow")
+                        .clicked()
+                    {
+                        let _ = self
+                            .cmd_tx
+                            .send(AppCommand::AddApiKeyProfile { name, api_key });
+                        self.state.api_key_name_input.clear();
+                        self.state.api_key_input.clear();
+                    }
+                });
+
                 if let Some(message) = &self.state.profile_message {
                     ui.label(message);
                 }
@@ -440,9 +1881,30 @@ impl eframe::App for RouterApp {
                             ui.monospace(&self.state.login_output);
                         });
                 }
+
+                ui.separator();
+                self.show_server_settings(ui);
+                self.show_admin_api(ui);
+                self.show_client_keys(ui);
+                self.show_request_log(ui);
+
+                ui.separator();
+                self.show_credential_storage(ui);
+
+                ui.separator();
+                self.show_profile_transfer(ui);
+
+                if !self.state.trash.is_empty() {
+                    ui.separator();
+                    self.show_trash(ui);
+                }
             }); // ScrollArea
         }); // CentralPanel
 
+        self.show_delete_confirm(ctx);
+        self.state.expire_undo_delete(Utc::now());
+        self.show_undo_delete(ctx);
+
         let interval = Duration::from_secs(self.state.refresh_interval_seconds.max(60));
         if auto_refresh_tick(
             self.state.auto_refresh_enabled,
@@ -488,7 +1950,7 @@ mod tests {
 
     #[test]
     fn router_app_initializes_state() {
-        let app = RouterApp::new();
+        let app = RouterApp::new(BindOverride::default());
         assert_eq!(app.state.refresh_interval_seconds, 600);
     }
 
@@ -529,6 +1991,11 @@ mod tests {
                 is_current: true,
                 is_valid: true,
                 quota: None,
+                quota_fetched_at: None,
+                quota_stale: false,
+                meta: Default::default(),
+                health: Default::default(),
+                auth_kind: Default::default(),
             },
             ProfileSummary {
                 name: "personal".to_string(),
@@ -536,6 +2003,11 @@ mod tests {
                 is_current: false,
                 is_valid: true,
                 quota: None,
+                quota_fetched_at: None,
+                quota_stale: false,
+                meta: Default::default(),
+                health: Default::default(),
+                auth_kind: Default::default(),
             },
         ];
 
@@ -550,6 +2022,23 @@ mod tests {
         let router_state = RouterState {
             refresh_interval_seconds: 300,
             auto_refresh_enabled: false,
+            allow_credit_spending: false,
+            allow_api_key_overflow: false,
+            server_host: "0.0.0.0".to_string(),
+            server_port: 8080,
+            client_keys: Vec::new(),
+            ip_rate_limits: Default::default(),
+            cors_origins: vec!["https://app.example.com".to_string()],
+            tls: TlsSettings {
+                enabled: true,
+                cert_path: Some(PathBuf::from("cert.pem")),
+                key_path: None,
+            },
+            tcp_enabled: false,
+            unix_socket_enabled: true,
+            unix_socket_path: None,
+            prompt_logging: Default::default(),
+            admin_api_enabled: false,
             last_selected_profile: Some("work".to_string()),
         };
 
@@ -557,6 +2046,111 @@ mod tests {
 
         assert_eq!(app_state.refresh_interval_seconds, 300);
         assert!(!app_state.auto_refresh_enabled);
+        assert!(!app_state.allow_credit_spending);
+        assert!(!app_state.allow_api_key_overflow);
+        assert_eq!(app_state.server_host_input, "0.0.0.0");
+        assert_eq!(app_state.server_port_input, 8080);
+        assert_eq!(app_state.cors_origins_input, "https://app.example.com");
+        assert!(app_state.tls_enabled_input);
+        assert_eq!(app_state.tls_cert_path_input, "cert.pem");
+        assert!(!app_state.tcp_enabled_input);
+        assert!(app_state.unix_socket_enabled_input);
+        assert!(!ServerSettingsInput::read(&app_state).differs_from(&router_state));
+    }
+
+    #[test]
+    fn server_settings_need_something_to_listen_on() {
+        let mut app_state = AppState::default();
+        let mut router_state = RouterState::default();
+        assert!(ServerSettingsInput::read(&app_state).is_valid());
+
+        app_state.tcp_enabled_input = false;
+        assert!(!ServerSettingsInput::read(&app_state).is_valid());
+
+        app_state.unix_socket_enabled_input = true;
+        app_state.unix_socket_path_input = " /tmp/router.sock ".to_string();
+        let input = ServerSettingsInput::read(&app_state);
+        assert!(input.is_valid());
+        assert!(input.clone().apply(&mut router_state));
+        assert!(!input.apply(&mut router_state));
+        assert_eq!(
+            router_state.unix_socket_path,
+            Some(PathBuf::from("/tmp/router.sock"))
+        );
+    }
+
+    #[test]
+    fn update_credit_spending_returns_change() {
+        let mut app_state = AppState::default();
+        let mut router_state = RouterState::default();
+
+        assert!(!update_credit_spending(
+            &mut router_state,
+            &mut app_state,
+            false
+        ));
+        assert!(update_credit_spending(
+            &mut router_state,
+            &mut app_state,
+            true
+        ));
+        assert!(router_state.allow_credit_spending);
+        assert!(app_state.allow_credit_spending);
+    }
+
+    #[test]
+    fn cors_origins_are_normalized_and_validated() {
+        let mut router_state = RouterState::default();
+
+        assert!(update_cors_origins(
+            &mut router_state,
+            "https://App.example.com/, http://localhost:3000, https://app.example.com"
+        )
+        .unwrap());
+        assert_eq!(
+            router_state.cors_origins,
+            vec!["https://app.example.com", "http://localhost:3000"]
+        );
+        assert!(update_cors_origins(&mut router_state, "*").is_err());
+        assert!(update_cors_origins(&mut router_state, "https://app.example.com/path").is_err());
+        assert!(update_cors_origins(&mut router_state, "").unwrap());
+        assert!(router_state.cors_origins.is_empty());
+    }
+
+    #[test]
+    fn created_client_keys_are_stored_hashed_and_revoked_once() {
+        let mut router_state = RouterState::default();
+        let key = create_client_key(&mut router_state, "laptop", "gpt-5", "");
+
+        let record = &router_state.client_keys[0];
+        assert_eq!(record.allowed_models, vec!["gpt-5".to_string()]);
+        assert!(record.pool_tags.is_empty());
+        assert!(!serde_json::to_string(&router_state).unwrap().contains(&key));
+
+        let id = record.id.clone();
+        assert!(revoke_client_key(&mut router_state, &id, Utc::now()));
+        assert!(!revoke_client_key(&mut router_state, &id, Utc::now()));
+        assert!(client_keys::find_active_key(&router_state.client_keys, &key).is_none());
+    }
+
+    #[test]
+    fn rename_updates_last_selected_profile_only_when_it_matches() {
+        let mut router_state = RouterState {
+            last_selected_profile: Some("john-2".to_string()),
+            ..RouterState::default()
+        };
+
+        assert!(!rename_last_selected_profile(
+            &mut router_state,
+            "work",
+            "job"
+        ));
+        assert!(rename_last_selected_profile(
+            &mut router_state,
+            "john-2",
+            "john"
+        ));
+        assert_eq!(router_state.last_selected_profile.as_deref(), Some("john"));
     }
 
     #[test]
@@ -571,6 +2165,101 @@ mod tests {
         assert!(!router_state.auto_refresh_enabled);
     }
 
+    #[test]
+    fn history_points_are_relative_hours() {
+        let now = Utc::now();
+        let samples = vec![
+            QuotaSample {
+                timestamp: now - chrono::Duration::hours(2),
+                primary_used: Some(20),
+                secondary_used: None,
+            },
+            QuotaSample {
+                timestamp: now,
+                primary_used: Some(140),
+                secondary_used: Some(5),
+            },
+        ];
+
+        let primary = history_points(&samples, now, |sample| sample.primary_used);
+        let secondary = history_points(&samples, now, |sample| sample.secondary_used);
+
+        assert_eq!(primary, vec![[-2.0, 20.0], [0.0, 100.0]]);
+        assert_eq!(secondary, vec![[0.0, 5.0]]);
+    }
+
+    #[test]
+    fn formats_profile_forecast() {
+        use crate::forecast::WindowForecast;
+
+        let idle = ProfileForecast {
+            primary: WindowForecast::Idle,
+            secondary: WindowForecast::SurvivesUntilReset,
+            primary_rate: Some(0.0),
+            secondary_rate: Some(2.5),
+        };
+        let unknown = ProfileForecast {
+            primary: WindowForecast::Unknown,
+            secondary: WindowForecast::Unknown,
+            primary_rate: None,
+            secondary_rate: None,
+        };
+
+        assert_eq!(format_profile_forecast(None), "-");
+        assert_eq!(
+            format_profile_forecast(Some(&idle)),
+            "Lasts until reset (2.5%/h)"
+        );
+        assert_eq!(
+            format_profile_forecast(Some(&unknown)),
+            "Not enough history yet"
+        );
+    }
+
+    #[test]
+    fn formats_window_usage_and_credits() {
+        let window = RateLimitWindow {
+            used_percent: 24.6,
+            window_seconds: Some(5 * 3600),
+            resets_at: None,
+        };
+        let credits = CreditsInfo {
+            has_credits: true,
+            unlimited: false,
+            balance: Some("12.34".to_string()),
+        };
+
+        assert_eq!(format_window_usage(None), "-");
+        assert_eq!(
+            format_window_usage(Some(&window)),
+            "25% used (75% left) / 5h"
+        );
+        assert_eq!(format_window_length(7 * 24 * 3600).as_deref(), Some("1w"));
+        assert_eq!(format_credits(&credits), "12.34");
+    }
+
+    #[test]
+    fn describes_official_auth_changes() {
+        let change = OfficialAuthChange {
+            email: Some("new@example.com".to_string()),
+            status: AuthMatch::New {
+                suggested_name: "new".to_string(),
+            },
+        };
+        assert!(official_auth_change_message(&change).contains("new@example.com"));
+
+        let change = OfficialAuthChange {
+            email: None,
+            status: AuthMatch::Refreshed {
+                name: "work".to_string(),
+            },
+        };
+        assert_eq!(
+            official_auth_change_message(&change),
+            "Codex CLI has newer credentials for profile 'work' (an unknown account)."
+        );
+    }
+
     #[test]
     fn auto_refresh_disabled_never_triggers() {
         let mut schedule = RefreshSchedule::new();
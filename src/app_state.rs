@@ -1,21 +1,93 @@
 use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use zeroize::Zeroize;
 
-use crate::api::QuotaInfo;
+use crate::api::{QuotaInfo, Workspace};
+use crate::bundle;
+use crate::forecast::{self, PoolForecast, ProfileForecast};
+use crate::history::QuotaHistory;
 use crate::login_output::LoginOutput;
-use crate::profile::{ProfileSummary, SaveProfileOutcome};
+use crate::profile::{AuthMatch, DuplicateMerge, ProfileSummary, SaveProfileOutcome};
+use crate::profile_meta::{self, ProfileMeta};
+use crate::secret_store::{Passphrase, VaultStatus};
+use crate::server::{DEFAULT_HOST, DEFAULT_PORT};
+use crate::trash::TrashEntry;
+
+/// How long the "Undo" toast stays up after deleting a profile
+pub const UNDO_DELETE_SECONDS: i64 = 10;
 
 #[derive(Debug, Clone)]
 pub enum AppCommand {
     LoadProfiles,
     SwitchProfile(String),
     SaveProfile(String),
-    #[allow(dead_code)]
+    /// Add a profile that authenticates with a platform API key
+    AddApiKeyProfile {
+        name: String,
+        api_key: String,
+    },
+    /// Move a profile to the trash
     DeleteProfile(String),
+    /// Move a trashed profile (by trash entry id) back to the profiles
+    RestoreProfile(String),
+    /// Permanently delete a trashed profile
+    PurgeTrashEntry(String),
+    /// Fold profiles holding the same account into one, keeping the freshest tokens
+    MergeDuplicateProfiles,
+    /// Look up the workspaces available to a profile's login
+    ListWorkspaces(String),
+    /// Add a sub-profile routing to another workspace of `parent`'s login
+    AddWorkspaceProfile {
+        parent: String,
+        workspace: Workspace,
+    },
     RunLogin,
     CancelLogin,
     OpenLoginUrl(String),
     FetchQuota,
-    FetchProfileQuota(String),
+    /// Fetch one profile's quota; `force` skips the error backoff for explicit refreshes
+    FetchProfileQuota {
+        name: String,
+        force: bool,
+    },
+    UpdateProfileMeta {
+        name: String,
+        meta: ProfileMeta,
+    },
+    /// The proxy served a request with this profile
+    RecordProfileUse(String),
+    RenameProfile {
+        name: String,
+        new_name: String,
+    },
+    /// Write the named profiles to a passphrase-encrypted bundle
+    ExportProfiles {
+        names: Vec<String>,
+        path: PathBuf,
+        passphrase: Passphrase,
+    },
+    /// Merge the profiles of a bundle into this machine's profiles
+    ImportProfiles {
+        path: PathBuf,
+        passphrase: Passphrase,
+    },
+    UnlockVault(Passphrase),
+    LockVault,
+    /// Encrypt all profiles with a new passphrase-protected vault
+    EnableVault(Passphrase),
+    /// Decrypt all profiles back to plaintext
+    DisableVault,
+    /// The official Codex auth.json changed on disk
+    CheckOfficialAuth,
+    /// Save the official Codex credentials as a profile and make it current
+    ImportOfficialAuth,
+    /// Run `command` between `CommandStarted(id)` and `CommandFinished(id)`, so the
+    /// admin API can tell which events it caused
+    Tracked {
+        id: u64,
+        command: Box<AppCommand>,
+    },
     Shutdown,
 }
 
@@ -23,6 +95,27 @@ pub enum AppCommand {
 pub enum AppEvent {
     ProfilesLoaded(Vec<ProfileSummary>),
     ProfileSaved(SaveProfileOutcome),
+    ProfileRenamed {
+        old_name: String,
+        new_name: String,
+    },
+    ProfileTrashed(TrashEntry),
+    ProfileRestored {
+        name: String,
+    },
+    TrashLoaded(Vec<TrashEntry>),
+    DuplicatesMerged(Vec<DuplicateMerge>),
+    WorkspacesLoaded {
+        profile: String,
+        /// The account the profile itself uses
+        own_account_id: Option<String>,
+        workspaces: Vec<Workspace>,
+    },
+    ProfilesExported {
+        count: usize,
+        path: PathBuf,
+    },
+    ProfilesImported(Vec<SaveProfileOutcome>),
     LoginOutput {
         output: String,
         parsed: LoginOutput,
@@ -36,20 +129,132 @@ pub enum AppEvent {
     ProfileQuotaLoaded {
         name: String,
         quota: QuotaInfo,
+        fetched_at: DateTime<Utc>,
+        stale: bool,
+    },
+    /// Fetching failed; the profile keeps its last known quota, marked stale
+    ProfileQuotaFailed {
+        name: String,
+        error: String,
+        retry_at: DateTime<Utc>,
+    },
+    QuotaHistoryLoaded(QuotaHistory),
+    VaultUpdated {
+        status: VaultStatus,
+        message: Option<String>,
+    },
+    /// Credentials the Codex CLI is using that are not saved yet; `None` once in sync
+    OfficialAuthChanged(Option<OfficialAuthChange>),
+    /// An expired access token was refreshed, or the refresh failed
+    TokenRefreshed {
+        name: String,
+        success: bool,
     },
+    /// Brackets the events of an `AppCommand::Tracked`
+    CommandStarted(u64),
+    CommandFinished(u64),
     Error(String),
 }
 
+/// Inline rename in progress
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileRenameDraft {
+    pub profile_name: String,
+    pub input: String,
+}
+
+/// In-progress edits to a profile's metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileMetaDraft {
+    pub profile_name: String,
+    pub meta: ProfileMeta,
+    pub display_name_input: String,
+    pub tags_input: String,
+}
+
+impl ProfileMetaDraft {
+    pub fn new(profile: &ProfileSummary) -> Self {
+        Self {
+            profile_name: profile.name.clone(),
+            meta: profile.meta.clone(),
+            display_name_input: profile.meta.display_name.clone().unwrap_or_default(),
+            tags_input: profile.meta.tags.join(", "),
+        }
+    }
+
+    pub fn into_meta(self) -> ProfileMeta {
+        let display_name = self.display_name_input.trim();
+        ProfileMeta {
+            display_name: (!display_name.is_empty()).then(|| display_name.to_string()),
+            tags: profile_meta::parse_tags(&self.tags_input),
+            ..self.meta
+        }
+    }
+}
+
+/// Workspaces offered for one profile; `workspaces` is `None` while loading
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspacePicker {
+    pub profile_name: String,
+    pub own_account_id: Option<String>,
+    pub workspaces: Option<Vec<Workspace>>,
+}
+
+/// Credentials found in the official Codex auth.json that the user can import
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfficialAuthChange {
+    pub email: Option<String>,
+    pub status: AuthMatch,
+}
+
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub profiles: Vec<ProfileSummary>,
     pub current_profile: Option<String>,
     pub quota: Option<QuotaInfo>,
+    pub quota_history: QuotaHistory,
+    pub forecasts: HashMap<String, ProfileForecast>,
+    pub pool_forecast: Option<PoolForecast>,
     pub refresh_interval_seconds: u64,
     pub auto_refresh_enabled: bool,
+    pub allow_credit_spending: bool,
+    pub allow_api_key_overflow: bool,
+    pub server_host_input: String,
+    pub server_port_input: u16,
+    pub cors_origins_input: String,
+    pub tls_enabled_input: bool,
+    pub tls_cert_path_input: String,
+    pub tls_key_path_input: String,
+    pub tcp_enabled_input: bool,
+    pub unix_socket_enabled_input: bool,
+    pub unix_socket_path_input: String,
+    pub client_key_label_input: String,
+    pub client_key_models_input: String,
+    pub client_key_tags_input: String,
+    /// Plaintext of the key just created, shown until dismissed
+    pub new_client_key: Option<String>,
+    pub request_log_filter: String,
     pub last_updated: Option<DateTime<Utc>>,
     pub profile_message: Option<String>,
     pub profile_name_input: String,
+    pub api_key_name_input: String,
+    pub api_key_input: String,
+    pub meta_editor: Option<ProfileMetaDraft>,
+    pub rename_draft: Option<ProfileRenameDraft>,
+    /// Profile awaiting confirmation in the delete dialog
+    pub delete_confirm: Option<String>,
+    /// Most recent deletion, offered for undo until the toast expires
+    pub undo_delete: Option<TrashEntry>,
+    pub trash: Vec<TrashEntry>,
+    pub workspace_picker: Option<WorkspacePicker>,
+    pub vault_status: Option<VaultStatus>,
+    pub vault_passphrase_input: String,
+    pub vault_message: Option<String>,
+    pub bundle_path_input: String,
+    pub bundle_passphrase_input: String,
+    pub export_selection: BTreeSet<String>,
+    pub bundle_message: Option<String>,
+    pub official_auth_change: Option<OfficialAuthChange>,
     pub login_output: String,
     pub login_url: Option<String>,
     pub login_code: Option<String>,
@@ -63,11 +268,46 @@ impl Default for AppState {
             profiles: Vec::new(),
             current_profile: None,
             quota: None,
+            quota_history: QuotaHistory::default(),
+            forecasts: HashMap::new(),
+            pool_forecast: None,
             refresh_interval_seconds: 600,
             auto_refresh_enabled: true,
+            allow_credit_spending: false,
+            allow_api_key_overflow: false,
+            server_host_input: DEFAULT_HOST.to_string(),
+            server_port_input: DEFAULT_PORT,
+            cors_origins_input: String::new(),
+            tls_enabled_input: false,
+            tls_cert_path_input: String::new(),
+            tls_key_path_input: String::new(),
+            tcp_enabled_input: true,
+            unix_socket_enabled_input: false,
+            unix_socket_path_input: String::new(),
+            client_key_label_input: String::new(),
+            client_key_models_input: String::new(),
+            client_key_tags_input: String::new(),
+            new_client_key: None,
+            request_log_filter: String::new(),
             last_updated: None,
             profile_message: None,
             profile_name_input: String::new(),
+            api_key_name_input: String::new(),
+            api_key_input: String::new(),
+            meta_editor: None,
+            rename_draft: None,
+            delete_confirm: None,
+            undo_delete: None,
+            trash: Vec::new(),
+            workspace_picker: None,
+            vault_status: None,
+            vault_passphrase_input: String::new(),
+            vault_message: None,
+            bundle_path_input: String::new(),
+            bundle_passphrase_input: String::new(),
+            export_selection: BTreeSet::new(),
+            bundle_message: None,
+            official_auth_change: None,
             login_output: String::new(),
             login_url: None,
             login_code: None,
@@ -78,6 +318,26 @@ impl Default for AppState {
 }
 
 impl AppState {
+    /// Recompute per-profile and pool forecasts from quotas, history and proxied traffic.
+    pub fn update_forecasts(
+        &mut self,
+        proxied_requests: &HashMap<String, Vec<DateTime<Utc>>>,
+        now: DateTime<Utc>,
+    ) {
+        self.forecasts =
+            forecast::forecast_profiles(&self.profiles, &self.quota_history, proxied_requests, now);
+        self.pool_forecast = forecast::forecast_pool(&self.profiles, &self.forecasts, now);
+    }
+
+    /// Drop the undo toast once it has been shown long enough
+    pub fn expire_undo_delete(&mut self, now: DateTime<Utc>) {
+        if self.undo_delete.as_ref().is_some_and(|entry| {
+            now - entry.deleted_at >= chrono::Duration::seconds(UNDO_DELETE_SECONDS)
+        }) {
+            self.undo_delete = None;
+        }
+    }
+
     pub fn apply_event(&mut self, event: AppEvent) {
         match event {
             AppEvent::ProfilesLoaded(mut profiles) => {
@@ -88,6 +348,8 @@ impl AppState {
                             self.profiles.iter().find(|p| p.name == new_profile.name)
                         {
                             new_profile.quota = existing.quota.clone();
+                            new_profile.quota_fetched_at = existing.quota_fetched_at;
+                            new_profile.quota_stale = existing.quota_stale;
                         }
                     }
                 }
@@ -95,17 +357,42 @@ impl AppState {
                     .iter()
                     .find(|profile| profile.is_current)
                     .map(|profile| profile.name.clone());
+                self.export_selection
+                    .retain(|name| profiles.iter().any(|profile| &profile.name == name));
                 self.profiles = profiles;
             }
             AppEvent::QuotaLoaded(quota) => {
                 self.quota = Some(quota);
                 self.last_updated = Some(Utc::now());
             }
-            AppEvent::ProfileQuotaLoaded { name, quota } => {
+            AppEvent::ProfileQuotaLoaded {
+                name,
+                quota,
+                fetched_at,
+                stale,
+            } => {
                 if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == name) {
                     profile.quota = Some(quota);
+                    profile.quota_fetched_at = Some(fetched_at);
+                    profile.quota_stale = stale;
                 }
             }
+            AppEvent::ProfileQuotaFailed {
+                name,
+                error,
+                retry_at,
+            } => {
+                if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == name) {
+                    profile.quota_stale = profile.quota.is_some();
+                }
+                self.error = Some(format!(
+                    "Failed to fetch quota for {name}: {error} (retrying after {})",
+                    retry_at.format("%H:%M UTC")
+                ));
+            }
+            AppEvent::QuotaHistoryLoaded(history) => {
+                self.quota_history = history;
+            }
             AppEvent::ProfileSaved(outcome) => {
                 self.profile_message = Some(match outcome {
                     SaveProfileOutcome::Created { name } => format!("Saved profile: {name}"),
@@ -115,6 +402,74 @@ impl AppState {
                     }
                 });
             }
+            AppEvent::ProfileRenamed { old_name, new_name } => {
+                if self.current_profile.as_deref() == Some(old_name.as_str()) {
+                    self.current_profile = Some(new_name.clone());
+                }
+                for profile in &mut self.profiles {
+                    if profile.name == old_name {
+                        profile.name = new_name.clone();
+                    }
+                }
+                self.quota_history.rename(&old_name, &new_name);
+                if let Some(forecast) = self.forecasts.remove(&old_name) {
+                    self.forecasts.insert(new_name.clone(), forecast);
+                }
+                if self
+                    .meta_editor
+                    .as_ref()
+                    .is_some_and(|draft| draft.profile_name == old_name)
+                {
+                    self.meta_editor = None;
+                }
+                self.rename_draft = None;
+                self.profile_message = Some(format!("Renamed profile {old_name} to {new_name}"));
+            }
+            AppEvent::ProfileTrashed(entry) => {
+                self.profile_message = Some(format!("Moved {} to the trash", entry.profile_name));
+                self.undo_delete = Some(entry);
+            }
+            AppEvent::ProfileRestored { name } => {
+                self.undo_delete = None;
+                self.profile_message = Some(format!("Restored profile {name}"));
+            }
+            AppEvent::TrashLoaded(entries) => {
+                self.trash = entries;
+            }
+            AppEvent::DuplicatesMerged(merges) => {
+                let summary: Vec<String> = merges
+                    .iter()
+                    .map(|merge| format!("{} into {}", merge.merged.join(", "), merge.kept))
+                    .collect();
+                self.profile_message = Some(if summary.is_empty() {
+                    "No duplicate profiles found".to_string()
+                } else {
+                    format!("Merged {}", summary.join("; "))
+                });
+            }
+            AppEvent::WorkspacesLoaded {
+                profile,
+                own_account_id,
+                workspaces,
+            } => {
+                if let Some(picker) = self
+                    .workspace_picker
+                    .as_mut()
+                    .filter(|picker| picker.profile_name == profile)
+                {
+                    picker.own_account_id = own_account_id;
+                    picker.workspaces = Some(workspaces);
+                }
+            }
+            AppEvent::ProfilesExported { count, path } => {
+                self.bundle_passphrase_input.zeroize();
+                self.bundle_message =
+                    Some(format!("Exported {count} profiles to {}", path.display()));
+            }
+            AppEvent::ProfilesImported(outcomes) => {
+                self.bundle_passphrase_input.zeroize();
+                self.bundle_message = Some(bundle::format_import_summary(&outcomes));
+            }
             AppEvent::LoginOutput {
                 output,
                 parsed,
@@ -146,6 +501,19 @@ impl AppState {
                     self.error = Some(message);
                 }
             }
+            AppEvent::VaultUpdated { status, message } => {
+                self.vault_status = Some(status);
+                if message.is_some() {
+                    self.vault_message = message;
+                }
+            }
+            AppEvent::OfficialAuthChanged(change) => {
+                self.official_auth_change = change;
+            }
+            // Only counted in the server metrics
+            AppEvent::TokenRefreshed { .. } => {}
+            // Only read by the admin API
+            AppEvent::CommandStarted(_) | AppEvent::CommandFinished(_) => {}
             AppEvent::Error(message) => {
                 self.error = Some(message);
             }
@@ -156,6 +524,7 @@ impl AppState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::RateLimitWindow;
     use crate::login_output::LoginOutput;
     use crate::profile::SaveProfileOutcome;
 
@@ -166,6 +535,11 @@ mod tests {
             is_current: true,
             is_valid: true,
             quota: None,
+            quota_fetched_at: None,
+            quota_stale: false,
+            meta: Default::default(),
+            health: Default::default(),
+            auth_kind: Default::default(),
         }
     }
 
@@ -177,6 +551,20 @@ mod tests {
         assert_eq!(state.current_profile.as_deref(), Some("work"));
     }
 
+    #[test]
+    fn meta_draft_normalizes_inputs() {
+        let mut draft = ProfileMetaDraft::new(&sample_profile());
+        draft.display_name_input = "  ".to_string();
+        draft.tags_input = "team, personal,".to_string();
+        draft.meta.enabled = false;
+
+        let meta = draft.into_meta();
+
+        assert_eq!(meta.display_name, None);
+        assert_eq!(meta.tags, vec!["team", "personal"]);
+        assert!(!meta.enabled);
+    }
+
     #[test]
     fn applies_profile_saved_event() {
         let mut state = AppState::default();
@@ -189,6 +577,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn profile_trashed_event_offers_undo_until_it_expires() {
+        let mut state = AppState::default();
+        let deleted_at = Utc::now();
+        state.apply_event(AppEvent::ProfileTrashed(TrashEntry {
+            id: "1-work".to_string(),
+            profile_name: "work".to_string(),
+            deleted_at,
+        }));
+        assert_eq!(
+            state.profile_message.as_deref(),
+            Some("Moved work to the trash")
+        );
+
+        state.expire_undo_delete(deleted_at + chrono::Duration::seconds(1));
+        assert!(state.undo_delete.is_some());
+        state.expire_undo_delete(deleted_at + chrono::Duration::seconds(UNDO_DELETE_SECONDS));
+        assert!(state.undo_delete.is_none());
+    }
+
+    #[test]
+    fn applies_profiles_imported_event() {
+        let mut state = AppState {
+            bundle_passphrase_input: "secret".to_string(),
+            ..AppState::default()
+        };
+        state.apply_event(AppEvent::ProfilesImported(vec![
+            SaveProfileOutcome::Created {
+                name: "work".to_string(),
+            },
+            SaveProfileOutcome::AlreadyExists {
+                name: "home".to_string(),
+            },
+        ]));
+        assert!(state.bundle_passphrase_input.is_empty());
+        assert_eq!(
+            state.bundle_message.as_deref(),
+            Some("Imported 2 profiles: 1 created, 0 updated, 1 skipped")
+        );
+    }
+
+    #[test]
+    fn applies_quota_history_loaded_event() {
+        let mut state = AppState::default();
+        let mut history = QuotaHistory::default();
+        history.profiles.insert(
+            "work".to_string(),
+            vec![crate::history::QuotaSample {
+                timestamp: Utc::now(),
+                primary_used: Some(10),
+                secondary_used: Some(5),
+            }],
+        );
+
+        state.apply_event(AppEvent::QuotaHistoryLoaded(history));
+
+        assert_eq!(state.quota_history.samples("work").len(), 1);
+    }
+
+    #[test]
+    fn applies_vault_updated_event() {
+        use crate::secret_store::StorageMode;
+
+        let mut state = AppState::default();
+        state.apply_event(AppEvent::VaultUpdated {
+            status: VaultStatus {
+                mode: StorageMode::Vault,
+                unlocked: false,
+            },
+            message: Some("Encrypted 2 profiles".to_string()),
+        });
+
+        assert!(state.vault_status.unwrap().is_locked());
+        assert_eq!(state.vault_message.as_deref(), Some("Encrypted 2 profiles"));
+    }
+
     #[test]
     fn applies_login_output_event() {
         let mut state = AppState::default();
@@ -220,12 +684,14 @@ mod tests {
             account_id: "acct_123".to_string(),
             email: "test@example.com".to_string(),
             plan_type: "pro".to_string(),
-            used_requests: Some(10),
-            total_requests: Some(100),
-            used_tokens: None,
-            total_tokens: None,
-            reset_date: None,
-            secondary_reset_date: None,
+            primary: Some(RateLimitWindow {
+                used_percent: 10.0,
+                window_seconds: None,
+                resets_at: None,
+            }),
+            secondary: None,
+            credits: None,
+            usage: None,
         });
         state.profiles = vec![old_profile.clone()];
 
@@ -240,6 +706,32 @@ mod tests {
         assert_eq!(state.profiles[0].quota, old_profile.quota);
     }
 
+    #[test]
+    fn applies_profile_quota_failed_event_keeps_quota_as_stale() {
+        let mut state = AppState::default();
+        let mut profile = sample_profile();
+        profile.quota = Some(QuotaInfo {
+            account_id: "acct_123".to_string(),
+            email: "test@example.com".to_string(),
+            plan_type: "pro".to_string(),
+            primary: None,
+            secondary: None,
+            credits: None,
+            usage: None,
+        });
+        state.profiles = vec![profile];
+
+        state.apply_event(AppEvent::ProfileQuotaFailed {
+            name: "work".to_string(),
+            error: "timeout".to_string(),
+            retry_at: Utc::now(),
+        });
+
+        assert!(state.profiles[0].quota.is_some());
+        assert!(state.profiles[0].quota_stale);
+        assert!(state.error.as_deref().unwrap().contains("timeout"));
+    }
+
     #[test]
     fn applies_profiles_loaded_event_does_not_merge_quota_for_invalid_profile() {
         let mut state = AppState::default();
@@ -248,12 +740,14 @@ mod tests {
             account_id: "acct_123".to_string(),
             email: "test@example.com".to_string(),
             plan_type: "pro".to_string(),
-            used_requests: Some(10),
-            total_requests: Some(100),
-            used_tokens: None,
-            total_tokens: None,
-            reset_date: None,
-            secondary_reset_date: None,
+            primary: Some(RateLimitWindow {
+                used_percent: 10.0,
+                window_seconds: None,
+                resets_at: None,
+            }),
+            secondary: None,
+            credits: None,
+            usage: None,
         });
         state.profiles = vec![old_profile];
 
@@ -0,0 +1,197 @@
+//! Crash-safe file writes.
+//!
+//! Auth and state files are read concurrently by the Codex CLI, so they are never
+//! rewritten in place: contents go to a temporary sibling that is fsynced and then
+//! renamed over the target. Readers see either the old file or the new one.
+
+use anyhow::{Context, Result};
+use rand::RngCore;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Atomically replace `path` with `contents`. The file is owner-only (0600) on Unix.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let temp_path = temp_path_for(path)?;
+    let result = write_and_rename(&temp_path, path, contents);
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn write_and_rename(temp_path: &Path, path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = open_private(temp_path)
+        .with_context(|| format!("Failed to create temporary file {:?}", temp_path))?;
+    file.write_all(contents)
+        .with_context(|| format!("Failed to write {:?}", temp_path))?;
+    file.sync_all()
+        .with_context(|| format!("Failed to sync {:?}", temp_path))?;
+    drop(file);
+
+    fs::rename(temp_path, path).with_context(|| format!("Failed to replace {:?}", path))?;
+    sync_parent_dir(path);
+    Ok(())
+}
+
+fn temp_path_for(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Invalid file path: {:?}", path))?
+        .to_string_lossy();
+    let suffix = rand::rng().next_u32();
+    Ok(path.with_file_name(format!(
+        ".{file_name}.tmp-{}-{suffix:08x}",
+        std::process::id()
+    )))
+}
+
+#[cfg(unix)]
+fn open_private(path: &Path) -> io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn open_private(path: &Path) -> io::Result<fs::File> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+}
+
+/// Persist the rename itself. Best effort: not every platform can open a directory.
+fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// A group of file writes that can be undone together.
+///
+/// Each write records the previous contents of its target so `rollback` can
+/// restore them (or remove files that did not exist) in reverse order.
+#[derive(Debug, Default)]
+pub struct FileTransaction {
+    written: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl FileTransaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
+        let previous = match fs::read(path) {
+            Ok(bytes) => Some(bytes),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to read {:?}", path));
+            }
+        };
+        write_atomic(path, contents)?;
+        self.written.push((path.to_path_buf(), previous));
+        Ok(())
+    }
+
+    /// Restore every file written so far. All targets are attempted; the first error is returned.
+    pub fn rollback(self) -> Result<()> {
+        let mut first_error = None;
+        for (path, previous) in self.written.into_iter().rev() {
+            let result = match previous {
+                Some(bytes) => write_atomic(&path, &bytes),
+                None => {
+                    fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))
+                }
+            };
+            if let Err(err) = result {
+                tracing::error!(path = ?path, error = %err, "Failed to roll back file");
+                first_error.get_or_insert(err);
+            }
+        }
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir_entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn replaces_contents_without_leaving_temp_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("state.json");
+        fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(dir_entries(temp_dir.path()), vec!["state.json"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn written_files_are_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("auth.json");
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_atomic(&path, b"{}").unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn failed_write_cleans_up_temp_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("auth.json");
+        fs::create_dir(&path).unwrap();
+
+        assert!(write_atomic(&path, b"{}").is_err());
+        assert_eq!(dir_entries(temp_dir.path()), vec!["auth.json"]);
+    }
+
+    #[test]
+    fn rollback_restores_previous_contents() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let existing = temp_dir.path().join("existing");
+        let created = temp_dir.path().join("created");
+        fs::write(&existing, "before").unwrap();
+
+        let mut transaction = FileTransaction::new();
+        transaction.write(&existing, b"after").unwrap();
+        transaction.write(&created, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "after");
+
+        transaction.rollback().unwrap();
+
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "before");
+        assert!(!created.exists());
+    }
+}
@@ -5,7 +5,9 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs;
 
-use crate::config::{get_auth_file, get_codex_home};
+use crate::atomic_file;
+use crate::config::{get_auth_file, get_codex_home, get_official_auth_file};
+use crate::secret_store;
 
 /// Auth data structure matching Codex's auth.json format
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -20,6 +22,26 @@ pub struct AuthDotJson {
     pub last_refresh: Option<DateTime<Utc>>,
 }
 
+/// Which upstream a set of credentials is for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuthKind {
+    /// ChatGPT login tokens, served by the Codex backend within plan rate limits
+    #[default]
+    ChatGpt,
+    /// Platform API key, served by the Responses API and billed per token
+    ApiKey,
+}
+
+impl AuthKind {
+    pub fn of(auth: &AuthDotJson) -> Self {
+        if auth.tokens.is_none() && auth.openai_api_key.is_some() {
+            AuthKind::ApiKey
+        } else {
+            AuthKind::ChatGpt
+        }
+    }
+}
+
 /// Token data structure
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TokenData {
@@ -84,8 +106,24 @@ pub fn load_auth() -> Result<AuthDotJson> {
     Ok(auth)
 }
 
+/// Load the official Codex CLI auth.json, if the CLI is logged in
+pub fn load_official_auth() -> Result<Option<AuthDotJson>> {
+    let auth_file = get_official_auth_file()?;
+
+    if !auth_file.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&auth_file)
+        .with_context(|| format!("Failed to read auth file: {:?}", auth_file))?;
+
+    let auth: AuthDotJson = serde_json::from_str(&content)
+        .with_context(|| "Failed to parse official Codex auth.json")?;
+
+    Ok(Some(auth))
+}
+
 /// Save auth to the active auth.json file
-#[allow(dead_code)]
 pub fn save_auth(auth: &AuthDotJson) -> Result<()> {
     let auth_file = get_auth_file()?;
 
@@ -96,15 +134,8 @@ pub fn save_auth(auth: &AuthDotJson) -> Result<()> {
 
     let json = serde_json::to_string_pretty(auth)?;
 
-    // Write with restrictive permissions
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&auth_file)?.permissions();
-        perms.set_mode(0o600);
-    }
-
-    fs::write(&auth_file, json)?;
+    // Replace atomically with restrictive permissions
+    atomic_file::write_atomic(&auth_file, json.as_bytes())?;
 
     Ok(())
 }
@@ -122,11 +153,11 @@ pub fn load_auth_from_profile(profile_name: &str) -> Result<AuthDotJson> {
         anyhow::bail!("Profile '{}' not found", profile_name);
     }
 
-    let content = fs::read_to_string(&profile_auth_file)
+    let content = secret_store::read_secret(&profile_auth_file)
         .with_context(|| format!("Failed to read profile auth file: {:?}", profile_auth_file))?;
 
     let auth: AuthDotJson =
-        serde_json::from_str(&content).with_context(|| "Failed to parse profile auth.json")?;
+        serde_json::from_slice(&content).with_context(|| "Failed to parse profile auth.json")?;
 
     Ok(auth)
 }
@@ -173,6 +204,15 @@ fn get_account_from_info(info: &IdTokenInfo) -> Option<String> {
     })
 }
 
+/// Credentials for a profile that only holds a platform API key
+pub fn api_key_auth(api_key: &str) -> AuthDotJson {
+    AuthDotJson {
+        openai_api_key: Some(api_key.to_string()),
+        tokens: None,
+        last_refresh: None,
+    }
+}
+
 /// Get account ID from auth
 pub fn get_account_id(auth: &AuthDotJson) -> Option<String> {
     auth.tokens
@@ -0,0 +1,98 @@
+//! Watches the official Codex CLI `auth.json` for changes made outside the router,
+//! e.g. by running `codex login` directly.
+//!
+//! The file is polled rather than watched through OS notifications: the CLI (and the
+//! router itself) replace it by renaming, which detaches inode-based watches.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::app_state::AppCommand;
+use crate::config::get_official_auth_file;
+
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Detects content changes of a single file between polls.
+#[derive(Debug)]
+pub struct AuthFileWatcher {
+    path: PathBuf,
+    last_seen: Option<u64>,
+}
+
+impl AuthFileWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_seen: None,
+        }
+    }
+
+    /// Returns true when the file exists and its contents differ from the previous poll.
+    /// The first poll reports an existing file so the router can reconcile on startup.
+    pub fn poll(&mut self) -> bool {
+        let Ok(contents) = fs::read(&self.path) else {
+            self.last_seen = None;
+            return false;
+        };
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+        let digest = hasher.finish();
+
+        let changed = self.last_seen != Some(digest);
+        self.last_seen = Some(digest);
+        changed
+    }
+}
+
+/// Poll the official auth file and ask the worker to check it whenever it changes.
+/// The thread exits once the worker's command channel is closed.
+pub fn start_official_auth_watcher(cmd_tx: Sender<AppCommand>) -> Option<JoinHandle<()>> {
+    let path = match get_official_auth_file() {
+        Ok(path) => path,
+        Err(err) => {
+            tracing::warn!(error = %err, "Cannot watch official Codex auth file");
+            return None;
+        }
+    };
+
+    Some(std::thread::spawn(move || {
+        let mut watcher = AuthFileWatcher::new(path);
+        loop {
+            if watcher.poll() && cmd_tx.send(AppCommand::CheckOfficialAuth).is_err() {
+                break;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_existing_file_then_only_changes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("auth.json");
+        let mut watcher = AuthFileWatcher::new(path.clone());
+
+        assert!(!watcher.poll());
+
+        fs::write(&path, "one").unwrap();
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        fs::write(&path, "two").unwrap();
+        assert!(watcher.poll());
+
+        fs::remove_file(&path).unwrap();
+        assert!(!watcher.poll());
+        fs::write(&path, "two").unwrap();
+        assert!(watcher.poll());
+    }
+}
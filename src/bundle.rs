@@ -0,0 +1,288 @@
+//! Passphrase-encrypted bundles for moving profiles between machines.
+//!
+//! A bundle holds the auth and metadata of the selected profiles. Importing merges
+//! by account id, or by key for API-key profiles: known accounts are updated only
+//! when the bundled tokens are newer, everything else becomes a new profile under its
+//! bundled name.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::atomic_file::write_atomic;
+use crate::auth::AuthDotJson;
+use crate::profile::{self, AuthMatch, SaveProfileOutcome};
+use crate::profile_meta::{self, ProfileMeta};
+use crate::secret_store::{self, Passphrase, PassphraseSealed};
+
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BundleFile {
+    #[serde(rename = "codex_router_bundle")]
+    version: u32,
+    #[serde(flatten)]
+    sealed: PassphraseSealed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleProfile {
+    pub name: String,
+    pub auth: AuthDotJson,
+    #[serde(default)]
+    pub meta: ProfileMeta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleContents {
+    pub exported_at: DateTime<Utc>,
+    pub profiles: Vec<BundleProfile>,
+}
+
+/// Collect the named profiles into bundle contents.
+pub fn collect_profiles(names: &[String]) -> Result<BundleContents> {
+    if names.is_empty() {
+        anyhow::bail!("Select at least one profile to export.");
+    }
+
+    let mut profiles = Vec::new();
+    for name in names {
+        // Workspace sub-profiles are exported with their resolved credentials
+        let mut meta = profile_meta::load_meta(name)?;
+        meta.workspace = None;
+        profiles.push(BundleProfile {
+            name: name.clone(),
+            auth: profile::load_profile_auth(name)?,
+            meta,
+        });
+    }
+    Ok(BundleContents {
+        exported_at: Utc::now(),
+        profiles,
+    })
+}
+
+/// Export the named profiles to an encrypted bundle. Returns how many were written.
+pub fn export_profiles(names: &[String], path: &Path, passphrase: &Passphrase) -> Result<usize> {
+    let contents = collect_profiles(names)?;
+    let plaintext = serde_json::to_vec(&contents)?;
+    let sealed = secret_store::seal_with_passphrase(passphrase, &plaintext)?;
+    write_bundle(path, sealed)?;
+    Ok(contents.profiles.len())
+}
+
+fn write_bundle(path: &Path, sealed: PassphraseSealed) -> Result<()> {
+    let file = BundleFile {
+        version: BUNDLE_VERSION,
+        sealed,
+    };
+    write_atomic(path, &serde_json::to_vec_pretty(&file)?)
+        .with_context(|| format!("Failed to write bundle {:?}", path))
+}
+
+pub fn read_bundle(path: &Path, passphrase: &Passphrase) -> Result<BundleContents> {
+    let raw = fs::read(path).with_context(|| format!("Failed to read bundle {:?}", path))?;
+    let file: BundleFile =
+        serde_json::from_slice(&raw).context("Not a Codex Router profile bundle")?;
+    if file.version != BUNDLE_VERSION {
+        anyhow::bail!("Unsupported bundle version {}", file.version);
+    }
+    let plaintext = secret_store::open_with_passphrase(passphrase, &file.sealed)?;
+    serde_json::from_slice(&plaintext).context("Failed to parse bundle contents")
+}
+
+/// Import an encrypted bundle, reporting what happened to each bundled profile.
+pub fn import_bundle(path: &Path, passphrase: &Passphrase) -> Result<Vec<SaveProfileOutcome>> {
+    let contents = read_bundle(path, passphrase)?;
+    import_contents(&contents)
+}
+
+pub fn import_contents(contents: &BundleContents) -> Result<Vec<SaveProfileOutcome>> {
+    let mut outcomes = Vec::new();
+    for bundled in &contents.profiles {
+        let outcome = match profile::match_auth_to_profile(&bundled.auth)? {
+            AuthMatch::Unchanged { name } => SaveProfileOutcome::AlreadyExists { name },
+            AuthMatch::Refreshed { .. } | AuthMatch::New { .. } => {
+                profile::save_auth_as_named_profile(&bundled.auth, &bundled.name)?
+            }
+        };
+        // Local metadata wins for accounts that already exist here.
+        if let SaveProfileOutcome::Created { name } = &outcome {
+            profile_meta::save_meta(name, &bundled.meta)?;
+        }
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+/// One-line summary of an import, e.g. "Imported 3 profiles: 1 created, 1 updated, 1 skipped"
+pub fn format_import_summary(outcomes: &[SaveProfileOutcome]) -> String {
+    let count = |pred: fn(&SaveProfileOutcome) -> bool| outcomes.iter().filter(|o| pred(o)).count();
+    let created = count(|o| matches!(o, SaveProfileOutcome::Created { .. }));
+    let updated = count(|o| matches!(o, SaveProfileOutcome::Updated { .. }));
+    let skipped = count(|o| matches!(o, SaveProfileOutcome::AlreadyExists { .. }));
+    format!(
+        "Imported {} profiles: {created} created, {updated} updated, {skipped} skipped",
+        outcomes.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{self, TokenData};
+    use crate::test_support::{EnvGuard, ENV_LOCK};
+
+    fn account_auth(account_id: &str, access_token: &str) -> AuthDotJson {
+        AuthDotJson {
+            openai_api_key: None,
+            tokens: Some(TokenData {
+                id_token: None,
+                access_token: access_token.to_string(),
+                refresh_token: "refresh".to_string(),
+                account_id: Some(account_id.to_string()),
+            }),
+            last_refresh: None,
+        }
+    }
+
+    fn write_profile(root: &Path, name: &str, auth: &AuthDotJson) {
+        let dir = root.join("profiles").join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("auth.json"), serde_json::to_string(auth).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn bundle_round_trips_with_passphrase() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        write_profile(
+            temp_dir.path(),
+            "work",
+            &account_auth("acct_work", "access"),
+        );
+
+        let passphrase = Passphrase::new("moving day");
+        let contents = collect_profiles(&["work".to_string()]).unwrap();
+        let sealed = secret_store::seal_with_passphrase_params(
+            &passphrase,
+            &serde_json::to_vec(&contents).unwrap(),
+            256,
+            1,
+            1,
+        )
+        .unwrap();
+        let path = temp_dir.path().join("profiles.bundle");
+        write_bundle(&path, sealed).unwrap();
+
+        assert!(!fs::read_to_string(&path).unwrap().contains("access"));
+        let opened = read_bundle(&path, &passphrase).unwrap();
+        assert_eq!(
+            serde_json::to_value(&opened).unwrap(),
+            serde_json::to_value(&contents).unwrap()
+        );
+        assert!(read_bundle(&path, &Passphrase::new("wrong")).is_err());
+    }
+
+    #[test]
+    fn import_merges_by_account_id() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        write_profile(
+            temp_dir.path(),
+            "same",
+            &account_auth("acct_same", "access"),
+        );
+        write_profile(temp_dir.path(), "rotated", &account_auth("acct_rot", "old"));
+        write_profile(
+            temp_dir.path(),
+            "laptop",
+            &account_auth("acct_other", "access"),
+        );
+
+        let bundled = |name: &str, auth: AuthDotJson| BundleProfile {
+            name: name.to_string(),
+            auth,
+            meta: ProfileMeta {
+                notes: format!("from {name}"),
+                ..ProfileMeta::default()
+            },
+        };
+        let contents = BundleContents {
+            exported_at: Utc::now(),
+            profiles: vec![
+                bundled("same-elsewhere", account_auth("acct_same", "access")),
+                bundled("rotated", account_auth("acct_rot", "new")),
+                bundled("laptop", account_auth("acct_new", "access")),
+            ],
+        };
+
+        let outcomes = import_contents(&contents).unwrap();
+
+        assert_eq!(
+            outcomes,
+            vec![
+                SaveProfileOutcome::AlreadyExists {
+                    name: "same".to_string()
+                },
+                SaveProfileOutcome::Updated {
+                    name: "rotated".to_string()
+                },
+                SaveProfileOutcome::Created {
+                    name: "laptop-2".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            profile_meta::load_meta("laptop-2").unwrap().notes,
+            "from laptop"
+        );
+        assert_eq!(profile_meta::load_meta("rotated").unwrap().notes, "");
+        assert_eq!(
+            format_import_summary(&outcomes),
+            "Imported 3 profiles: 1 created, 1 updated, 1 skipped"
+        );
+    }
+
+    #[test]
+    fn reimporting_api_key_profiles_skips_known_keys() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        write_profile(temp_dir.path(), "paid", &auth::api_key_auth("sk-paid"));
+
+        let contents = collect_profiles(&["paid".to_string()]).unwrap();
+        let outcomes = import_contents(&contents).unwrap();
+        assert_eq!(
+            outcomes,
+            vec![SaveProfileOutcome::AlreadyExists {
+                name: "paid".to_string()
+            }]
+        );
+
+        let other_key = BundleContents {
+            exported_at: Utc::now(),
+            profiles: vec![BundleProfile {
+                name: "paid".to_string(),
+                auth: auth::api_key_auth("sk-other"),
+                meta: ProfileMeta::default(),
+            }],
+        };
+        assert_eq!(
+            import_contents(&other_key).unwrap(),
+            vec![SaveProfileOutcome::Created {
+                name: "paid-2".to_string()
+            }]
+        );
+        assert_eq!(
+            fs::read_dir(temp_dir.path().join("profiles"))
+                .unwrap()
+                .count(),
+            2
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! API keys the router issues to its own clients. Only a hash of each key is stored;
+//! the plaintext is shown once when the key is created.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::profile::ProfileSummary;
+use crate::rate_limit::RateLimits;
+
+/// Prefix of every issued key, so they are easy to tell apart from OpenAI keys
+pub const CLIENT_KEY_PREFIX: &str = "crk-";
+
+/// Characters of the key kept in the clear to identify it in the UI
+const DISPLAY_PREFIX_LEN: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientKey {
+    pub id: String,
+    pub label: String,
+    /// SHA-256 of the key, base64url encoded
+    pub key_hash: String,
+    /// Start of the key, e.g. `crk-a1B2c3`
+    pub display_prefix: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Models the key may request; empty allows all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_models: Vec<String>,
+    /// Only profiles carrying one of these tags serve the key; empty allows the whole pool
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pool_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "RateLimits::is_unlimited")]
+    pub limits: RateLimits,
+}
+
+impl ClientKey {
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+
+    pub fn allows_model(&self, model: &str) -> bool {
+        self.allowed_models.is_empty()
+            || self
+                .allowed_models
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(model))
+    }
+
+    pub fn allows_profile(&self, profile: &ProfileSummary) -> bool {
+        self.pool_tags.is_empty()
+            || profile
+                .meta
+                .tags
+                .iter()
+                .any(|tag| self.pool_tags.contains(tag))
+    }
+}
+
+/// What a key has been used for since the router started
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientKeyUsage {
+    pub requests: u64,
+    pub failed_requests: u64,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Create a key. Returns the plaintext, which is not stored anywhere, and its record.
+pub fn issue_key(
+    label: &str,
+    allowed_models: Vec<String>,
+    pool_tags: Vec<String>,
+    now: DateTime<Utc>,
+) -> (String, ClientKey) {
+    let mut secret = [0u8; 32];
+    rand::rng().fill_bytes(&mut secret);
+    let key = format!("{CLIENT_KEY_PREFIX}{}", URL_SAFE_NO_PAD.encode(secret));
+
+    let mut id_bytes = [0u8; 6];
+    rand::rng().fill_bytes(&mut id_bytes);
+    let record = ClientKey {
+        id: URL_SAFE_NO_PAD.encode(id_bytes),
+        label: label.trim().to_string(),
+        key_hash: hash_key(&key),
+        display_prefix: key.chars().take(DISPLAY_PREFIX_LEN).collect(),
+        created_at: now,
+        revoked_at: None,
+        allowed_models,
+        pool_tags,
+        limits: RateLimits::default(),
+    };
+    (key, record)
+}
+
+pub fn hash_key(key: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(key.as_bytes()))
+}
+
+/// The active key matching a presented bearer token
+pub fn find_active_key<'a>(keys: &'a [ClientKey], presented: &str) -> Option<&'a ClientKey> {
+    let presented_hash = hash_key(presented);
+    keys.iter()
+        .filter(|key| key.is_active())
+        .find(|key| constant_time_eq(key.key_hash.as_bytes(), presented_hash.as_bytes()))
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile_meta::parse_tags;
+
+    #[test]
+    fn issued_keys_are_found_by_hash_until_revoked() {
+        let (key, record) = issue_key(" ci ", vec![], vec![], Utc::now());
+        assert!(key.starts_with(CLIENT_KEY_PREFIX));
+        assert!(key.starts_with(&record.display_prefix));
+        assert_eq!(record.label, "ci");
+        assert_ne!(record.key_hash, key);
+
+        let mut keys = vec![record];
+        assert_eq!(
+            find_active_key(&keys, &key).map(|k| k.label.as_str()),
+            Some("ci")
+        );
+        assert!(find_active_key(&keys, "crk-wrong").is_none());
+
+        keys[0].revoked_at = Some(Utc::now());
+        assert!(find_active_key(&keys, &key).is_none());
+    }
+
+    #[test]
+    fn restrictions_limit_models_and_pool() {
+        let (_, key) = issue_key(
+            "team",
+            parse_tags("gpt-5, gpt-5-mini,"),
+            parse_tags("team"),
+            Utc::now(),
+        );
+        assert!(key.allows_model("GPT-5"));
+        assert!(!key.allows_model("o3"));
+
+        let mut profile = ProfileSummary {
+            name: "work".to_string(),
+            email: None,
+            is_current: false,
+            is_valid: true,
+            quota: None,
+            quota_fetched_at: None,
+            quota_stale: false,
+            meta: Default::default(),
+            health: Default::default(),
+            auth_kind: Default::default(),
+        };
+        assert!(!key.allows_profile(&profile));
+        profile.meta.tags = vec!["team".to_string()];
+        assert!(key.allows_profile(&profile));
+    }
+}
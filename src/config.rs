@@ -38,6 +38,12 @@ pub fn get_profiles_dir() -> Result<PathBuf> {
     Ok(codex_home.join("profiles"))
 }
 
+/// Get the metadata file path for a profile
+pub fn get_profile_meta_file(profile_name: &str) -> Result<PathBuf> {
+    let profiles_dir = get_profiles_dir()?;
+    Ok(profiles_dir.join(profile_name).join("meta.json"))
+}
+
 /// Get the current auth file path
 pub fn get_auth_file() -> Result<PathBuf> {
     let codex_home = get_codex_home()?;
@@ -62,6 +68,60 @@ pub fn get_router_state_file() -> Result<PathBuf> {
     Ok(config_dir.join("state.json"))
 }
 
+/// Get the quota history file path
+pub fn get_quota_history_file() -> Result<PathBuf> {
+    let config_dir = get_router_config_dir()?;
+    Ok(config_dir.join("quota_history.json"))
+}
+
+/// Get the quota cache file path
+pub fn get_quota_cache_file() -> Result<PathBuf> {
+    let config_dir = get_router_config_dir()?;
+    Ok(config_dir.join("quota_cache.json"))
+}
+
+/// Get the directory deleted profiles are moved to
+pub fn get_trash_dir() -> Result<PathBuf> {
+    let config_dir = get_router_config_dir()?;
+    Ok(config_dir.join("trash"))
+}
+
+/// Get the self-signed TLS certificate path
+pub fn get_tls_cert_file() -> Result<PathBuf> {
+    let config_dir = get_router_config_dir()?;
+    Ok(config_dir.join("tls").join("cert.pem"))
+}
+
+/// Get the self-signed TLS private key path
+pub fn get_tls_key_file() -> Result<PathBuf> {
+    let config_dir = get_router_config_dir()?;
+    Ok(config_dir.join("tls").join("key.pem"))
+}
+
+/// Get the default Unix socket path for the API server
+pub fn get_api_socket_file() -> Result<PathBuf> {
+    let config_dir = get_router_config_dir()?;
+    Ok(config_dir.join("api.sock"))
+}
+
+/// Get the admin API token file path
+pub fn get_admin_token_file() -> Result<PathBuf> {
+    let config_dir = get_router_config_dir()?;
+    Ok(config_dir.join("admin.token"))
+}
+
+/// Get the request log file path
+pub fn get_request_log_file() -> Result<PathBuf> {
+    let config_dir = get_router_config_dir()?;
+    Ok(config_dir.join("requests.jsonl"))
+}
+
+/// Get the credential vault config file path
+pub fn get_vault_file() -> Result<PathBuf> {
+    let config_dir = get_router_config_dir()?;
+    Ok(config_dir.join("vault.json"))
+}
+
 pub const DEFAULT_USER_AGENT: &str = "codex-cli";
 
 pub fn default_user_agent() -> String {
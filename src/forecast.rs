@@ -0,0 +1,360 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+use crate::history::{QuotaHistory, QuotaSample};
+use crate::profile::ProfileSummary;
+
+/// Only samples and proxied traffic from this far back feed the burn rate
+const LOOKBACK_HOURS: i64 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowForecast {
+    /// Not enough samples to estimate a burn rate
+    Unknown,
+    /// Usage is flat, nothing is being consumed
+    Idle,
+    /// Projected to hit 100% before the window resets
+    ExhaustsAt(DateTime<Utc>),
+    /// Projected to still have headroom when the window resets
+    SurvivesUntilReset,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileForecast {
+    pub primary: WindowForecast,
+    pub secondary: WindowForecast,
+    /// Percent of the primary window consumed per hour
+    pub primary_rate: Option<f64>,
+    /// Percent of the secondary window consumed per hour
+    pub secondary_rate: Option<f64>,
+}
+
+impl ProfileForecast {
+    /// Earliest time any window is projected to run out before its reset
+    pub fn exhausts_at(&self) -> Option<DateTime<Utc>> {
+        [self.primary, self.secondary]
+            .into_iter()
+            .filter_map(|window| match window {
+                WindowForecast::ExhaustsAt(at) => Some(at),
+                _ => None,
+            })
+            .min()
+    }
+
+    pub fn survives_until_reset(&self) -> bool {
+        self.exhausts_at().is_none()
+            && (self.primary != WindowForecast::Unknown
+                || self.secondary != WindowForecast::Unknown)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolForecast {
+    /// When the combined secondary-window headroom of all accounts runs out
+    pub exhausts_at: Option<DateTime<Utc>>,
+    /// Earliest secondary-window reset across the pool
+    pub next_reset: Option<DateTime<Utc>>,
+    pub survives_until_reset: bool,
+}
+
+/// Forecast every profile that has a quota, keyed by profile name.
+pub fn forecast_profiles(
+    profiles: &[ProfileSummary],
+    history: &QuotaHistory,
+    proxied_requests: &HashMap<String, Vec<DateTime<Utc>>>,
+    now: DateTime<Utc>,
+) -> HashMap<String, ProfileForecast> {
+    profiles
+        .iter()
+        .filter_map(|profile| {
+            let quota = profile.quota.as_ref()?;
+            let samples = history.samples(&profile.name);
+            let requests = proxied_requests
+                .get(&profile.name)
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+
+            let primary_rate = burn_rate(samples, requests, now, |s| s.primary_used);
+            let secondary_rate = burn_rate(samples, requests, now, |s| s.secondary_used);
+            let forecast = ProfileForecast {
+                primary: project_window(
+                    quota.primary_used_percent(),
+                    primary_rate,
+                    parse_reset(quota.primary_resets_at()),
+                    now,
+                ),
+                secondary: project_window(
+                    quota.secondary_used_percent(),
+                    secondary_rate,
+                    parse_reset(quota.secondary_resets_at()),
+                    now,
+                ),
+                primary_rate,
+                secondary_rate,
+            };
+            Some((profile.name.clone(), forecast))
+        })
+        .collect()
+}
+
+/// Forecast whether the pool's combined secondary-window headroom outlasts the next reset.
+pub fn forecast_pool(
+    profiles: &[ProfileSummary],
+    forecasts: &HashMap<String, ProfileForecast>,
+    now: DateTime<Utc>,
+) -> Option<PoolForecast> {
+    let mut remaining = 0.0;
+    let mut rate = 0.0;
+    let mut next_reset: Option<DateTime<Utc>> = None;
+    let mut known = false;
+
+    for profile in profiles.iter().filter(|p| p.is_valid) {
+        let Some(quota) = &profile.quota else {
+            continue;
+        };
+        let Some(used) = quota.secondary_used_percent() else {
+            continue;
+        };
+        remaining += 100.0 - used.min(100) as f64;
+        if let Some(profile_rate) = forecasts
+            .get(&profile.name)
+            .and_then(|forecast| forecast.secondary_rate)
+        {
+            rate += profile_rate;
+            known = true;
+        }
+        if let Some(reset) = parse_reset(quota.secondary_resets_at()) {
+            next_reset = Some(next_reset.map_or(reset, |current| current.min(reset)));
+        }
+    }
+
+    if !known {
+        return None;
+    }
+
+    let exhausts_at = (rate > 0.0).then(|| now + hours(remaining / rate));
+    let survives_until_reset = match (exhausts_at, next_reset) {
+        (None, _) => true,
+        (Some(exhausts_at), Some(next_reset)) => exhausts_at >= next_reset,
+        (Some(_), None) => false,
+    };
+
+    Some(PoolForecast {
+        exhausts_at,
+        next_reset,
+        survives_until_reset,
+    })
+}
+
+/// Percent-per-hour burn rate for one window.
+///
+/// The snapshot trend covers only the samples since the window last reset. When
+/// proxied traffic overlaps that span, the observed cost per request is scaled by
+/// the last hour's request rate, and the higher of the two estimates wins.
+fn burn_rate(
+    samples: &[QuotaSample],
+    requests: &[DateTime<Utc>],
+    now: DateTime<Utc>,
+    used: impl Fn(&QuotaSample) -> Option<u64>,
+) -> Option<f64> {
+    let cutoff = now - Duration::hours(LOOKBACK_HOURS);
+    let points: Vec<(DateTime<Utc>, f64)> = samples
+        .iter()
+        .filter(|sample| sample.timestamp >= cutoff)
+        .filter_map(|sample| used(sample).map(|value| (sample.timestamp, value as f64)))
+        .collect();
+
+    // Keep only the run after the most recent drop, i.e. after the window reset.
+    let start = points
+        .windows(2)
+        .rposition(|pair| pair[1].1 < pair[0].1)
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    let segment = &points[start..];
+    let (first, last) = (segment.first()?, segment.last()?);
+
+    let span_hours = (last.0 - first.0).num_seconds() as f64 / 3600.0;
+    if span_hours <= 0.0 {
+        return None;
+    }
+    let consumed = last.1 - first.1;
+    let snapshot_rate = consumed / span_hours;
+
+    let requests_in_span = requests
+        .iter()
+        .filter(|at| **at >= first.0 && **at <= last.0)
+        .count();
+    let traffic_rate = if requests_in_span > 0 && consumed > 0.0 {
+        let per_request = consumed / requests_in_span as f64;
+        let last_hour = requests
+            .iter()
+            .filter(|at| **at >= now - Duration::hours(1))
+            .count();
+        per_request * last_hour as f64
+    } else {
+        0.0
+    };
+
+    Some(snapshot_rate.max(traffic_rate))
+}
+
+fn project_window(
+    used: Option<u64>,
+    rate: Option<f64>,
+    reset: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> WindowForecast {
+    let (Some(used), Some(rate)) = (used, rate) else {
+        return WindowForecast::Unknown;
+    };
+    if used >= 100 {
+        return WindowForecast::ExhaustsAt(now);
+    }
+    if rate <= 0.0 {
+        return WindowForecast::Idle;
+    }
+
+    let exhausts_at = now + hours((100 - used) as f64 / rate);
+    match reset {
+        Some(reset) if exhausts_at >= reset => WindowForecast::SurvivesUntilReset,
+        _ => WindowForecast::ExhaustsAt(exhausts_at),
+    }
+}
+
+fn parse_reset(value: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value?)
+        .ok()
+        .map(|value| value.with_timezone(&Utc))
+}
+
+fn hours(value: f64) -> Duration {
+    Duration::seconds((value * 3600.0).round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{QuotaInfo, RateLimitWindow};
+
+    fn sample(now: DateTime<Utc>, hours_ago: i64, primary: u64, secondary: u64) -> QuotaSample {
+        QuotaSample {
+            timestamp: now - Duration::hours(hours_ago),
+            primary_used: Some(primary),
+            secondary_used: Some(secondary),
+        }
+    }
+
+    fn profile(name: &str, primary: u64, secondary: u64, reset: Option<String>) -> ProfileSummary {
+        ProfileSummary {
+            name: name.to_string(),
+            email: None,
+            is_current: false,
+            is_valid: true,
+            quota: Some(QuotaInfo {
+                account_id: "id".to_string(),
+                email: "email".to_string(),
+                plan_type: "plan".to_string(),
+                primary: Some(RateLimitWindow {
+                    used_percent: primary as f64,
+                    window_seconds: Some(5 * 3600),
+                    resets_at: reset.clone(),
+                }),
+                secondary: Some(RateLimitWindow {
+                    used_percent: secondary as f64,
+                    window_seconds: Some(7 * 24 * 3600),
+                    resets_at: reset,
+                }),
+                credits: None,
+                usage: None,
+            }),
+            quota_fetched_at: None,
+            quota_stale: false,
+            meta: Default::default(),
+            health: Default::default(),
+            auth_kind: Default::default(),
+        }
+    }
+
+    #[test]
+    fn burn_rate_ignores_samples_before_reset() {
+        let now = Utc::now();
+        let samples = vec![
+            sample(now, 4, 80, 0),
+            sample(now, 3, 90, 0),
+            sample(now, 2, 10, 0),
+            sample(now, 0, 30, 0),
+        ];
+
+        let rate = burn_rate(&samples, &[], now, |s| s.primary_used).unwrap();
+
+        assert!((rate - 10.0).abs() < 0.01, "unexpected rate {rate}");
+    }
+
+    #[test]
+    fn burn_rate_uses_recent_proxied_traffic_when_higher() {
+        let now = Utc::now();
+        let samples = vec![sample(now, 3, 10, 0), sample(now, 1, 20, 0)];
+        // Two requests over the sampled span cost 10%; six more in the last hour.
+        let mut requests = vec![now - Duration::minutes(150), now - Duration::minutes(120)];
+        requests.extend((0..6).map(|i| now - Duration::minutes(i * 5)));
+
+        let rate = burn_rate(&samples, &requests, now, |s| s.primary_used).unwrap();
+
+        assert!((rate - 30.0).abs() < 0.01, "unexpected rate {rate}");
+    }
+
+    #[test]
+    fn forecast_reports_exhaustion_before_reset() {
+        let now = Utc::now();
+        let reset = (now + Duration::hours(10)).to_rfc3339();
+        let profiles = vec![profile("work", 50, 50, Some(reset))];
+        let mut history = QuotaHistory::default();
+        history.profiles.insert(
+            "work".to_string(),
+            vec![sample(now, 2, 30, 48), sample(now, 0, 50, 50)],
+        );
+
+        let forecasts = forecast_profiles(&profiles, &history, &HashMap::new(), now);
+        let forecast = &forecasts["work"];
+
+        let expected = now + Duration::hours(5);
+        assert_eq!(forecast.primary, WindowForecast::ExhaustsAt(expected));
+        assert_eq!(forecast.secondary, WindowForecast::SurvivesUntilReset);
+        assert_eq!(forecast.exhausts_at(), Some(expected));
+        assert!(!forecast.survives_until_reset());
+    }
+
+    #[test]
+    fn forecast_is_unknown_without_history() {
+        let now = Utc::now();
+        let profiles = vec![profile("work", 50, 50, None)];
+
+        let forecasts =
+            forecast_profiles(&profiles, &QuotaHistory::default(), &HashMap::new(), now);
+
+        assert_eq!(forecasts["work"].primary, WindowForecast::Unknown);
+        assert!(!forecasts["work"].survives_until_reset());
+    }
+
+    #[test]
+    fn pool_survives_when_combined_headroom_outlasts_reset() {
+        let now = Utc::now();
+        let reset = (now + Duration::hours(4)).to_rfc3339();
+        let profiles = vec![
+            profile("a", 0, 90, Some(reset.clone())),
+            profile("b", 0, 50, Some(reset)),
+        ];
+        let mut history = QuotaHistory::default();
+        history.profiles.insert(
+            "a".to_string(),
+            vec![sample(now, 1, 0, 85), sample(now, 0, 0, 90)],
+        );
+
+        let forecasts = forecast_profiles(&profiles, &history, &HashMap::new(), now);
+        let pool = forecast_pool(&profiles, &forecasts, now).unwrap();
+
+        // 60% headroom at 5%/h lasts 12h, past the 4h reset.
+        assert_eq!(pool.exhausts_at, Some(now + Duration::hours(12)));
+        assert!(pool.survives_until_reset);
+    }
+}
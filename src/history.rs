@@ -0,0 +1,243 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::api::QuotaInfo;
+use crate::atomic_file::write_atomic;
+use crate::config::get_quota_history_file;
+
+/// Maximum number of samples kept per profile
+pub const MAX_SAMPLES_PER_PROFILE: usize = 2000;
+
+/// Samples older than this are dropped on every write
+pub const RETENTION_DAYS: i64 = 30;
+
+/// A single quota poll result for one profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaSample {
+    pub timestamp: DateTime<Utc>,
+    pub primary_used: Option<u64>,
+    pub secondary_used: Option<u64>,
+}
+
+/// Timestamped quota samples keyed by profile name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaHistory {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Vec<QuotaSample>>,
+}
+
+impl QuotaHistory {
+    /// Append a sample for the profile. Quotas without any rate-limit window
+    /// (e.g. the fallback quota) are ignored.
+    pub fn record(&mut self, profile_name: &str, quota: &QuotaInfo, now: DateTime<Utc>) -> bool {
+        let primary_used = quota.primary_used_percent();
+        let secondary_used = quota.secondary_used_percent();
+        if primary_used.is_none() && secondary_used.is_none() {
+            return false;
+        }
+
+        let samples = self.profiles.entry(profile_name.to_string()).or_default();
+        samples.push(QuotaSample {
+            timestamp: now,
+            primary_used,
+            secondary_used,
+        });
+        true
+    }
+
+    /// Drop samples outside the retention window and cap each profile's length.
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - Duration::days(RETENTION_DAYS);
+        for samples in self.profiles.values_mut() {
+            samples.retain(|sample| sample.timestamp >= cutoff);
+            samples.sort_by_key(|sample| sample.timestamp);
+            if samples.len() > MAX_SAMPLES_PER_PROFILE {
+                let excess = samples.len() - MAX_SAMPLES_PER_PROFILE;
+                samples.drain(..excess);
+            }
+        }
+        self.profiles.retain(|_, samples| !samples.is_empty());
+    }
+
+    /// Move a profile's samples to its new name
+    pub fn rename(&mut self, profile_name: &str, new_name: &str) {
+        if let Some(samples) = self.profiles.remove(profile_name) {
+            self.profiles.insert(new_name.to_string(), samples);
+        }
+    }
+
+    pub fn samples(&self, profile_name: &str) -> &[QuotaSample] {
+        self.profiles
+            .get(profile_name)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+}
+
+pub fn load_history() -> Result<QuotaHistory> {
+    let history_file = get_quota_history_file()?;
+    if !history_file.exists() {
+        return Ok(QuotaHistory::default());
+    }
+
+    let contents = fs::read_to_string(&history_file)
+        .with_context(|| format!("Failed to read quota history: {:?}", history_file))?;
+    let history: QuotaHistory =
+        serde_json::from_str(&contents).with_context(|| "Failed to parse quota history")?;
+    Ok(history)
+}
+
+pub fn save_history(history: &QuotaHistory) -> Result<()> {
+    let history_file = get_quota_history_file()?;
+    if let Some(parent) = history_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let contents = serde_json::to_string(history)?;
+    write_atomic(&history_file, contents.as_bytes())?;
+    Ok(())
+}
+
+/// Carry a renamed profile's history over to its new name.
+pub fn rename_profile(profile_name: &str, new_name: &str) -> Result<()> {
+    let mut history = load_history()?;
+    if !history.profiles.contains_key(profile_name) {
+        return Ok(());
+    }
+    history.rename(profile_name, new_name);
+    save_history(&history)
+}
+
+/// Record a quota snapshot for a profile and persist the pruned history.
+pub fn record_quota(profile_name: &str, quota: &QuotaInfo) -> Result<()> {
+    record_quotas(&[(profile_name.to_string(), quota.clone())])
+}
+
+/// Record the snapshots of one refresh with a single write of the pruned history.
+pub fn record_quotas(samples: &[(String, QuotaInfo)]) -> Result<()> {
+    if samples.is_empty() {
+        return Ok(());
+    }
+    let now = Utc::now();
+    let mut history = load_history()?;
+    let mut recorded = false;
+    for (profile_name, quota) in samples {
+        recorded |= history.record(profile_name, quota, now);
+    }
+    if !recorded {
+        return Ok(());
+    }
+    history.prune(now);
+    save_history(&history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::RateLimitWindow;
+    use crate::test_support::{EnvGuard, ENV_LOCK};
+
+    fn quota(primary: Option<u64>, secondary: Option<u64>) -> QuotaInfo {
+        let window = |used: u64| RateLimitWindow {
+            used_percent: used as f64,
+            window_seconds: None,
+            resets_at: None,
+        };
+        QuotaInfo {
+            account_id: "acct_123".to_string(),
+            email: "user@example.com".to_string(),
+            plan_type: "pro".to_string(),
+            primary: primary.map(window),
+            secondary: secondary.map(window),
+            credits: None,
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn record_skips_quota_without_usage() {
+        let mut history = QuotaHistory::default();
+
+        let recorded = history.record("work", &quota(None, None), Utc::now());
+
+        assert!(!recorded);
+        assert!(history.samples("work").is_empty());
+    }
+
+    #[test]
+    fn prune_drops_old_samples_and_caps_length() {
+        let now = Utc::now();
+        let mut history = QuotaHistory::default();
+        history.record(
+            "work",
+            &quota(Some(1), Some(1)),
+            now - Duration::days(RETENTION_DAYS + 1),
+        );
+        for i in 0..(MAX_SAMPLES_PER_PROFILE + 5) {
+            history.record(
+                "work",
+                &quota(Some(10), Some(20)),
+                now - Duration::seconds(i as i64),
+            );
+        }
+
+        history.prune(now);
+
+        let samples = history.samples("work");
+        assert_eq!(samples.len(), MAX_SAMPLES_PER_PROFILE);
+        assert!(samples.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        assert_eq!(samples.last().map(|s| s.timestamp), Some(now));
+    }
+
+    #[test]
+    fn record_quota_persists_history() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        record_quota("work", &quota(Some(25), Some(10))).unwrap();
+        record_quota("work", &quota(Some(30), Some(12))).unwrap();
+
+        let history = load_history().unwrap();
+        let samples = history.samples("work");
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].primary_used, Some(30));
+        assert_eq!(samples[1].secondary_used, Some(12));
+    }
+
+    #[test]
+    fn record_quotas_persists_every_profile() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        record_quotas(&[
+            ("work".to_string(), quota(Some(25), Some(10))),
+            ("idle".to_string(), quota(None, None)),
+            ("home".to_string(), quota(Some(40), None)),
+        ])
+        .unwrap();
+
+        let history = load_history().unwrap();
+        assert_eq!(history.samples("work").len(), 1);
+        assert!(history.samples("idle").is_empty());
+        assert_eq!(history.samples("home")[0].primary_used, Some(40));
+    }
+
+    #[test]
+    fn rename_profile_moves_samples() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        record_quota("john-2", &quota(Some(25), Some(10))).unwrap();
+        rename_profile("john-2", "john").unwrap();
+
+        let history = load_history().unwrap();
+        assert!(history.samples("john-2").is_empty());
+        assert_eq!(history.samples("john").len(), 1);
+    }
+}
@@ -1,19 +1,34 @@
+pub mod admin;
 pub mod api;
 pub mod app;
 pub mod app_state;
+pub mod atomic_file;
 pub mod auth;
+pub mod auth_watch;
+pub mod bundle;
+pub mod client_keys;
 pub mod codex_types;
 pub mod config;
 pub mod dock;
+pub mod forecast;
+pub mod history;
 pub mod icon;
 pub mod login_output;
+pub mod metrics;
 pub mod oauth;
 pub mod profile;
+pub mod profile_meta;
+pub mod quota_cache;
+pub mod rate_limit;
 pub mod refresh;
+pub mod request_log;
+pub mod secret_store;
 pub mod server;
 pub mod shared;
 pub mod state;
 #[cfg(test)]
 pub mod test_support;
+pub mod tls;
+pub mod trash;
 pub mod tray;
 pub mod worker;
@@ -1,3 +1,4 @@
+use codex_router::server::BindOverride;
 use codex_router::{app, icon};
 
 // Re-export specific modules if needed locally or just use them from codex_router
@@ -7,6 +8,14 @@ use codex_router::{app, icon};
 fn main() -> eframe::Result<()> {
     tracing_subscriber::fmt::init();
 
+    let bind_override = match BindOverride::from_env_and_args(std::env::args().skip(1)) {
+        Ok(bind_override) => bind_override,
+        Err(err) => {
+            eprintln!("codex_router: {err}");
+            std::process::exit(2);
+        }
+    };
+
     // Initialize Tokio runtime
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -31,6 +40,6 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "Codex Router",
         options,
-        Box::new(|_cc| Ok(Box::new(app::RouterApp::new()))),
+        Box::new(|_cc| Ok(Box::new(app::RouterApp::new(bind_override)))),
     )
 }
@@ -0,0 +1,375 @@
+//! Prometheus metrics for the proxy, rendered in the text exposition format on `/metrics`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::profile::ProfileSummary;
+
+/// Upper bounds in seconds, shared by the latency histograms
+const BUCKETS: [f64; 11] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RequestLabels {
+    model: String,
+    profile: String,
+    status: u16,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Observations per bucket in `BUCKETS`, not cumulative
+    counts: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: Duration) {
+        let seconds = value.as_secs_f64();
+        if let Some(bucket) = BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.counts[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<RequestLabels, u64>>,
+    failovers: Mutex<BTreeMap<String, u64>>,
+    upstream_latency: Mutex<BTreeMap<String, Histogram>>,
+    time_to_first_token: Mutex<BTreeMap<String, Histogram>>,
+    in_flight_streams: AtomicI64,
+    token_refreshes: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+/// Counts a streamed response as in flight until dropped
+#[derive(Debug)]
+pub struct StreamGuard {
+    metrics: Arc<Metrics>,
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        self.metrics
+            .in_flight_streams
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    /// A finished proxy request; `profile` is empty when no profile served it
+    pub fn record_request(&self, model: &str, profile: &str, status: u16) {
+        let labels = RequestLabels {
+            model: model.to_string(),
+            profile: profile.to_string(),
+            status,
+        };
+        *lock(&self.requests).entry(labels).or_default() += 1;
+    }
+
+    /// An upstream attempt that failed, so the router moved on to the next profile
+    pub fn record_failover(&self, profile: &str) {
+        *lock(&self.failovers)
+            .entry(profile.to_string())
+            .or_default() += 1;
+    }
+
+    /// Time until an upstream answered with response headers
+    pub fn observe_upstream_latency(&self, profile: &str, latency: Duration) {
+        lock(&self.upstream_latency)
+            .entry(profile.to_string())
+            .or_default()
+            .observe(latency);
+    }
+
+    /// Time from receiving a request until its first streamed token
+    pub fn observe_time_to_first_token(&self, profile: &str, elapsed: Duration) {
+        lock(&self.time_to_first_token)
+            .entry(profile.to_string())
+            .or_default()
+            .observe(elapsed);
+    }
+
+    pub fn start_stream(self: &Arc<Self>) -> StreamGuard {
+        self.in_flight_streams.fetch_add(1, Ordering::Relaxed);
+        StreamGuard {
+            metrics: self.clone(),
+        }
+    }
+
+    pub fn record_token_refresh(&self, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        *lock(&self.token_refreshes).entry(outcome).or_default() += 1;
+    }
+
+    /// Render every metric, with quota gauges taken from `profiles`
+    pub fn render(&self, profiles: &[ProfileSummary]) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "codex_router_requests_total",
+            "counter",
+            "Proxied chat completion requests by model, serving profile and status.",
+        );
+        for (labels, count) in lock(&self.requests).iter() {
+            let status = labels.status.to_string();
+            sample(
+                &mut out,
+                "codex_router_requests_total",
+                &[
+                    ("model", &labels.model),
+                    ("profile", &labels.profile),
+                    ("status", &status),
+                ],
+                *count as f64,
+            );
+        }
+
+        header(
+            &mut out,
+            "codex_router_failovers_total",
+            "counter",
+            "Failed upstream attempts after which the next profile was tried.",
+        );
+        for (profile, count) in lock(&self.failovers).iter() {
+            sample(
+                &mut out,
+                "codex_router_failovers_total",
+                &[("profile", profile)],
+                *count as f64,
+            );
+        }
+
+        header(
+            &mut out,
+            "codex_router_upstream_latency_seconds",
+            "histogram",
+            "Time until an upstream returned response headers.",
+        );
+        for (profile, histogram) in lock(&self.upstream_latency).iter() {
+            histogram_samples(
+                &mut out,
+                "codex_router_upstream_latency_seconds",
+                profile,
+                histogram,
+            );
+        }
+
+        header(
+            &mut out,
+            "codex_router_time_to_first_token_seconds",
+            "histogram",
+            "Time from receiving a request until the first streamed token.",
+        );
+        for (profile, histogram) in lock(&self.time_to_first_token).iter() {
+            histogram_samples(
+                &mut out,
+                "codex_router_time_to_first_token_seconds",
+                profile,
+                histogram,
+            );
+        }
+
+        header(
+            &mut out,
+            "codex_router_in_flight_streams",
+            "gauge",
+            "Responses currently being streamed to clients.",
+        );
+        sample(
+            &mut out,
+            "codex_router_in_flight_streams",
+            &[],
+            self.in_flight_streams.load(Ordering::Relaxed) as f64,
+        );
+
+        header(
+            &mut out,
+            "codex_router_quota_used_percent",
+            "gauge",
+            "Used share of each profile's rate-limit windows, as last fetched.",
+        );
+        for profile in profiles {
+            let Some(quota) = &profile.quota else {
+                continue;
+            };
+            for (window, limit) in [("primary", &quota.primary), ("secondary", &quota.secondary)] {
+                if let Some(limit) = limit {
+                    sample(
+                        &mut out,
+                        "codex_router_quota_used_percent",
+                        &[("profile", &profile.name), ("window", window)],
+                        limit.used_percent,
+                    );
+                }
+            }
+        }
+
+        header(
+            &mut out,
+            "codex_router_token_refreshes_total",
+            "counter",
+            "OAuth token refresh attempts by outcome.",
+        );
+        for (outcome, count) in lock(&self.token_refreshes).iter() {
+            sample(
+                &mut out,
+                "codex_router_token_refreshes_total",
+                &[("outcome", outcome)],
+                *count as f64,
+            );
+        }
+
+        out
+    }
+}
+
+/// Whether a chunk of an upstream Responses stream carries generated text
+pub fn has_token_delta(chunk: &[u8]) -> bool {
+    chunk.windows(7).any(|window| window == b".delta\"")
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, value)| format!("{key}=\"{}\"", escape_label(value)))
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(out, " {value}");
+}
+
+fn histogram_samples(out: &mut String, name: &str, profile: &str, histogram: &Histogram) {
+    let bucket_name = format!("{name}_bucket");
+    let mut cumulative = 0;
+    for (bound, count) in BUCKETS.iter().zip(histogram.counts) {
+        cumulative += count;
+        sample(
+            out,
+            &bucket_name,
+            &[("profile", profile), ("le", &bound.to_string())],
+            cumulative as f64,
+        );
+    }
+    sample(
+        out,
+        &bucket_name,
+        &[("profile", profile), ("le", "+Inf")],
+        histogram.count as f64,
+    );
+    sample(
+        out,
+        &format!("{name}_sum"),
+        &[("profile", profile)],
+        histogram.sum,
+    );
+    sample(
+        out,
+        &format!("{name}_count"),
+        &[("profile", profile)],
+        histogram.count as f64,
+    );
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{QuotaInfo, RateLimitWindow};
+
+    #[test]
+    fn renders_counters_histograms_and_quota_gauges() {
+        let metrics = Arc::new(Metrics::default());
+        metrics.record_request("gpt-5", "work", 200);
+        metrics.record_request("gpt-5", "work", 200);
+        metrics.record_request("o3", "", 503);
+        metrics.record_failover("personal");
+        metrics.observe_upstream_latency("work", Duration::from_millis(300));
+        metrics.observe_upstream_latency("work", Duration::from_secs(3));
+        metrics.record_token_refresh(false);
+        let stream = metrics.start_stream();
+
+        let profile = ProfileSummary {
+            name: "work".to_string(),
+            quota: Some(QuotaInfo {
+                account_id: "acct".to_string(),
+                email: "work@example.com".to_string(),
+                plan_type: "plus".to_string(),
+                primary: Some(RateLimitWindow {
+                    used_percent: 42.5,
+                    window_seconds: None,
+                    resets_at: None,
+                }),
+                secondary: None,
+                credits: None,
+                usage: None,
+            }),
+            email: None,
+            is_current: false,
+            is_valid: true,
+            quota_fetched_at: None,
+            quota_stale: false,
+            meta: Default::default(),
+            health: Default::default(),
+            auth_kind: Default::default(),
+        };
+        let text = metrics.render(&[profile]);
+
+        assert!(text.contains(
+            "codex_router_requests_total{model=\"gpt-5\",profile=\"work\",status=\"200\"} 2\n"
+        ));
+        assert!(text
+            .contains("codex_router_requests_total{model=\"o3\",profile=\"\",status=\"503\"} 1\n"));
+        assert!(text.contains("codex_router_failovers_total{profile=\"personal\"} 1\n"));
+        assert!(text.contains(
+            "codex_router_upstream_latency_seconds_bucket{profile=\"work\",le=\"0.5\"} 1\n"
+        ));
+        assert!(text.contains(
+            "codex_router_upstream_latency_seconds_bucket{profile=\"work\",le=\"+Inf\"} 2\n"
+        ));
+        assert!(text.contains("codex_router_upstream_latency_seconds_sum{profile=\"work\"} 3.3\n"));
+        assert!(text.contains("codex_router_in_flight_streams 1\n"));
+        assert!(text.contains(
+            "codex_router_quota_used_percent{profile=\"work\",window=\"primary\"} 42.5\n"
+        ));
+        assert!(text.contains("codex_router_token_refreshes_total{outcome=\"failure\"} 1\n"));
+
+        drop(stream);
+        assert!(metrics
+            .render(&[])
+            .contains("codex_router_in_flight_streams 0\n"));
+    }
+
+    #[test]
+    fn detects_text_deltas_in_stream_chunks() {
+        assert!(has_token_delta(
+            b"event: response.output_text.delta\ndata: {\"type\":\"response.output_text.delta\"}\n\n"
+        ));
+        assert!(!has_token_delta(
+            b"data: {\"type\":\"response.created\"}\n\n"
+        ));
+    }
+}
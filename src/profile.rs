@@ -1,8 +1,15 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 
-use crate::auth::{self, AuthDotJson, IdToken};
+use crate::atomic_file::{write_atomic, FileTransaction};
+use crate::auth::{self, AuthDotJson, AuthKind, IdToken};
 use crate::config::{get_auth_file, get_current_profile_file, get_profiles_dir};
+use crate::profile_meta::{self, ProfileMeta, WorkspaceLink};
+use crate::secret_store;
+use crate::trash::{self, TrashEntry};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ProfileSummary {
@@ -11,6 +18,58 @@ pub struct ProfileSummary {
     pub is_current: bool,
     pub is_valid: bool,
     pub quota: Option<crate::api::QuotaInfo>,
+    /// When `quota` was fetched from the usage endpoint
+    pub quota_fetched_at: Option<DateTime<Utc>>,
+    /// `quota` is the last known good value rather than a fresh result
+    pub quota_stale: bool,
+    pub meta: ProfileMeta,
+    pub health: ProfileHealth,
+    pub auth_kind: AuthKind,
+}
+
+/// Problems found when validating a profile directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ProfileHealth {
+    #[default]
+    Ok,
+    MissingAuthFile,
+    /// auth.json exists but could not be read, e.g. while the vault is locked
+    Unreadable(String),
+    ParseError(String),
+    /// auth.json holds neither ChatGPT tokens nor an API key
+    MissingTokens,
+    /// Holds the same account as the named profile, which is the one routed to
+    DuplicateOf(String),
+    /// Workspace sub-profile whose parent profile no longer exists
+    MissingParent(String),
+}
+
+impl ProfileHealth {
+    /// Whether the profile has credentials that can be used at all
+    pub fn is_usable(&self) -> bool {
+        matches!(self, ProfileHealth::Ok | ProfileHealth::DuplicateOf(_))
+    }
+
+    pub fn problem(&self) -> Option<String> {
+        match self {
+            ProfileHealth::Ok => None,
+            ProfileHealth::MissingAuthFile => Some("auth.json is missing".to_string()),
+            ProfileHealth::Unreadable(err) => Some(format!("auth.json is unreadable: {err}")),
+            ProfileHealth::ParseError(err) => Some(format!("auth.json is corrupt: {err}")),
+            ProfileHealth::MissingTokens => Some("auth.json has no tokens or API key".to_string()),
+            ProfileHealth::DuplicateOf(name) => Some(format!("Duplicate account of {name}")),
+            ProfileHealth::MissingParent(name) => {
+                Some(format!("Workspace of missing profile {name}"))
+            }
+        }
+    }
+}
+
+/// Same-account profiles folded into one by [`merge_duplicate_profiles`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateMerge {
+    pub kept: String,
+    pub merged: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -20,6 +79,16 @@ pub enum SaveProfileOutcome {
     AlreadyExists { name: String },
 }
 
+impl SaveProfileOutcome {
+    pub fn name(&self) -> &str {
+        match self {
+            SaveProfileOutcome::Created { name }
+            | SaveProfileOutcome::Updated { name }
+            | SaveProfileOutcome::AlreadyExists { name } => name,
+        }
+    }
+}
+
 pub fn list_profiles_data() -> Result<Vec<ProfileSummary>> {
     let profiles_dir = get_profiles_dir()?;
 
@@ -29,47 +98,214 @@ pub fn list_profiles_data() -> Result<Vec<ProfileSummary>> {
     }
 
     let current_profile = get_current_profile()?;
+    let loaded = load_all_profiles()?;
+    let primaries = duplicate_primaries(&loaded, current_profile.as_deref());
     let mut profiles = Vec::new();
 
-    for entry in fs::read_dir(&profiles_dir)? {
-        let entry = entry?;
-        if !entry.path().is_dir() {
-            continue;
-        }
-
-        let name = entry.file_name().to_string_lossy().to_string();
-        let profile_auth_file = entry.path().join("auth.json");
-        let email = fs::read_to_string(&profile_auth_file)
-            .ok()
-            .and_then(|auth_json| serde_json::from_str::<AuthDotJson>(&auth_json).ok())
-            .and_then(|auth| auth::get_email(&auth));
+    for (name, result) in loaded {
+        let (email, health, auth_kind) = match &result {
+            Ok(auth) => {
+                let health = match primaries.get(&name) {
+                    Some(primary) => ProfileHealth::DuplicateOf(primary.clone()),
+                    None => ProfileHealth::Ok,
+                };
+                (auth::get_email(auth), health, AuthKind::of(auth))
+            }
+            Err(health) => (None, health.clone(), AuthKind::default()),
+        };
+        let meta = profile_meta::load_meta(&name).unwrap_or_else(|err| {
+            tracing::warn!(profile = %name, error = %err, "Failed to load profile metadata");
+            ProfileMeta::default()
+        });
 
         let is_current = current_profile.as_deref() == Some(name.as_str());
         profiles.push(ProfileSummary {
             name,
             email,
             is_current,
-            is_valid: true,
+            is_valid: health.is_usable(),
             quota: None,
+            quota_fetched_at: None,
+            quota_stale: false,
+            meta,
+            health,
+            auth_kind,
         });
     }
 
-    profiles.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(profiles)
 }
 
-/// Load auth data for a specific profile
+/// A profile's credentials, or why they cannot be used
+type LoadedAuth = std::result::Result<AuthDotJson, ProfileHealth>;
+
+/// Read every profile's auth.json, classifying the ones that cannot be used. Sorted by name.
+fn load_all_profiles() -> Result<Vec<(String, LoadedAuth)>> {
+    let profiles_dir = get_profiles_dir()?;
+    let mut loaded = Vec::new();
+    if !profiles_dir.exists() {
+        return Ok(loaded);
+    }
+
+    for entry in fs::read_dir(&profiles_dir)? {
+        let entry = entry?;
+        if !entry.path().is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let result = match workspace_link(&name) {
+            Some(link) => validate_auth_file(&profiles_dir.join(&link.parent).join("auth.json"))
+                .map(|auth| with_account_id(auth, &link.account_id))
+                .map_err(|health| match health {
+                    ProfileHealth::MissingAuthFile => ProfileHealth::MissingParent(link.parent),
+                    other => other,
+                }),
+            None => validate_auth_file(&entry.path().join("auth.json")),
+        };
+        loaded.push((name, result));
+    }
+    loaded.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(loaded)
+}
+
+fn validate_auth_file(path: &Path) -> LoadedAuth {
+    if !path.exists() {
+        return Err(ProfileHealth::MissingAuthFile);
+    }
+    let contents = secret_store::read_secret(path)
+        .map_err(|err| ProfileHealth::Unreadable(format!("{err:#}")))?;
+    let auth: AuthDotJson = serde_json::from_slice(&contents)
+        .map_err(|err| ProfileHealth::ParseError(err.to_string()))?;
+    if auth.tokens.is_none() && auth.openai_api_key.is_none() {
+        return Err(ProfileHealth::MissingTokens);
+    }
+    Ok(auth)
+}
+
+/// Group readable profiles by account id, keeping only accounts held by several profiles
+fn duplicate_groups(loaded: &[(String, LoadedAuth)]) -> Vec<Vec<(&str, &AuthDotJson)>> {
+    let mut groups: Vec<(String, Vec<(&str, &AuthDotJson)>)> = Vec::new();
+    for (name, result) in loaded {
+        let Ok(auth) = result else {
+            continue;
+        };
+        // A workspace sub-profile has no tokens of its own to merge, and routing to its
+        // workspace is the point even when another login reaches the same account
+        if workspace_link(name).is_some() {
+            continue;
+        }
+        let Some(account_id) = auth::get_account_id(auth) else {
+            continue;
+        };
+        match groups.iter_mut().find(|(id, _)| *id == account_id) {
+            Some((_, group)) => group.push((name.as_str(), auth)),
+            None => groups.push((account_id, vec![(name.as_str(), auth)])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, group)| group)
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+/// The profile a group of duplicates is merged into: the current profile when it is one
+/// of them, otherwise the one with the freshest tokens
+fn duplicate_primary<'a>(group: &[(&'a str, &AuthDotJson)], current: Option<&str>) -> &'a str {
+    if let Some((name, _)) = group.iter().find(|(name, _)| Some(*name) == current) {
+        return name;
+    }
+    freshest(group).0
+}
+
+/// The entry with the most recently refreshed tokens; the first one wins ties
+fn freshest<'a, 'b>(group: &[(&'a str, &'b AuthDotJson)]) -> (&'a str, &'b AuthDotJson) {
+    let mut best = group[0];
+    for entry in &group[1..] {
+        if entry.1.last_refresh > best.1.last_refresh {
+            best = *entry;
+        }
+    }
+    best
+}
+
+/// Map each duplicate profile to the profile it duplicates
+fn duplicate_primaries(
+    loaded: &[(String, LoadedAuth)],
+    current: Option<&str>,
+) -> HashMap<String, String> {
+    let mut primaries = HashMap::new();
+    for group in duplicate_groups(loaded) {
+        let primary = duplicate_primary(&group, current);
+        for (name, _) in group {
+            if name != primary {
+                primaries.insert(name.to_string(), primary.to_string());
+            }
+        }
+    }
+    primaries
+}
+
+/// Fold profiles holding the same account into one. The kept profile gets the freshest
+/// tokens of its group; the others are moved to the trash.
+pub fn merge_duplicate_profiles() -> Result<Vec<DuplicateMerge>> {
+    let current_profile = get_current_profile()?;
+    let loaded = load_all_profiles()?;
+    let profiles_dir = get_profiles_dir()?;
+    let now = Utc::now();
+    let mut merges = Vec::new();
+
+    for group in duplicate_groups(&loaded) {
+        let kept = duplicate_primary(&group, current_profile.as_deref());
+        let (_, kept_auth) = group.iter().find(|(name, _)| *name == kept).unwrap();
+        let (_, freshest_auth) = freshest(&group);
+        if token_fingerprint(freshest_auth) != token_fingerprint(kept_auth) {
+            write_auth_file(&profiles_dir.join(kept).join("auth.json"), freshest_auth)?;
+            if current_profile.as_deref() == Some(kept) {
+                auth::save_auth(freshest_auth)?;
+            }
+        }
+
+        let mut merged = Vec::new();
+        for (name, _) in &group {
+            if *name != kept {
+                trash::trash_profile(name, now)?;
+                merged.push(name.to_string());
+            }
+        }
+        merges.push(DuplicateMerge {
+            kept: kept.to_string(),
+            merged,
+        });
+    }
+
+    Ok(merges)
+}
+
+/// Load auth data for a specific profile. Workspace sub-profiles get their parent's
+/// tokens with the workspace's account id.
 pub fn load_profile_auth(profile_name: &str) -> Result<AuthDotJson> {
     let profiles_dir = get_profiles_dir()?;
+    if let Some(link) = workspace_link(profile_name) {
+        let parent_auth_file = profiles_dir.join(&link.parent).join("auth.json");
+        if !parent_auth_file.exists() {
+            anyhow::bail!(
+                "Profile '{}' belongs to missing profile '{}'.",
+                profile_name,
+                link.parent
+            );
+        }
+        let auth = read_auth_file(&parent_auth_file)?;
+        return Ok(with_account_id(auth, &link.account_id));
+    }
+
     let profile_auth_file = profiles_dir.join(profile_name).join("auth.json");
 
     if !profile_auth_file.exists() {
         anyhow::bail!("Profile '{}' not found.", profile_name);
     }
 
-    let auth_json = fs::read_to_string(&profile_auth_file)?;
-    let auth: AuthDotJson = serde_json::from_str(&auth_json)?;
-    Ok(auth)
+    read_auth_file(&profile_auth_file)
 }
 
 /// Save auth data for a specific profile
@@ -81,12 +317,152 @@ pub fn save_profile_auth(profile_name: &str, auth: &AuthDotJson) -> Result<()> {
         anyhow::bail!("Profile '{}' not found.", profile_name);
     }
 
-    let profile_auth_file = profile_dir.join("auth.json");
-    let auth_json = serde_json::to_string_pretty(auth)?;
-    fs::write(&profile_auth_file, auth_json)?;
+    // Refreshed tokens of a workspace sub-profile belong to its parent's login
+    if let Some(link) = workspace_link(profile_name) {
+        let parent_auth_file = profiles_dir.join(&link.parent).join("auth.json");
+        let parent_account_id = auth::get_account_id(&read_auth_file(&parent_auth_file)?);
+        let mut parent_auth = auth.clone();
+        if let Some(tokens) = parent_auth.tokens.as_mut() {
+            tokens.account_id = parent_account_id;
+        }
+        return write_auth_file(&parent_auth_file, &parent_auth);
+    }
+
+    write_auth_file(&profile_dir.join("auth.json"), auth)
+}
+
+fn workspace_link(profile_name: &str) -> Option<WorkspaceLink> {
+    profile_meta::load_meta(profile_name).ok()?.workspace
+}
+
+/// Point the tokens at another ChatGPT account of the same login
+fn with_account_id(mut auth: AuthDotJson, account_id: &str) -> AuthDotJson {
+    if let Some(tokens) = auth.tokens.as_mut() {
+        tokens.account_id = Some(account_id.to_string());
+    }
+    auth
+}
+
+/// Create a sub-profile that shares `parent`'s login but sends `account_id` as its
+/// ChatGPT account, so a team workspace's quota is tracked and routed separately.
+pub fn create_workspace_profile(parent: &str, account_id: &str, label: &str) -> Result<String> {
+    if let Some(link) = workspace_link(parent) {
+        anyhow::bail!(
+            "Profile '{}' is already a workspace of '{}'.",
+            parent,
+            link.parent
+        );
+    }
+    let parent_auth = load_profile_auth(parent)?;
+    if parent_auth.tokens.is_none() {
+        anyhow::bail!(
+            "Workspaces need a ChatGPT login; '{}' uses an API key.",
+            parent
+        );
+    }
+    if auth::get_account_id(&parent_auth).as_deref() == Some(account_id) {
+        anyhow::bail!("Profile '{}' already uses this account.", parent);
+    }
+
+    let link = WorkspaceLink {
+        parent: parent.to_string(),
+        account_id: account_id.to_string(),
+    };
+    let profiles_dir = get_profiles_dir()?;
+    for entry in fs::read_dir(&profiles_dir)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if workspace_link(&name).as_ref() == Some(&link) {
+            anyhow::bail!("This workspace is already added as '{}'.", name);
+        }
+    }
+
+    let base_name = sanitize_profile_name(&format!("{parent}-{label}"));
+    let mut name = base_name.clone();
+    let mut attempt = 1;
+    while profiles_dir.join(&name).exists() {
+        attempt += 1;
+        name = format!("{base_name}-{attempt}");
+    }
+    fs::create_dir(profiles_dir.join(&name))?;
+    let meta = ProfileMeta {
+        created_at: Some(Utc::now()),
+        workspace: Some(link),
+        ..ProfileMeta::default()
+    };
+    if let Err(err) = profile_meta::save_meta(&name, &meta) {
+        let _ = fs::remove_dir_all(profiles_dir.join(&name));
+        return Err(err);
+    }
+    Ok(name)
+}
+
+/// Create a profile that authenticates with a platform API key instead of a ChatGPT login.
+/// The name is normalized with `sanitize_profile_name`; returns the name used.
+pub fn create_api_key_profile(name: &str, api_key: &str) -> Result<String> {
+    let api_key = api_key.trim();
+    if !api_key.starts_with("sk-") {
+        anyhow::bail!("API keys start with 'sk-'.");
+    }
+    if !name.chars().any(|ch| ch.is_ascii_alphanumeric()) {
+        anyhow::bail!("Profile name must contain letters or digits.");
+    }
+    let name = sanitize_profile_name(name);
+
+    for (existing, result) in load_all_profiles()? {
+        let Ok(auth) = result else {
+            continue;
+        };
+        if AuthKind::of(&auth) == AuthKind::ApiKey
+            && auth.openai_api_key.as_deref() == Some(api_key)
+        {
+            anyhow::bail!("This API key is already saved as '{}'.", existing);
+        }
+    }
+
+    let profiles_dir = get_profiles_dir()?;
+    fs::create_dir_all(&profiles_dir)?;
+    let profile_dir = profiles_dir.join(&name);
+    if profile_dir.exists() {
+        anyhow::bail!("Profile '{}' already exists.", name);
+    }
+    fs::create_dir(&profile_dir)?;
+    write_auth_file(&profile_dir.join("auth.json"), &auth::api_key_auth(api_key))?;
+    init_profile_meta(&name)?;
+    Ok(name)
+}
+
+/// Keep workspace sub-profiles attached to a renamed parent
+fn relink_workspaces(old_parent: &str, new_parent: &str) -> Result<()> {
+    for entry in fs::read_dir(get_profiles_dir()?)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        let mut meta = match profile_meta::load_meta(&name) {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+        if let Some(link) = meta
+            .workspace
+            .as_mut()
+            .filter(|link| link.parent == old_parent)
+        {
+            link.parent = new_parent.to_string();
+            profile_meta::save_meta(&name, &meta)?;
+        }
+    }
     Ok(())
 }
 
+/// Read a profile auth file, decrypting it when the credential vault sealed it
+fn read_auth_file(path: &Path) -> Result<AuthDotJson> {
+    let contents = secret_store::read_secret(path)?;
+    Ok(serde_json::from_slice(&contents)?)
+}
+
+/// Write a profile auth file through the active credential backend
+fn write_auth_file(path: &Path, auth: &AuthDotJson) -> Result<()> {
+    let json = serde_json::to_string_pretty(auth)?;
+    secret_store::write_secret(path, json.as_bytes())
+}
+
 fn token_fingerprint(auth: &AuthDotJson) -> Option<String> {
     if let Some(key) = &auth.openai_api_key {
         return Some(key.clone());
@@ -323,21 +699,298 @@ mod tests {
             Some("acct_123")
         );
     }
-}
 
-/// Switch to a profile
-pub async fn switch_profile(profile_name: &str) -> Result<()> {
-    let profiles_dir = get_profiles_dir()?;
+    #[test]
+    fn match_auth_ignores_tokens_older_than_the_profile() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
 
-    let profile_auth_file = profiles_dir.join(profile_name).join("auth.json");
+        let auth_with = |access: &str, refreshed_hours_ago: i64| AuthDotJson {
+            openai_api_key: None,
+            tokens: Some(TokenData {
+                id_token: None,
+                access_token: access.to_string(),
+                refresh_token: "refresh".to_string(),
+                account_id: Some("acct_123".to_string()),
+            }),
+            last_refresh: Some(Utc::now() - chrono::Duration::hours(refreshed_hours_ago)),
+        };
+        let profile_dir = temp_dir.path().join("profiles").join("work");
+        fs::create_dir_all(&profile_dir).unwrap();
+        fs::write(
+            profile_dir.join("auth.json"),
+            serde_json::to_string(&auth_with("current", 1)).unwrap(),
+        )
+        .unwrap();
 
-    if !profile_auth_file.exists() {
-        anyhow::bail!("Profile '{}' not found.", profile_name);
+        let work = "work".to_string();
+        assert_eq!(
+            match_auth_to_profile(&auth_with("stale", 2)).unwrap(),
+            AuthMatch::Unchanged { name: work.clone() }
+        );
+        assert_eq!(
+            match_auth_to_profile(&auth_with("fresh", 0)).unwrap(),
+            AuthMatch::Refreshed { name: work }
+        );
+    }
+
+    fn account_auth(account_id: &str, access: &str, refreshed_hours_ago: i64) -> AuthDotJson {
+        AuthDotJson {
+            openai_api_key: None,
+            tokens: Some(TokenData {
+                id_token: None,
+                access_token: access.to_string(),
+                refresh_token: "refresh".to_string(),
+                account_id: Some(account_id.to_string()),
+            }),
+            last_refresh: Some(Utc::now() - chrono::Duration::hours(refreshed_hours_ago)),
+        }
+    }
+
+    #[test]
+    fn list_profiles_classifies_broken_and_duplicate_profiles() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        let profiles_dir = temp_dir.path().join("profiles");
+        let write = |name: &str, contents: &str| {
+            fs::create_dir_all(profiles_dir.join(name)).unwrap();
+            fs::write(profiles_dir.join(name).join("auth.json"), contents).unwrap();
+        };
+        write("corrupt", "{not json");
+        write("empty", "{}");
+        write(
+            "old",
+            &serde_json::to_string(&account_auth("acct_1", "a", 5)).unwrap(),
+        );
+        write(
+            "fresh",
+            &serde_json::to_string(&account_auth("acct_1", "b", 1)).unwrap(),
+        );
+        fs::create_dir_all(profiles_dir.join("missing")).unwrap();
+
+        let profiles = list_profiles_data().unwrap();
+        let health = |name: &str| {
+            let profile = profiles.iter().find(|p| p.name == name).unwrap();
+            (profile.health.clone(), profile.is_valid)
+        };
+
+        assert!(matches!(
+            health("corrupt"),
+            (ProfileHealth::ParseError(_), false)
+        ));
+        assert_eq!(health("empty"), (ProfileHealth::MissingTokens, false));
+        assert_eq!(health("missing"), (ProfileHealth::MissingAuthFile, false));
+        assert_eq!(health("fresh"), (ProfileHealth::Ok, true));
+        assert_eq!(
+            health("old"),
+            (ProfileHealth::DuplicateOf("fresh".to_string()), true)
+        );
     }
 
+    #[test]
+    fn merge_duplicates_keeps_current_profile_with_freshest_tokens() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        let profiles_dir = temp_dir.path().join("profiles");
+        for (name, auth) in [
+            ("work", account_auth("acct_1", "old", 5)),
+            ("work-2", account_auth("acct_1", "fresh", 1)),
+            ("home", account_auth("acct_2", "home", 1)),
+        ] {
+            fs::create_dir_all(profiles_dir.join(name)).unwrap();
+            fs::write(
+                profiles_dir.join(name).join("auth.json"),
+                serde_json::to_string(&auth).unwrap(),
+            )
+            .unwrap();
+        }
+        fs::write(temp_dir.path().join(".current_profile"), "work").unwrap();
+
+        let merges = merge_duplicate_profiles().unwrap();
+
+        assert_eq!(
+            merges,
+            vec![DuplicateMerge {
+                kept: "work".to_string(),
+                merged: vec!["work-2".to_string()],
+            }]
+        );
+        let kept = load_profile_auth("work").unwrap();
+        assert_eq!(kept.tokens.unwrap().access_token, "fresh");
+        assert!(!profiles_dir.join("work-2").exists());
+        assert_eq!(trash::list_trash().unwrap()[0].profile_name, "work-2");
+        assert!(list_profiles_data()
+            .unwrap()
+            .iter()
+            .all(|p| p.health == ProfileHealth::Ok));
+    }
+
+    #[test]
+    fn merge_duplicates_leaves_workspace_profiles_alone() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        let profiles_dir = temp_dir.path().join("profiles");
+        // "team" logged in to the team workspace that "work" also reaches as a sub-profile
+        for (name, auth) in [
+            ("work", account_auth("acct_personal", "work", 1)),
+            ("team", account_auth("acct_team", "team", 5)),
+        ] {
+            fs::create_dir_all(profiles_dir.join(name)).unwrap();
+            fs::write(
+                profiles_dir.join(name).join("auth.json"),
+                serde_json::to_string(&auth).unwrap(),
+            )
+            .unwrap();
+        }
+        let workspace = create_workspace_profile("work", "acct_team", "Team").unwrap();
+
+        assert!(merge_duplicate_profiles().unwrap().is_empty());
+
+        assert!(profiles_dir.join(&workspace).exists());
+        assert!(profiles_dir.join("team").join("auth.json").exists());
+        assert!(list_profiles_data()
+            .unwrap()
+            .iter()
+            .all(|p| p.health == ProfileHealth::Ok));
+    }
+
+    #[test]
+    fn rename_profile_moves_directory_and_current_marker() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        let profiles_dir = temp_dir.path().join("profiles");
+        for name in ["john-2", "work"] {
+            fs::create_dir_all(profiles_dir.join(name)).unwrap();
+            fs::write(profiles_dir.join(name).join("auth.json"), "{}").unwrap();
+        }
+        fs::write(temp_dir.path().join(".current_profile"), "john-2").unwrap();
+
+        let renamed = rename_profile("john-2", "John Personal").unwrap();
+        assert_eq!(renamed, "john-personal");
+        assert!(profiles_dir.join(&renamed).join("auth.json").exists());
+        assert!(!profiles_dir.join("john-2").exists());
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(".current_profile")).unwrap(),
+            "john-personal"
+        );
+
+        let err = rename_profile("john-personal", "Work").unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert!(rename_profile("john-personal", " !! ").is_err());
+        assert!(rename_profile("missing", "other").is_err());
+    }
+
+    #[test]
+    fn switch_profile_rolls_back_when_a_write_fails() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _codex_home_guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        let _home_guard = EnvGuard::set("HOME", temp_dir.path());
+
+        let profiles_dir = temp_dir.path().join("profiles");
+        fs::create_dir_all(profiles_dir.join("beta")).unwrap();
+        fs::write(
+            profiles_dir.join("beta").join("auth.json"),
+            r#"{"OPENAI_API_KEY":"sk-beta"}"#,
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("auth.json"),
+            r#"{"OPENAI_API_KEY":"sk-alpha"}"#,
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join(".current_profile"), "alpha").unwrap();
+        // A directory in place of the official auth.json makes the second write fail.
+        fs::create_dir_all(temp_dir.path().join(".codex").join("auth.json")).unwrap();
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        assert!(rt.block_on(switch_profile("beta")).is_err());
+
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("auth.json")).unwrap(),
+            r#"{"OPENAI_API_KEY":"sk-alpha"}"#
+        );
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(".current_profile")).unwrap(),
+            "alpha"
+        );
+    }
+    #[test]
+    fn workspace_profile_shares_parent_login_with_its_own_account() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        let parent_dir = temp_dir.path().join("profiles").join("work");
+        fs::create_dir_all(&parent_dir).unwrap();
+        fs::write(
+            parent_dir.join("auth.json"),
+            serde_json::to_string(&account_auth("acct_personal", "access", 1)).unwrap(),
+        )
+        .unwrap();
+
+        let name = create_workspace_profile("work", "acct_team", "Team").unwrap();
+        assert_eq!(name, "work-team");
+        assert!(create_workspace_profile("work", "acct_team", "Team").is_err());
+        assert!(create_workspace_profile("work", "acct_personal", "Personal").is_err());
+
+        let workspace_auth = load_profile_auth(&name).unwrap();
+        assert_eq!(
+            auth::get_account_id(&workspace_auth).as_deref(),
+            Some("acct_team")
+        );
+
+        // Refreshed tokens land in the parent without changing its account
+        save_profile_auth(&name, &account_auth("acct_team", "refreshed", 0)).unwrap();
+        let parent_auth = load_profile_auth("work").unwrap();
+        assert_eq!(
+            parent_auth.tokens.as_ref().unwrap().access_token,
+            "refreshed"
+        );
+        assert_eq!(
+            auth::get_account_id(&parent_auth).as_deref(),
+            Some("acct_personal")
+        );
+
+        rename_profile("work", "main").unwrap();
+        assert_eq!(
+            auth::get_account_id(&load_profile_auth(&name).unwrap()).as_deref(),
+            Some("acct_team")
+        );
+    }
+
+    #[test]
+    fn api_key_profiles_are_listed_as_api_key_auth() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        assert!(create_api_key_profile("Paid", "not-a-key").is_err());
+        let name = create_api_key_profile("Paid Overflow", " sk-test ").unwrap();
+        assert_eq!(name, "paid-overflow");
+        assert!(create_api_key_profile("other", "sk-test").is_err());
+
+        let auth = load_profile_auth(&name).unwrap();
+        assert_eq!(auth.openai_api_key.as_deref(), Some("sk-test"));
+        let profiles = list_profiles_data().unwrap();
+        assert_eq!(profiles[0].auth_kind, AuthKind::ApiKey);
+        assert_eq!(profiles[0].health, ProfileHealth::Ok);
+    }
+}
+
+/// Switch to a profile
+pub async fn switch_profile(profile_name: &str) -> Result<()> {
     // Read profile auth
-    let profile_auth = fs::read_to_string(&profile_auth_file)?;
-    let mut auth: AuthDotJson = serde_json::from_str(&profile_auth)?;
+    let mut auth = load_profile_auth(profile_name)?;
     if auth
         .tokens
         .as_ref()
@@ -350,22 +1003,46 @@ pub async fn switch_profile(profile_name: &str) -> Result<()> {
         }
     }
 
+    // Write both auth files and the marker together so a failure part-way
+    // through never leaves them pointing at different accounts.
+    let auth_json = serde_json::to_string_pretty(&auth)?;
+    let mut transaction = FileTransaction::new();
+    if let Err(err) = write_switch_files(&mut transaction, profile_name, auth_json.as_bytes()) {
+        if let Err(rollback_err) = transaction.rollback() {
+            tracing::error!(error = %rollback_err, "Failed to roll back profile switch");
+        }
+        return Err(err.context(format!("Failed to switch to profile '{}'", profile_name)));
+    }
+
+    if let Err(err) = profile_meta::touch_last_used(profile_name, Utc::now()) {
+        tracing::warn!(profile = %profile_name, error = %err, "Failed to record profile use");
+    }
+
+    Ok(())
+}
+
+fn write_switch_files(
+    transaction: &mut FileTransaction,
+    profile_name: &str,
+    auth_json: &[u8],
+) -> Result<()> {
     // Save to our isolated auth.json
     let main_auth_file = get_auth_file()?;
     if let Some(parent) = main_auth_file.parent() {
         fs::create_dir_all(parent)?;
     }
-    fs::write(&main_auth_file, serde_json::to_string_pretty(&auth)?)?;
+    transaction.write(&main_auth_file, auth_json)?;
 
     // Also sync to official codex auth.json so `codex` CLI uses this account
     let official_auth_file = crate::config::get_official_auth_file()?;
     if let Some(parent) = official_auth_file.parent() {
         fs::create_dir_all(parent)?;
     }
-    fs::write(&official_auth_file, serde_json::to_string_pretty(&auth)?)?;
+    transaction.write(&official_auth_file, auth_json)?;
 
     // Update current profile marker
-    save_current_profile(profile_name)?;
+    let current_file = get_current_profile_file()?;
+    transaction.write(&current_file, profile_name.as_bytes())?;
 
     Ok(())
 }
@@ -388,9 +1065,7 @@ pub fn save_profile(profile_name: &str) -> Result<SaveProfileOutcome> {
             }
             let existing_name = entry.file_name().to_string_lossy().to_string();
             let existing_auth_file = entry.path().join("auth.json");
-            let existing_auth = fs::read_to_string(&existing_auth_file)
-                .ok()
-                .and_then(|contents| serde_json::from_str::<AuthDotJson>(&contents).ok());
+            let existing_auth = read_auth_file(&existing_auth_file).ok();
             let Some(existing_auth) = existing_auth else {
                 continue;
             };
@@ -407,7 +1082,7 @@ pub fn save_profile(profile_name: &str) -> Result<SaveProfileOutcome> {
                 });
             }
 
-            fs::write(&existing_auth_file, serde_json::to_string_pretty(&auth)?)?;
+            write_auth_file(&existing_auth_file, &auth)?;
             save_current_profile(&existing_name)?;
             return Ok(SaveProfileOutcome::Updated {
                 name: existing_name,
@@ -425,7 +1100,8 @@ pub fn save_profile(profile_name: &str) -> Result<SaveProfileOutcome> {
 
     fs::create_dir(&profile_dir)?;
     let profile_auth_file = profile_dir.join("auth.json");
-    fs::write(&profile_auth_file, serde_json::to_string_pretty(&auth)?)?;
+    write_auth_file(&profile_auth_file, &auth)?;
+    init_profile_meta(profile_name)?;
 
     if get_current_profile()?.is_none() {
         save_current_profile(profile_name)?;
@@ -438,43 +1114,32 @@ pub fn save_profile(profile_name: &str) -> Result<SaveProfileOutcome> {
 
 /// Save the provided auth as a profile without switching the current profile.
 pub fn save_auth_as_profile_without_switch(auth: &AuthDotJson) -> Result<SaveProfileOutcome> {
+    save_auth_as_named_profile(auth, &suggested_profile_name(auth))
+}
+
+/// Save auth without switching, merging into the profile with the same account id.
+/// New profiles are named after `preferred_name` (sanitized, suffixed on collision).
+pub fn save_auth_as_named_profile(
+    auth: &AuthDotJson,
+    preferred_name: &str,
+) -> Result<SaveProfileOutcome> {
     let profiles_dir = get_profiles_dir()?;
     fs::create_dir_all(&profiles_dir)?;
 
-    if let Some(account_id) = auth::get_account_id(auth) {
-        for entry in fs::read_dir(&profiles_dir)? {
-            let entry = entry?;
-            if !entry.path().is_dir() {
-                continue;
-            }
-            let existing_name = entry.file_name().to_string_lossy().to_string();
-            let existing_auth_file = entry.path().join("auth.json");
-            let existing_auth = fs::read_to_string(&existing_auth_file)
-                .ok()
-                .and_then(|contents| serde_json::from_str::<AuthDotJson>(&contents).ok());
-            let Some(existing_auth) = existing_auth else {
-                continue;
-            };
-            if auth::get_account_id(&existing_auth).as_deref() != Some(account_id.as_str()) {
-                continue;
-            }
-
-            let incoming_fp = token_fingerprint(auth);
-            let existing_fp = token_fingerprint(&existing_auth);
-            if incoming_fp == existing_fp {
-                return Ok(SaveProfileOutcome::AlreadyExists {
-                    name: existing_name,
-                });
-            }
-
-            fs::write(&existing_auth_file, serde_json::to_string_pretty(auth)?)?;
-            return Ok(SaveProfileOutcome::Updated {
+    if let Some((existing_name, existing_auth)) = find_profile_for_account(auth)? {
+        if token_fingerprint(auth) == token_fingerprint(&existing_auth) {
+            return Ok(SaveProfileOutcome::AlreadyExists {
                 name: existing_name,
             });
         }
+
+        save_profile_auth(&existing_name, auth)?;
+        return Ok(SaveProfileOutcome::Updated {
+            name: existing_name,
+        });
     }
 
-    let base_name = suggested_profile_name(auth);
+    let base_name = sanitize_profile_name(preferred_name);
     for attempt in 0..100 {
         let candidate = if attempt == 0 {
             base_name.clone()
@@ -487,13 +1152,88 @@ pub fn save_auth_as_profile_without_switch(auth: &AuthDotJson) -> Result<SavePro
         }
         fs::create_dir(&profile_dir)?;
         let profile_auth_file = profile_dir.join("auth.json");
-        fs::write(&profile_auth_file, serde_json::to_string_pretty(auth)?)?;
+        write_auth_file(&profile_auth_file, auth)?;
+        init_profile_meta(&candidate)?;
         return Ok(SaveProfileOutcome::Created { name: candidate });
     }
 
     anyhow::bail!("Failed to find available profile name starting with '{base_name}'");
 }
 
+/// How a set of credentials relates to the saved profiles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthMatch {
+    /// A profile holds this account with the same tokens
+    Unchanged { name: String },
+    /// A profile holds this account, but these tokens are newer
+    Refreshed { name: String },
+    /// No profile holds this account yet
+    New { suggested_name: String },
+}
+
+/// Compare credentials (e.g. from the official Codex CLI) with the saved profiles by account id.
+/// Tokens that were refreshed before the profile's copy count as unchanged.
+pub fn match_auth_to_profile(auth: &AuthDotJson) -> Result<AuthMatch> {
+    let Some((name, existing_auth)) = find_profile_for_account(auth)? else {
+        return Ok(AuthMatch::New {
+            suggested_name: suggested_profile_name(auth),
+        });
+    };
+
+    let is_older = matches!(
+        (auth.last_refresh, existing_auth.last_refresh),
+        (Some(incoming), Some(existing)) if incoming < existing
+    );
+    if is_older || token_fingerprint(auth) == token_fingerprint(&existing_auth) {
+        Ok(AuthMatch::Unchanged { name })
+    } else {
+        Ok(AuthMatch::Refreshed { name })
+    }
+}
+
+/// Find the profile holding the same account as `auth`. API keys carry no account id,
+/// so API-key profiles match on the key's fingerprint instead. Workspace sub-profiles
+/// match with their parent's tokens and their workspace's account id; when several
+/// profiles hold the account, the one with the same tokens wins.
+fn find_profile_for_account(auth: &AuthDotJson) -> Result<Option<(String, AuthDotJson)>> {
+    let is_api_key = AuthKind::of(auth) == AuthKind::ApiKey;
+    let account_id = auth::get_account_id(auth);
+    if !is_api_key && account_id.is_none() {
+        return Ok(None);
+    }
+
+    let mut found = None;
+    for (name, result) in load_all_profiles()? {
+        let Ok(existing_auth) = result else {
+            continue;
+        };
+        let same_account = if is_api_key {
+            AuthKind::of(&existing_auth) == AuthKind::ApiKey
+                && token_fingerprint(&existing_auth) == token_fingerprint(auth)
+        } else {
+            auth::get_account_id(&existing_auth) == account_id
+        };
+        if !same_account {
+            continue;
+        }
+        if token_fingerprint(&existing_auth) == token_fingerprint(auth) {
+            return Ok(Some((name, existing_auth)));
+        }
+        found.get_or_insert((name, existing_auth));
+    }
+
+    Ok(found)
+}
+
+/// Write the initial metadata for a newly created profile
+fn init_profile_meta(profile_name: &str) -> Result<()> {
+    let meta = ProfileMeta {
+        created_at: Some(Utc::now()),
+        ..ProfileMeta::default()
+    };
+    profile_meta::save_meta(profile_name, &meta)
+}
+
 fn suggested_profile_name(auth: &AuthDotJson) -> String {
     let raw = auth::get_email(auth)
         .and_then(|email| email.split('@').next().map(|value| value.to_string()))
@@ -545,8 +1285,46 @@ fn sanitize_profile_name(input: &str) -> String {
     }
 }
 
-/// Delete a profile
-pub fn delete_profile(profile_name: &str) -> Result<()> {
+/// Rename a profile directory, keeping `.current_profile` pointing at it.
+/// The new name is normalized with `sanitize_profile_name`; returns the name used.
+pub fn rename_profile(profile_name: &str, new_name: &str) -> Result<String> {
+    if !new_name.chars().any(|ch| ch.is_ascii_alphanumeric()) {
+        anyhow::bail!("Profile name must contain letters or digits.");
+    }
+    let new_name = sanitize_profile_name(new_name);
+
+    let profiles_dir = get_profiles_dir()?;
+    let profile_dir = profiles_dir.join(profile_name);
+    if !profile_dir.is_dir() {
+        anyhow::bail!("Profile '{}' not found.", profile_name);
+    }
+    if new_name == profile_name {
+        return Ok(new_name);
+    }
+
+    let new_dir = profiles_dir.join(&new_name);
+    // On case-insensitive filesystems a case-only rename finds the profile itself.
+    if new_dir.exists() && fs::canonicalize(&new_dir)? != fs::canonicalize(&profile_dir)? {
+        anyhow::bail!("Profile '{}' already exists.", new_name);
+    }
+
+    fs::rename(&profile_dir, &new_dir)?;
+
+    if get_current_profile()?.as_deref() == Some(profile_name) {
+        if let Err(err) = save_current_profile(&new_name) {
+            fs::rename(&new_dir, &profile_dir)?;
+            return Err(err.context("Failed to update current profile marker"));
+        }
+    }
+    if let Err(err) = relink_workspaces(profile_name, &new_name) {
+        tracing::warn!(error = %err, "Failed to update workspaces of renamed profile");
+    }
+
+    Ok(new_name)
+}
+
+/// Delete a profile by moving it to the trash, from where it can be restored
+pub fn delete_profile(profile_name: &str) -> Result<TrashEntry> {
     let profiles_dir = get_profiles_dir()?;
 
     let profile_dir = profiles_dir.join(profile_name);
@@ -562,13 +1340,11 @@ pub fn delete_profile(profile_name: &str) -> Result<()> {
         }
     }
 
-    fs::remove_dir_all(&profile_dir)?;
-
-    Ok(())
+    trash::trash_profile(profile_name, Utc::now())
 }
 
 /// Get the current profile name
-fn get_current_profile() -> Result<Option<String>> {
+pub fn get_current_profile() -> Result<Option<String>> {
     let current_file = get_current_profile_file()?;
 
     if !current_file.exists() {
@@ -587,7 +1363,7 @@ fn save_current_profile(profile_name: &str) -> Result<()> {
         fs::create_dir_all(parent)?;
     }
 
-    fs::write(&current_file, profile_name)?;
+    write_atomic(&current_file, profile_name.as_bytes())?;
 
     Ok(())
 }
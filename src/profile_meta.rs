@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::atomic_file::write_atomic;
+use crate::config::get_profile_meta_file;
+
+/// `last_used_at` is only rewritten when it is older than this, so routing does not
+/// write the file on every request
+pub const LAST_USED_RESOLUTION_SECONDS: i64 = 60;
+
+pub const DEFAULT_PRIORITY_WEIGHT: u32 = 1;
+
+/// User-editable settings stored next to a profile's auth.json as `meta.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileMeta {
+    pub display_name: Option<String>,
+    /// `#rrggbb`
+    pub color: Option<String>,
+    pub notes: String,
+    pub tags: Vec<String>,
+    /// Among profiles with headroom, higher weights are routed to first
+    pub priority_weight: u32,
+    /// Disabled profiles are skipped by routing but kept on disk
+    pub enabled: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Set on workspace sub-profiles, which have no auth.json of their own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<WorkspaceLink>,
+}
+
+/// Ties a sub-profile to the profile whose login it shares, sending a different
+/// `ChatGPT-Account-Id` so the workspace's quota is used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceLink {
+    pub parent: String,
+    pub account_id: String,
+}
+
+impl Default for ProfileMeta {
+    fn default() -> Self {
+        Self {
+            display_name: None,
+            color: None,
+            notes: String::new(),
+            tags: Vec::new(),
+            priority_weight: DEFAULT_PRIORITY_WEIGHT,
+            enabled: true,
+            created_at: None,
+            last_used_at: None,
+            workspace: None,
+        }
+    }
+}
+
+impl ProfileMeta {
+    /// Display name when set, otherwise the profile directory name
+    pub fn label<'a>(&'a self, profile_name: &'a str) -> &'a str {
+        self.display_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(profile_name)
+    }
+
+    pub fn color_rgb(&self) -> Option<[u8; 3]> {
+        self.color.as_deref().and_then(parse_hex_color)
+    }
+}
+
+pub fn parse_hex_color(value: &str) -> Option<[u8; 3]> {
+    let hex = value.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn format_hex_color(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+/// Split a comma-separated tag list, dropping blanks and duplicates
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+    {
+        if !tags.iter().any(|existing| existing == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+pub fn load_meta(profile_name: &str) -> Result<ProfileMeta> {
+    let meta_file = get_profile_meta_file(profile_name)?;
+    if !meta_file.exists() {
+        return Ok(ProfileMeta::default());
+    }
+
+    let contents = fs::read_to_string(&meta_file)
+        .with_context(|| format!("Failed to read profile metadata: {:?}", meta_file))?;
+    let meta: ProfileMeta = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse metadata for profile '{}'", profile_name))?;
+    Ok(meta)
+}
+
+pub fn save_meta(profile_name: &str, meta: &ProfileMeta) -> Result<()> {
+    let meta_file = get_profile_meta_file(profile_name)?;
+    if !meta_file.parent().is_some_and(|dir| dir.exists()) {
+        anyhow::bail!("Profile '{}' not found.", profile_name);
+    }
+
+    let contents = serde_json::to_string_pretty(meta)?;
+    write_atomic(&meta_file, contents.as_bytes())?;
+    Ok(())
+}
+
+/// Record that a profile was just used. Returns whether the file was rewritten.
+pub fn touch_last_used(profile_name: &str, now: DateTime<Utc>) -> Result<bool> {
+    let mut meta = load_meta(profile_name)?;
+    let recent = meta
+        .last_used_at
+        .is_some_and(|last| now - last < Duration::seconds(LAST_USED_RESOLUTION_SECONDS));
+    if recent {
+        return Ok(false);
+    }
+    meta.last_used_at = Some(now);
+    save_meta(profile_name, &meta)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::get_profiles_dir;
+    use crate::test_support::{EnvGuard, ENV_LOCK};
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let meta: ProfileMeta = serde_json::from_str(r#"{"notes":"team seat"}"#).unwrap();
+
+        assert!(meta.enabled);
+        assert_eq!(meta.priority_weight, DEFAULT_PRIORITY_WEIGHT);
+        assert_eq!(meta.notes, "team seat");
+        assert_eq!(meta.label("work"), "work");
+    }
+
+    #[test]
+    fn parses_colors_and_tags() {
+        assert_eq!(parse_hex_color("#ff8000"), Some([255, 128, 0]));
+        assert_eq!(parse_hex_color("ff8000"), None);
+        assert_eq!(parse_hex_color("#ff80"), None);
+        assert_eq!(format_hex_color([255, 128, 0]), "#ff8000");
+        assert_eq!(
+            parse_tags(" team, ,personal,team "),
+            vec!["team", "personal"]
+        );
+    }
+
+    #[test]
+    fn saves_loads_and_touches_meta() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        assert!(save_meta("work", &ProfileMeta::default()).is_err());
+        fs::create_dir_all(get_profiles_dir().unwrap().join("work")).unwrap();
+
+        let meta = ProfileMeta {
+            display_name: Some("Work seat".to_string()),
+            enabled: false,
+            ..ProfileMeta::default()
+        };
+        save_meta("work", &meta).unwrap();
+        assert_eq!(load_meta("work").unwrap(), meta);
+
+        let now = Utc::now();
+        assert!(touch_last_used("work", now).unwrap());
+        assert!(!touch_last_used("work", now + Duration::seconds(5)).unwrap());
+        let loaded = load_meta("work").unwrap();
+        assert_eq!(loaded.last_used_at, Some(now));
+        assert_eq!(loaded.label("work"), "Work seat");
+    }
+}
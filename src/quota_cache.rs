@@ -0,0 +1,281 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::api::QuotaInfo;
+use crate::atomic_file::write_atomic;
+use crate::config::get_quota_cache_file;
+use crate::profile::ProfileSummary;
+
+/// A quota fetched less than this long ago is reused instead of hitting the endpoint again
+pub const MIN_FETCH_INTERVAL_SECONDS: i64 = 30;
+
+/// First retry delay after a failed fetch; doubled on every consecutive failure
+pub const BACKOFF_BASE_SECONDS: i64 = 60;
+
+/// Upper bound for the retry delay
+pub const BACKOFF_MAX_SECONDS: i64 = 60 * 60;
+
+/// Quotas older than this are shown as stale even without a failed fetch
+pub const STALE_AFTER_HOURS: i64 = 3;
+
+/// Last successful quota for a profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedQuota {
+    pub quota: QuotaInfo,
+    pub fetched_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaCacheEntry {
+    #[serde(default)]
+    pub last_good: Option<CachedQuota>,
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Fetches are skipped until this time while backing off
+    #[serde(default)]
+    pub retry_at: Option<DateTime<Utc>>,
+}
+
+impl QuotaCacheEntry {
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        match &self.last_good {
+            Some(cached) => {
+                self.consecutive_failures > 0
+                    || now - cached.fetched_at > Duration::hours(STALE_AFTER_HOURS)
+            }
+            None => false,
+        }
+    }
+}
+
+/// Cached quota results and fetch backoff keyed by profile name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaCache {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, QuotaCacheEntry>,
+}
+
+impl QuotaCache {
+    pub fn entry(&self, profile_name: &str) -> Option<&QuotaCacheEntry> {
+        self.profiles.get(profile_name)
+    }
+
+    /// Whether the endpoint should be called for this profile now. Returns false while
+    /// backing off after errors or when the cached quota is only seconds old.
+    pub fn should_fetch(&self, profile_name: &str, now: DateTime<Utc>) -> bool {
+        let Some(entry) = self.profiles.get(profile_name) else {
+            return true;
+        };
+        if let Some(retry_at) = entry.retry_at {
+            return now >= retry_at;
+        }
+        match &entry.last_good {
+            Some(cached) => {
+                now - cached.fetched_at >= Duration::seconds(MIN_FETCH_INTERVAL_SECONDS)
+            }
+            None => true,
+        }
+    }
+
+    pub fn record_success(&mut self, profile_name: &str, quota: QuotaInfo, now: DateTime<Utc>) {
+        self.profiles.insert(
+            profile_name.to_string(),
+            QuotaCacheEntry {
+                last_good: Some(CachedQuota {
+                    quota,
+                    fetched_at: now,
+                }),
+                ..QuotaCacheEntry::default()
+            },
+        );
+    }
+
+    /// Record a failed fetch and return when the next attempt is allowed.
+    pub fn record_failure(
+        &mut self,
+        profile_name: &str,
+        error: &str,
+        now: DateTime<Utc>,
+    ) -> DateTime<Utc> {
+        let entry = self.profiles.entry(profile_name.to_string()).or_default();
+        entry.consecutive_failures = entry.consecutive_failures.saturating_add(1);
+        entry.last_error = Some(error.to_string());
+        let retry_at = now + backoff_delay(entry.consecutive_failures);
+        entry.retry_at = Some(retry_at);
+        retry_at
+    }
+
+    /// Fill in the last good quota for a profile, marking it stale when appropriate.
+    pub fn apply_to(&self, profile: &mut ProfileSummary, now: DateTime<Utc>) {
+        let Some(entry) = self.profiles.get(&profile.name) else {
+            return;
+        };
+        let Some(cached) = &entry.last_good else {
+            return;
+        };
+        profile.quota = Some(cached.quota.clone());
+        profile.quota_fetched_at = Some(cached.fetched_at);
+        profile.quota_stale = entry.is_stale(now);
+    }
+
+    pub fn remove(&mut self, profile_name: &str) {
+        self.profiles.remove(profile_name);
+    }
+
+    pub fn rename(&mut self, profile_name: &str, new_name: &str) {
+        if let Some(entry) = self.profiles.remove(profile_name) {
+            self.profiles.insert(new_name.to_string(), entry);
+        }
+    }
+}
+
+/// Exponential backoff for the given number of consecutive failures
+pub fn backoff_delay(consecutive_failures: u32) -> Duration {
+    let exponent = consecutive_failures.saturating_sub(1).min(16);
+    let seconds = BACKOFF_BASE_SECONDS.saturating_mul(1 << exponent);
+    Duration::seconds(seconds.min(BACKOFF_MAX_SECONDS))
+}
+
+pub fn load_cache() -> Result<QuotaCache> {
+    let cache_file = get_quota_cache_file()?;
+    if !cache_file.exists() {
+        return Ok(QuotaCache::default());
+    }
+
+    let contents = fs::read_to_string(&cache_file)
+        .with_context(|| format!("Failed to read quota cache: {:?}", cache_file))?;
+    let cache: QuotaCache =
+        serde_json::from_str(&contents).with_context(|| "Failed to parse quota cache")?;
+    Ok(cache)
+}
+
+pub fn save_cache(cache: &QuotaCache) -> Result<()> {
+    let cache_file = get_quota_cache_file()?;
+    if let Some(parent) = cache_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let contents = serde_json::to_string_pretty(cache)?;
+    write_atomic(&cache_file, contents.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::RateLimitWindow;
+    use crate::test_support::{EnvGuard, ENV_LOCK};
+
+    fn quota(used: u64) -> QuotaInfo {
+        QuotaInfo {
+            account_id: "acct_123".to_string(),
+            email: "user@example.com".to_string(),
+            plan_type: "pro".to_string(),
+            primary: Some(RateLimitWindow {
+                used_percent: used as f64,
+                window_seconds: None,
+                resets_at: None,
+            }),
+            secondary: None,
+            credits: None,
+            usage: None,
+        }
+    }
+
+    fn profile(name: &str) -> ProfileSummary {
+        ProfileSummary {
+            name: name.to_string(),
+            email: None,
+            is_current: false,
+            is_valid: true,
+            quota: None,
+            quota_fetched_at: None,
+            quota_stale: false,
+            meta: Default::default(),
+            health: Default::default(),
+            auth_kind: Default::default(),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(backoff_delay(1), Duration::seconds(60));
+        assert_eq!(backoff_delay(2), Duration::seconds(120));
+        assert_eq!(backoff_delay(4), Duration::seconds(480));
+        assert_eq!(backoff_delay(30), Duration::seconds(BACKOFF_MAX_SECONDS));
+    }
+
+    #[test]
+    fn should_fetch_respects_backoff_and_fresh_results() {
+        let now = Utc::now();
+        let mut cache = QuotaCache::default();
+        assert!(cache.should_fetch("work", now));
+
+        cache.record_success("work", quota(10), now);
+        assert!(!cache.should_fetch("work", now + Duration::seconds(5)));
+        assert!(cache.should_fetch("work", now + Duration::seconds(MIN_FETCH_INTERVAL_SECONDS)));
+
+        let retry_at = cache.record_failure("work", "boom", now);
+        assert_eq!(retry_at, now + Duration::seconds(60));
+        assert!(!cache.should_fetch("work", now + Duration::seconds(59)));
+        assert!(cache.should_fetch("work", retry_at));
+
+        let retry_at = cache.record_failure("work", "boom", now);
+        assert_eq!(retry_at, now + Duration::seconds(120));
+    }
+
+    #[test]
+    fn failure_keeps_last_good_quota_marked_stale() {
+        let now = Utc::now();
+        let mut cache = QuotaCache::default();
+        cache.record_success("work", quota(40), now - Duration::minutes(10));
+        cache.record_failure("work", "timeout", now);
+
+        let mut summary = profile("work");
+        cache.apply_to(&mut summary, now);
+
+        assert_eq!(summary.quota, Some(quota(40)));
+        assert_eq!(summary.quota_fetched_at, Some(now - Duration::minutes(10)));
+        assert!(summary.quota_stale);
+
+        cache.record_success("work", quota(45), now);
+        cache.apply_to(&mut summary, now);
+        assert!(!summary.quota_stale);
+        assert_eq!(cache.entry("work").unwrap().consecutive_failures, 0);
+    }
+
+    #[test]
+    fn old_quota_is_stale() {
+        let now = Utc::now();
+        let mut cache = QuotaCache::default();
+        cache.record_success(
+            "work",
+            quota(40),
+            now - Duration::hours(STALE_AFTER_HOURS + 1),
+        );
+
+        assert!(cache.entry("work").unwrap().is_stale(now));
+    }
+
+    #[test]
+    fn saves_and_loads_cache() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        let now = Utc::now();
+        let mut cache = QuotaCache::default();
+        cache.record_success("work", quota(25), now);
+        cache.record_failure("home", "boom", now);
+
+        save_cache(&cache).unwrap();
+        let loaded = load_cache().unwrap();
+
+        assert_eq!(loaded, cache);
+    }
+}
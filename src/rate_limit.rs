@@ -0,0 +1,233 @@
+//! Request limits per client: requests per minute, concurrent requests and a daily budget.
+//! A client is a router API key, or the source IP when no key is used.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+const MINUTE: TimeDelta = TimeDelta::minutes(1);
+
+/// How long a client at its concurrency limit is asked to wait
+const CONCURRENCY_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Zero disables a limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimits {
+    #[serde(default)]
+    pub requests_per_minute: u32,
+    /// Requests, streaming or not, being served at once
+    #[serde(default)]
+    pub concurrent_requests: u32,
+    /// Requests per local calendar day
+    #[serde(default)]
+    pub daily_requests: u32,
+}
+
+impl RateLimits {
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClientId {
+    Key(String),
+    Ip(IpAddr),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+    pub message: String,
+    pub retry_after: Duration,
+}
+
+#[derive(Debug, Default)]
+struct ClientWindow {
+    /// Start times of the requests admitted in the last minute
+    recent: VecDeque<DateTime<Local>>,
+    in_flight: u32,
+    day: Option<NaiveDate>,
+    requests_today: u32,
+}
+
+impl ClientWindow {
+    fn is_idle(&self, now: DateTime<Local>) -> bool {
+        self.in_flight == 0
+            && self.day != Some(now.date_naive())
+            && self.recent.back().is_none_or(|last| now - *last >= MINUTE)
+    }
+}
+
+type Windows = Arc<Mutex<HashMap<ClientId, ClientWindow>>>;
+
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    clients: Windows,
+}
+
+/// Holds one of the client's concurrent slots until dropped
+#[derive(Debug)]
+pub struct Permit {
+    clients: Windows,
+    client: ClientId,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(window) = lock(&self.clients).get_mut(&self.client) {
+            window.in_flight = window.in_flight.saturating_sub(1);
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Admit a request or say how long the client must wait
+    pub fn acquire(
+        &self,
+        client: ClientId,
+        limits: &RateLimits,
+        now: DateTime<Local>,
+    ) -> Result<Permit, RateLimited> {
+        let mut clients = lock(&self.clients);
+        if !clients.contains_key(&client) {
+            clients.retain(|_, window| !window.is_idle(now));
+        }
+        let window = clients.entry(client.clone()).or_default();
+
+        let today = now.date_naive();
+        if window.day != Some(today) {
+            window.day = Some(today);
+            window.requests_today = 0;
+        }
+        while window
+            .recent
+            .front()
+            .is_some_and(|started| now - *started >= MINUTE)
+        {
+            window.recent.pop_front();
+        }
+
+        if limits.daily_requests > 0 && window.requests_today >= limits.daily_requests {
+            return Err(RateLimited {
+                message: format!(
+                    "Daily budget of {} requests reached.",
+                    limits.daily_requests
+                ),
+                retry_after: until_next_day(now),
+            });
+        }
+        if limits.requests_per_minute > 0
+            && window.recent.len() >= limits.requests_per_minute as usize
+        {
+            let oldest = window.recent[window.recent.len() - limits.requests_per_minute as usize];
+            return Err(RateLimited {
+                message: format!(
+                    "Rate limit of {} requests per minute reached.",
+                    limits.requests_per_minute
+                ),
+                retry_after: (oldest + MINUTE - now).to_std().unwrap_or_default(),
+            });
+        }
+        if limits.concurrent_requests > 0 && window.in_flight >= limits.concurrent_requests {
+            return Err(RateLimited {
+                message: format!(
+                    "Limit of {} concurrent requests reached.",
+                    limits.concurrent_requests
+                ),
+                retry_after: CONCURRENCY_RETRY_AFTER,
+            });
+        }
+
+        window.recent.push_back(now);
+        window.requests_today += 1;
+        window.in_flight += 1;
+        Ok(Permit {
+            clients: self.clients.clone(),
+            client,
+        })
+    }
+}
+
+fn until_next_day(now: DateTime<Local>) -> Duration {
+    now.date_naive()
+        .succ_opt()
+        .and_then(|tomorrow| tomorrow.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .and_then(|midnight| (midnight - now).to_std().ok())
+        .unwrap_or(Duration::from_secs(3600))
+}
+
+fn lock(clients: &Windows) -> MutexGuard<'_, HashMap<ClientId, ClientWindow>> {
+    clients.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, min: u32, sec: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, 3, 10, hour, min, sec)
+            .single()
+            .unwrap()
+    }
+
+    #[test]
+    fn limits_requests_per_minute_and_per_day() {
+        let limiter = RateLimiter::default();
+        let client = ClientId::Key("ci".to_string());
+        let limits = RateLimits {
+            requests_per_minute: 2,
+            daily_requests: 3,
+            ..RateLimits::default()
+        };
+
+        limiter
+            .acquire(client.clone(), &limits, at(9, 0, 0))
+            .unwrap();
+        limiter
+            .acquire(client.clone(), &limits, at(9, 0, 20))
+            .unwrap();
+        let limited = limiter
+            .acquire(client.clone(), &limits, at(9, 0, 30))
+            .unwrap_err();
+        assert_eq!(limited.retry_after, Duration::from_secs(30));
+
+        limiter
+            .acquire(client.clone(), &limits, at(9, 1, 0))
+            .unwrap();
+        let limited = limiter
+            .acquire(client.clone(), &limits, at(9, 5, 0))
+            .unwrap_err();
+        assert!(limited.message.contains("Daily budget"));
+
+        let other = ClientId::Key("other".to_string());
+        assert!(limiter.acquire(other, &limits, at(9, 5, 0)).is_ok());
+    }
+
+    #[test]
+    fn permits_hold_concurrent_slots_until_dropped() {
+        let limiter = RateLimiter::default();
+        let client = ClientId::Ip("127.0.0.1".parse().unwrap());
+        let limits = RateLimits {
+            concurrent_requests: 1,
+            ..RateLimits::default()
+        };
+
+        let permit = limiter
+            .acquire(client.clone(), &limits, at(9, 0, 0))
+            .unwrap();
+        let limited = limiter
+            .acquire(client.clone(), &limits, at(9, 0, 1))
+            .unwrap_err();
+        assert_eq!(limited.retry_after, CONCURRENCY_RETRY_AFTER);
+
+        drop(permit);
+        assert!(limiter.acquire(client, &limits, at(9, 0, 2)).is_ok());
+    }
+}
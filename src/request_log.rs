@@ -0,0 +1,461 @@
+//! A record of every proxied request: who sent it, where it was routed and how it
+//! went. Entries are appended as JSON lines under the router dir; prompts are only
+//! kept when enabled, and then with likely secrets masked unless logging is set to full.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::thread::JoinHandle;
+
+/// Entries kept in memory for the log panel
+const RECENT_ENTRIES: usize = 500;
+/// The log file is rotated to `<name>.1` past this size
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+/// Longest prompt excerpt stored per request
+const MAX_PROMPT_CHARS: usize = 4000;
+const REDACTED: &str = "[REDACTED]";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptLogging {
+    /// Only metadata is logged
+    #[default]
+    Off,
+    /// Prompts with API keys, tokens and email addresses masked
+    Redacted,
+    Full,
+}
+
+impl PromptLogging {
+    pub const ALL: [PromptLogging; 3] = [Self::Off, Self::Redacted, Self::Full];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Redacted => "Redacted",
+            Self::Full => "Full",
+        }
+    }
+
+    /// The prompt to store for `messages`, if any
+    pub fn capture(self, messages: &[serde_json::Value]) -> Option<String> {
+        if self == Self::Off {
+            return None;
+        }
+        let prompt = messages
+            .iter()
+            .map(|message| {
+                let role = message
+                    .get("role")
+                    .and_then(|role| role.as_str())
+                    .unwrap_or("user");
+                let content = match message.get("content") {
+                    Some(serde_json::Value::String(text)) => text.clone(),
+                    Some(other) => other.to_string(),
+                    None => String::new(),
+                };
+                format!("{role}: {content}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt = match self {
+            Self::Redacted => redact(&prompt),
+            _ => prompt,
+        };
+        Some(truncate(&prompt, MAX_PROMPT_CHARS))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestLogEntry {
+    pub timestamp: DateTime<Utc>,
+    /// Client key label, source IP, or `local` for the Unix socket
+    pub client: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Profile that served the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Profiles tried, including the one that served it
+    pub attempts: u32,
+    pub status: u16,
+    pub latency_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    /// Last upstream error when profiles failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+}
+
+impl RequestLogEntry {
+    /// Case-insensitive search over the entry's text fields and status
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return true;
+        }
+        let status = self.status.to_string();
+        let fields = [
+            Some(self.client.as_str()),
+            self.model.as_deref(),
+            self.profile.as_deref(),
+            Some(status.as_str()),
+            self.error.as_deref(),
+            self.prompt.as_deref(),
+        ];
+        fields
+            .iter()
+            .flatten()
+            .any(|field| field.to_lowercase().contains(&query))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RequestLog {
+    writer: Mutex<Option<LogWriter>>,
+    recent: Mutex<VecDeque<RequestLogEntry>>,
+}
+
+impl RequestLog {
+    /// Persist entries to `path` from now on, loading its latest entries for display
+    pub fn attach_file(&self, path: PathBuf) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+        let loaded = if path.exists() {
+            load_tail(&path)?
+        } else {
+            VecDeque::new()
+        };
+        if let Ok(mut recent) = self.recent.lock() {
+            let newer = std::mem::replace(&mut *recent, loaded);
+            for entry in newer {
+                push_bounded(&mut recent, entry);
+            }
+        }
+        let writer = LogWriter::spawn(path)?;
+        if let Ok(mut current) = self.writer.lock() {
+            *current = Some(writer);
+        }
+        Ok(())
+    }
+
+    /// Called from request handlers, so the file is written on the writer thread
+    pub fn record(&self, entry: RequestLogEntry) {
+        if let Ok(writer) = self.writer.lock() {
+            if let Some(tx) = writer.as_ref().and_then(|writer| writer.tx.as_ref()) {
+                let _ = tx.send(entry.clone());
+            }
+        }
+        if let Ok(mut recent) = self.recent.lock() {
+            push_bounded(&mut recent, entry);
+        }
+    }
+
+    /// Latest entries, oldest first
+    pub fn recent(&self) -> Vec<RequestLogEntry> {
+        self.recent
+            .lock()
+            .map(|recent| recent.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Appends entries to the log file in order; dropping it writes the queued ones first
+#[derive(Debug)]
+struct LogWriter {
+    tx: Option<mpsc::Sender<RequestLogEntry>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl LogWriter {
+    fn spawn(path: PathBuf) -> Result<Self> {
+        let (tx, rx) = mpsc::channel::<RequestLogEntry>();
+        let thread = std::thread::Builder::new()
+            .name("request-log".to_string())
+            .spawn(move || {
+                for entry in rx {
+                    if let Err(err) = append(&path, &entry) {
+                        tracing::warn!("Failed to write request log: {:#}", err);
+                    }
+                }
+            })
+            .context("Failed to start the request log writer")?;
+        Ok(Self {
+            tx: Some(tx),
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for LogWriter {
+    fn drop(&mut self) {
+        // Closing the channel ends the thread once the queue is written
+        self.tx = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn push_bounded(recent: &mut VecDeque<RequestLogEntry>, entry: RequestLogEntry) {
+    if recent.len() == RECENT_ENTRIES {
+        recent.pop_front();
+    }
+    recent.push_back(entry);
+}
+
+fn load_tail(path: &Path) -> Result<VecDeque<RequestLogEntry>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let mut entries = VecDeque::new();
+    // Lines that fail to parse, e.g. one cut short by a crash, are skipped
+    for entry in contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+    {
+        push_bounded(&mut entries, entry);
+    }
+    Ok(entries)
+}
+
+fn append(path: &Path, entry: &RequestLogEntry) -> Result<()> {
+    if fs::metadata(path).is_ok_and(|meta| meta.len() > MAX_LOG_BYTES) {
+        let mut rotated = path.as_os_str().to_owned();
+        rotated.push(".1");
+        fs::rename(path, &rotated).with_context(|| format!("Failed to rotate {:?}", path))?;
+    }
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    let mut file = open_append(path).with_context(|| format!("Failed to open {:?}", path))?;
+    file.write_all(&line)
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn open_append(path: &Path) -> std::io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn open_append(path: &Path) -> std::io::Result<fs::File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Mask words that look like credentials or email addresses, keeping whitespace intact
+pub fn redact(text: &str) -> String {
+    text.split_inclusive(char::is_whitespace)
+        .map(|piece| {
+            let word = piece.trim_end();
+            let core = word.trim_matches(|c: char| "\"'`()[]{}<>,;".contains(c));
+            if looks_secret(core) {
+                piece.replacen(core, REDACTED, 1)
+            } else {
+                piece.to_string()
+            }
+        })
+        .collect()
+}
+
+fn looks_secret(word: &str) -> bool {
+    const SECRET_PREFIXES: [&str; 8] = [
+        "sk-",
+        "crk-",
+        "ghp_",
+        "gho_",
+        "github_pat_",
+        "xoxb-",
+        "xoxp-",
+        "AKIA",
+    ];
+    if word.is_empty() {
+        return false;
+    }
+    if SECRET_PREFIXES
+        .iter()
+        .any(|prefix| word.starts_with(prefix))
+    {
+        return true;
+    }
+    if word.eq_ignore_ascii_case("bearer") {
+        return false;
+    }
+    let is_email = word
+        .split_once('@')
+        .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'));
+    // Long runs of key-like characters with both letters and digits: tokens, JWTs, hashes
+    let is_token = word.len() >= 32
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.=+/".contains(c))
+        && word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().any(|c| c.is_ascii_alphabetic());
+    is_email || is_token
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Picks token usage out of a Responses API event stream as it passes through
+#[derive(Debug, Default)]
+pub struct UsageScanner {
+    line: Vec<u8>,
+    usage: Option<TokenUsage>,
+}
+
+impl UsageScanner {
+    pub fn feed(&mut self, chunk: &[u8]) {
+        for &byte in chunk {
+            if byte == b'\n' {
+                self.scan_line();
+                self.line.clear();
+            } else {
+                self.line.push(byte);
+            }
+        }
+    }
+
+    pub fn usage(&self) -> Option<TokenUsage> {
+        self.usage
+    }
+
+    fn scan_line(&mut self) {
+        let Some(data) = self.line.strip_prefix(b"data:") else {
+            return;
+        };
+        // Only the final event carries usage; skip parsing the text deltas
+        if !data.windows(7).any(|window| window == b"\"usage\"") {
+            return;
+        }
+        let Ok(event) = serde_json::from_slice::<serde_json::Value>(data) else {
+            return;
+        };
+        let usage = event
+            .get("response")
+            .and_then(|response| response.get("usage"))
+            .or_else(|| event.get("usage"));
+        if let Some(usage) = usage {
+            let tokens = |key: &str| usage.get(key).and_then(|value| value.as_u64());
+            if let (Some(input_tokens), Some(output_tokens)) =
+                (tokens("input_tokens"), tokens("output_tokens"))
+            {
+                self.usage = Some(TokenUsage {
+                    input_tokens,
+                    output_tokens,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(client: &str, status: u16) -> RequestLogEntry {
+        RequestLogEntry {
+            timestamp: Utc::now(),
+            client: client.to_string(),
+            model: Some("gpt-5".to_string()),
+            profile: Some("work".to_string()),
+            attempts: 1,
+            status,
+            latency_ms: 120,
+            usage: None,
+            error: None,
+            prompt: None,
+        }
+    }
+
+    #[test]
+    fn redacts_credentials_and_emails_but_keeps_text() {
+        let text = "Use key sk-proj-abc123 and mail jane@example.com.\n\
+                    Header: Bearer eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxMjM0NTY3ODkwIn0.abc";
+        let redacted = redact(text);
+
+        assert!(!redacted.contains("sk-proj"));
+        assert!(!redacted.contains("jane@"));
+        assert!(!redacted.contains("eyJhbGci"));
+        assert!(redacted.starts_with("Use key [REDACTED] and mail [REDACTED]\n"));
+        assert!(redacted.contains("Header: Bearer [REDACTED]"));
+    }
+
+    #[test]
+    fn prompt_capture_follows_the_setting() {
+        let messages = vec![
+            serde_json::json!({"role": "system", "content": "Be brief"}),
+            serde_json::json!({"role": "user", "content": "my key is sk-live-123"}),
+        ];
+        assert_eq!(PromptLogging::Off.capture(&messages), None);
+        assert_eq!(
+            PromptLogging::Redacted.capture(&messages).as_deref(),
+            Some("system: Be brief\nuser: my key is [REDACTED]")
+        );
+        assert!(PromptLogging::Full
+            .capture(&messages)
+            .unwrap()
+            .contains("sk-live-123"));
+    }
+
+    #[test]
+    fn scanner_reads_usage_split_across_chunks() {
+        let mut scanner = UsageScanner::default();
+        scanner.feed(b"event: response.output_text.delta\ndata: {\"delta\":\"Hi\"}\n\n");
+        scanner.feed(b"event: response.completed\ndata: {\"type\":\"response.completed\",");
+        scanner.feed(b"\"response\":{\"usage\":{\"input_tokens\":12,\"output_tokens\":3}}}\n\n");
+
+        assert_eq!(
+            scanner.usage(),
+            Some(TokenUsage {
+                input_tokens: 12,
+                output_tokens: 3
+            })
+        );
+    }
+
+    #[test]
+    fn persists_entries_and_reloads_them() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("requests.jsonl");
+
+        let log = RequestLog::default();
+        log.attach_file(path.clone()).unwrap();
+        log.record(entry("ci", 200));
+        log.record(entry("10.0.0.7", 503));
+        drop(log);
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "{\"truncated\n").unwrap();
+
+        let reloaded = RequestLog::default();
+        reloaded.attach_file(path).unwrap();
+        let entries = reloaded.recent();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[1].matches("503"));
+        assert!(entries[1].matches("10.0.0"));
+        assert!(!entries[0].matches("10.0.0"));
+    }
+}
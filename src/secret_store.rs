@@ -0,0 +1,561 @@
+//! Storage for profile credentials.
+//!
+//! Profile `auth.json` files are either plaintext JSON or sealed envelopes encrypted
+//! with a key derived from a passphrase. The vault settings live in
+//! `router/vault.json`; when that file exists new writes are sealed. Reads accept
+//! both formats, so a partially migrated profiles directory stays usable.
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::atomic_file::write_atomic;
+use crate::config::{get_profiles_dir, get_trash_dir, get_vault_file};
+
+/// Marker field identifying a sealed file
+const ENVELOPE_MARKER: &str = "codex_router_vault";
+const ENVELOPE_VERSION: u32 = 1;
+const VERIFIER_PLAINTEXT: &[u8] = b"codex-router-vault";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+/// Environment variable used to unlock the vault without the UI
+pub const PASSPHRASE_ENV: &str = "CODEX_ROUTER_VAULT_PASSPHRASE";
+
+/// An AES key, wiped from memory when dropped
+type Key = Zeroizing<[u8; KEY_LEN]>;
+
+/// Key of the unlocked vault, tagged with the vault file it belongs to
+static UNLOCKED: Mutex<Option<(PathBuf, Key)>> = Mutex::new(None);
+
+/// A passphrase that never shows up in debug output and is wiped when dropped
+#[derive(Clone, PartialEq, Eq)]
+pub struct Passphrase(Zeroizing<String>);
+
+impl Passphrase {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(Zeroizing::new(value.into()))
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Passphrase(***)")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageMode {
+    Plaintext,
+    Vault,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VaultStatus {
+    pub mode: StorageMode,
+    pub unlocked: bool,
+}
+
+impl VaultStatus {
+    pub fn is_locked(&self) -> bool {
+        self.mode == StorageMode::Vault && !self.unlocked
+    }
+}
+
+/// How credentials are encoded on disk
+pub trait SecretBackend {
+    fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>>;
+    fn open(&self, stored: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Stores credentials as-is
+pub struct PlaintextBackend;
+
+impl SecretBackend for PlaintextBackend {
+    fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        Ok(plaintext.to_vec())
+    }
+
+    fn open(&self, stored: &[u8]) -> Result<Vec<u8>> {
+        if is_sealed(stored) {
+            anyhow::bail!("Credential vault is locked. Unlock it with your passphrase.");
+        }
+        Ok(stored.to_vec())
+    }
+}
+
+/// Encrypts credentials with AES-256-GCM under the vault key
+pub struct VaultBackend {
+    key: Key,
+}
+
+impl SecretBackend for VaultBackend {
+    fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let envelope = encrypt(&self.key, plaintext)?;
+        Ok(serde_json::to_vec_pretty(&envelope)?)
+    }
+
+    fn open(&self, stored: &[u8]) -> Result<Vec<u8>> {
+        if !is_sealed(stored) {
+            return Ok(stored.to_vec());
+        }
+        let envelope: Envelope =
+            serde_json::from_slice(stored).context("Failed to parse sealed credentials")?;
+        decrypt(&self.key, &envelope)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Envelope {
+    #[serde(rename = "codex_router_vault")]
+    version: u32,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+}
+
+impl KdfParams {
+    fn generate(m_cost: u32, t_cost: u32, p_cost: u32) -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        rand::rng().fill_bytes(&mut salt);
+        Self {
+            algorithm: "argon2id".to_string(),
+            m_cost,
+            t_cost,
+            p_cost,
+            salt: STANDARD.encode(salt),
+        }
+    }
+
+    fn derive_key(&self, passphrase: &Passphrase) -> Result<Key> {
+        if self.algorithm != "argon2id" {
+            anyhow::bail!("Unsupported vault key derivation: {}", self.algorithm);
+        }
+        let salt = STANDARD.decode(&self.salt).context("Invalid vault salt")?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(KEY_LEN))
+            .map_err(|err| anyhow::anyhow!("Invalid vault parameters: {err}"))?;
+        let mut key = Key::new([0u8; KEY_LEN]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|err| anyhow::anyhow!("Failed to derive vault key: {err}"))?;
+        Ok(key)
+    }
+}
+
+/// Contents of `router/vault.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultConfig {
+    kdf: KdfParams,
+    /// A known value sealed with the key, used to reject wrong passphrases
+    verifier: Envelope,
+}
+
+/// Data encrypted under its own passphrase rather than the vault key, e.g. export bundles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassphraseSealed {
+    kdf: KdfParams,
+    payload: Envelope,
+}
+
+/// Encrypt `plaintext` with a key derived from `passphrase` (argon2 defaults).
+pub fn seal_with_passphrase(passphrase: &Passphrase, plaintext: &[u8]) -> Result<PassphraseSealed> {
+    let defaults = Params::default();
+    seal_with_passphrase_params(
+        passphrase,
+        plaintext,
+        defaults.m_cost(),
+        defaults.t_cost(),
+        defaults.p_cost(),
+    )
+}
+
+pub(crate) fn seal_with_passphrase_params(
+    passphrase: &Passphrase,
+    plaintext: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<PassphraseSealed> {
+    if passphrase.as_bytes().is_empty() {
+        anyhow::bail!("Passphrase must not be empty");
+    }
+    let kdf = KdfParams::generate(m_cost, t_cost, p_cost);
+    let key = kdf.derive_key(passphrase)?;
+    Ok(PassphraseSealed {
+        payload: encrypt(&key, plaintext)?,
+        kdf,
+    })
+}
+
+pub fn open_with_passphrase(passphrase: &Passphrase, sealed: &PassphraseSealed) -> Result<Vec<u8>> {
+    let key = sealed.kdf.derive_key(passphrase)?;
+    decrypt(&key, &sealed.payload)
+}
+
+fn is_sealed(stored: &[u8]) -> bool {
+    serde_json::from_slice::<serde_json::Value>(stored)
+        .ok()
+        .is_some_and(|value| value.get(ENVELOPE_MARKER).is_some())
+}
+
+fn encrypt(key: &[u8; KEY_LEN], plaintext: &[u8]) -> Result<Envelope> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|err| anyhow::anyhow!("Invalid vault key: {err}"))?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rng().fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt credentials"))?;
+    Ok(Envelope {
+        version: ENVELOPE_VERSION,
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+}
+
+fn decrypt(key: &[u8; KEY_LEN], envelope: &Envelope) -> Result<Vec<u8>> {
+    if envelope.version != ENVELOPE_VERSION {
+        anyhow::bail!("Unsupported sealed credential version {}", envelope.version);
+    }
+    let nonce = STANDARD
+        .decode(&envelope.nonce)
+        .context("Invalid sealed credential nonce")?;
+    if nonce.len() != NONCE_LEN {
+        anyhow::bail!("Invalid sealed credential nonce");
+    }
+    let ciphertext = STANDARD
+        .decode(&envelope.ciphertext)
+        .context("Invalid sealed credential payload")?;
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|err| anyhow::anyhow!("Invalid vault key: {err}"))?;
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| anyhow::anyhow!("Failed to decrypt credentials (wrong passphrase?)"))
+}
+
+fn load_vault_config() -> Result<Option<VaultConfig>> {
+    let vault_file = get_vault_file()?;
+    if !vault_file.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(&vault_file)
+        .with_context(|| format!("Failed to read vault config: {:?}", vault_file))?;
+    let config = serde_json::from_str(&contents).with_context(|| "Failed to parse vault config")?;
+    Ok(Some(config))
+}
+
+fn unlocked_key() -> Result<Option<Key>> {
+    let vault_file = get_vault_file()?;
+    let unlocked = UNLOCKED.lock().unwrap_or_else(|err| err.into_inner());
+    Ok(unlocked
+        .as_ref()
+        .filter(|(path, _)| *path == vault_file)
+        .map(|(_, key)| key.clone()))
+}
+
+/// Replacing the key, e.g. with `None` to lock, drops and thereby wipes the old one
+fn set_unlocked_key(key: Option<Key>) -> Result<()> {
+    let vault_file = get_vault_file()?;
+    let mut unlocked = UNLOCKED.lock().unwrap_or_else(|err| err.into_inner());
+    *unlocked = key.map(|key| (vault_file, key));
+    Ok(())
+}
+
+pub fn status() -> Result<VaultStatus> {
+    if load_vault_config()?.is_none() {
+        return Ok(VaultStatus {
+            mode: StorageMode::Plaintext,
+            unlocked: false,
+        });
+    }
+    Ok(VaultStatus {
+        mode: StorageMode::Vault,
+        unlocked: unlocked_key()?.is_some(),
+    })
+}
+
+/// The backend used for new writes.
+///
+/// Fails while the vault is locked so credentials are never written in the clear
+/// by accident.
+pub fn backend() -> Result<Box<dyn SecretBackend>> {
+    if load_vault_config()?.is_none() {
+        return Ok(Box::new(PlaintextBackend));
+    }
+    match unlocked_key()? {
+        Some(key) => Ok(Box::new(VaultBackend { key })),
+        None => anyhow::bail!("Credential vault is locked. Unlock it with your passphrase."),
+    }
+}
+
+/// Read a credential file, decrypting it when it is sealed.
+pub fn read_secret(path: &Path) -> Result<Vec<u8>> {
+    let stored = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+    if !is_sealed(&stored) {
+        return Ok(stored);
+    }
+    match unlocked_key()? {
+        Some(key) => VaultBackend { key }.open(&stored),
+        None => PlaintextBackend.open(&stored),
+    }
+}
+
+/// Write a credential file with the active backend and owner-only permissions.
+pub fn write_secret(path: &Path, plaintext: &[u8]) -> Result<()> {
+    let stored = backend()?.seal(plaintext)?;
+    write_atomic(path, &stored)
+}
+
+/// Unlock the vault for this process.
+pub fn unlock(passphrase: &Passphrase) -> Result<()> {
+    let config = load_vault_config()?.context("Credential vault is not enabled")?;
+    let key = config.kdf.derive_key(passphrase)?;
+    let verified = decrypt(&key, &config.verifier)
+        .map(|plaintext| plaintext == VERIFIER_PLAINTEXT)
+        .unwrap_or(false);
+    if !verified {
+        anyhow::bail!("Wrong vault passphrase");
+    }
+    set_unlocked_key(Some(key))
+}
+
+/// Unlock the vault from `CODEX_ROUTER_VAULT_PASSPHRASE` if it is set.
+pub fn unlock_from_env() -> Result<bool> {
+    let Some(passphrase) = std::env::var(PASSPHRASE_ENV)
+        .ok()
+        .filter(|value| !value.is_empty())
+    else {
+        return Ok(false);
+    };
+    if status()?.mode != StorageMode::Vault {
+        return Ok(false);
+    }
+    unlock(&Passphrase::new(passphrase))?;
+    Ok(true)
+}
+
+pub fn lock() -> Result<()> {
+    set_unlocked_key(None)
+}
+
+/// Create the vault and encrypt every existing profile with it.
+pub fn enable(passphrase: &Passphrase) -> Result<usize> {
+    let defaults = Params::default();
+    enable_with_params(
+        passphrase,
+        defaults.m_cost(),
+        defaults.t_cost(),
+        defaults.p_cost(),
+    )
+}
+
+fn enable_with_params(
+    passphrase: &Passphrase,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<usize> {
+    if load_vault_config()?.is_some() {
+        anyhow::bail!("Credential vault is already enabled");
+    }
+    if passphrase.as_bytes().is_empty() {
+        anyhow::bail!("Vault passphrase must not be empty");
+    }
+
+    // Read everything before switching modes so a bad file aborts early.
+    let profiles = read_all_profile_secrets()?;
+
+    let kdf = KdfParams::generate(m_cost, t_cost, p_cost);
+    let key = kdf.derive_key(passphrase)?;
+    let config = VaultConfig {
+        kdf,
+        verifier: encrypt(&key, VERIFIER_PLAINTEXT)?,
+    };
+    let vault_file = get_vault_file()?;
+    if let Some(parent) = vault_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(&vault_file, &serde_json::to_vec_pretty(&config)?)?;
+    set_unlocked_key(Some(key.clone()))?;
+
+    rewrite_profile_secrets(&profiles, &VaultBackend { key })
+}
+
+/// Decrypt every profile back to plaintext and remove the vault.
+pub fn disable() -> Result<usize> {
+    let status = status()?;
+    if status.mode != StorageMode::Vault {
+        anyhow::bail!("Credential vault is not enabled");
+    }
+    if !status.unlocked {
+        anyhow::bail!("Unlock the credential vault before disabling it");
+    }
+
+    // The vault stays in place until every profile is plaintext again, so a failed
+    // write leaves the remaining sealed files readable with the same passphrase.
+    let profiles = read_all_profile_secrets()?;
+    let rewritten = rewrite_profile_secrets(&profiles, &PlaintextBackend)?;
+    fs::remove_file(get_vault_file()?)?;
+    set_unlocked_key(None)?;
+    Ok(rewritten)
+}
+
+/// Credentials of every profile, including those in the trash
+fn read_all_profile_secrets() -> Result<Vec<(PathBuf, Vec<u8>)>> {
+    let mut secrets = Vec::new();
+    for dir in [get_profiles_dir()?, get_trash_dir()?] {
+        if !dir.exists() {
+            continue;
+        }
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let auth_file = entry.path().join("auth.json");
+            if !auth_file.is_file() {
+                continue;
+            }
+            let plaintext = read_secret(&auth_file)?;
+            secrets.push((auth_file, plaintext));
+        }
+    }
+    Ok(secrets)
+}
+
+fn rewrite_profile_secrets(
+    secrets: &[(PathBuf, Vec<u8>)],
+    backend: &dyn SecretBackend,
+) -> Result<usize> {
+    for (path, plaintext) in secrets {
+        write_atomic(path, &backend.seal(plaintext)?)?;
+    }
+    Ok(secrets.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{EnvGuard, ENV_LOCK};
+
+    // Cheap parameters keep the tests fast; production uses the argon2 defaults.
+    fn enable_for_test(passphrase: &str) -> usize {
+        enable_with_params(&Passphrase::new(passphrase), 256, 1, 1).unwrap()
+    }
+
+    fn write_profile(root: &Path, name: &str, contents: &str) -> PathBuf {
+        let dir = root.join("profiles").join(name);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("auth.json");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn plaintext_mode_reads_and_writes_as_is() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        let path = write_profile(temp_dir.path(), "work", "{}");
+
+        write_secret(&path, br#"{"OPENAI_API_KEY":"sk"}"#).unwrap();
+
+        assert_eq!(status().unwrap().mode, StorageMode::Plaintext);
+        assert_eq!(fs::read(&path).unwrap(), br#"{"OPENAI_API_KEY":"sk"}"#);
+        assert_eq!(read_secret(&path).unwrap(), br#"{"OPENAI_API_KEY":"sk"}"#);
+    }
+
+    #[test]
+    fn enable_migrates_profiles_and_lock_blocks_access() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        let contents = r#"{"OPENAI_API_KEY":null,"tokens":{"access_token":"secret-access","refresh_token":"r","account_id":null}}"#;
+        let path = write_profile(temp_dir.path(), "work", contents);
+
+        assert_eq!(enable_for_test("correct horse"), 1);
+
+        let stored = fs::read_to_string(&path).unwrap();
+        assert!(!stored.contains("secret-access"));
+        assert!(stored.contains(ENVELOPE_MARKER));
+        assert_eq!(read_secret(&path).unwrap(), contents.as_bytes());
+
+        lock().unwrap();
+        assert!(status().unwrap().is_locked());
+        assert!(read_secret(&path).is_err());
+        assert!(write_secret(&path, b"{}").is_err());
+
+        assert!(unlock(&Passphrase::new("wrong")).is_err());
+        unlock(&Passphrase::new("correct horse")).unwrap();
+        assert_eq!(read_secret(&path).unwrap(), contents.as_bytes());
+    }
+
+    #[test]
+    fn disable_restores_plaintext() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        let path = write_profile(temp_dir.path(), "work", r#"{"OPENAI_API_KEY":"sk"}"#);
+        write_profile(temp_dir.path(), "old", r#"{"OPENAI_API_KEY":"sk-old"}"#);
+        crate::trash::trash_profile("old", chrono::Utc::now()).unwrap();
+        let trashed = get_trash_dir()
+            .unwrap()
+            .read_dir()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path()
+            .join("auth.json");
+        assert_eq!(enable_for_test("pass"), 2);
+        assert!(is_sealed(&fs::read(&trashed).unwrap()));
+
+        assert_eq!(disable().unwrap(), 2);
+
+        assert_eq!(status().unwrap().mode, StorageMode::Plaintext);
+        assert_eq!(fs::read(&path).unwrap(), br#"{"OPENAI_API_KEY":"sk"}"#);
+        assert_eq!(
+            fs::read(&trashed).unwrap(),
+            br#"{"OPENAI_API_KEY":"sk-old"}"#
+        );
+        assert!(!get_vault_file().unwrap().exists());
+    }
+
+    #[test]
+    fn passphrase_sealed_round_trips() {
+        let passphrase = Passphrase::new("bundle-pass");
+        let sealed = seal_with_passphrase_params(&passphrase, b"secret", 256, 1, 1).unwrap();
+
+        assert_eq!(
+            open_with_passphrase(&passphrase, &sealed).unwrap(),
+            b"secret"
+        );
+        assert!(open_with_passphrase(&Passphrase::new("wrong"), &sealed).is_err());
+    }
+
+    #[test]
+    fn passphrase_debug_is_redacted() {
+        let passphrase = Passphrase::new("hunter2");
+        assert!(!format!("{passphrase:?}").contains("hunter2"));
+    }
+}
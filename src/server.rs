@@ -1,37 +1,795 @@
 use axum::{
-    extract::{Json, State},
+    body::Body,
+    extract::{ConnectInfo, Json, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
-    routing::post,
-    Router,
+    routing::{get, post},
+    serve::ListenerExt,
+    Extension, Router,
 };
+use chrono::Local;
+use futures_util::{FutureExt, StreamExt};
 use std::collections::HashMap;
-use std::net::SocketAddr;
-use std::sync::Arc;
-use tower_http::cors::CorsLayer;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tower_http::cors::{AllowHeaders, AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 
-use crate::auth;
-use crate::profile::ProfileSummary;
+use crate::admin;
+use crate::api::{self, CreditsInfo, RateLimitWindow};
+use crate::app_state::AppCommand;
+use crate::auth::{self, AuthKind};
+use crate::client_keys::{self, ClientKey};
+use crate::config::get_api_socket_file;
+use crate::metrics::has_token_delta;
+use crate::profile::{ProfileHealth, ProfileSummary};
+use crate::rate_limit::{ClientId, RateLimited};
+use crate::request_log::{RequestLogEntry, UsageScanner};
 use crate::shared::SharedState;
+use crate::state::RouterState;
+use crate::tls::{self, TlsIdentity, TlsListener, TlsSettings};
 
-pub async fn start_server(state: Arc<SharedState>) {
-    // Add CORS layer to allow all origins/methods/headers for local dev
+pub const DEFAULT_HOST: &str = "127.0.0.1";
+pub const DEFAULT_PORT: u16 = 9876;
+
+/// How long a restart waits for in-flight requests before cutting them off
+const RESTART_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Where the API server listens: TCP on host and port, a Unix socket, or both
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: TlsSettings,
+    pub tcp_enabled: bool,
+    pub unix_socket: Option<PathBuf>,
+}
+
+impl Default for BindConfig {
+    fn default() -> Self {
+        Self {
+            host: DEFAULT_HOST.to_string(),
+            port: DEFAULT_PORT,
+            tls: TlsSettings::default(),
+            tcp_enabled: true,
+            unix_socket: None,
+        }
+    }
+}
+
+impl BindConfig {
+    /// The configured address with any `--host`/`--port` or environment override applied
+    pub fn resolve(router_state: &RouterState, bind_override: &BindOverride) -> Self {
+        Self {
+            host: bind_override
+                .host
+                .clone()
+                .unwrap_or_else(|| router_state.server_host.clone()),
+            port: bind_override.port.unwrap_or(router_state.server_port),
+            tls: router_state.tls.clone(),
+            tcp_enabled: router_state.tcp_enabled,
+            unix_socket: router_state
+                .unix_socket_enabled
+                .then(|| {
+                    router_state
+                        .unix_socket_path
+                        .clone()
+                        .or_else(|| get_api_socket_file().ok())
+                })
+                .flatten(),
+        }
+    }
+
+    pub fn scheme(&self) -> &'static str {
+        if self.tls.enabled {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// Whether only this machine can reach the server
+    pub fn is_loopback(&self) -> bool {
+        self.host.eq_ignore_ascii_case("localhost")
+            || self
+                .host
+                .parse::<IpAddr>()
+                .is_ok_and(|addr| addr.is_loopback())
+    }
+}
+
+impl std::fmt::Display for BindConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Bind settings given on the command line or in the environment, which take
+/// precedence over the saved router settings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BindOverride {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+}
+
+impl BindOverride {
+    /// `CODEX_ROUTER_HOST`/`CODEX_ROUTER_PORT`, overridden in turn by `--host`/`--port`
+    pub fn from_env_and_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Self> {
+        let mut bind_override = Self {
+            host: std::env::var("CODEX_ROUTER_HOST")
+                .ok()
+                .filter(|host| !host.trim().is_empty()),
+            port: match std::env::var("CODEX_ROUTER_PORT") {
+                Ok(port) => Some(parse_port(&port)?),
+                Err(_) => None,
+            },
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            if flag != "--host" && flag != "--port" {
+                continue;
+            }
+            let value = inline_value
+                .or_else(|| args.next())
+                .ok_or_else(|| anyhow::anyhow!("{flag} needs a value"))?;
+            if flag == "--host" {
+                bind_override.host = Some(value);
+            } else {
+                bind_override.port = Some(parse_port(&value)?);
+            }
+        }
+        Ok(bind_override)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.host.is_none() && self.port.is_none()
+    }
+}
+
+fn parse_port(value: &str) -> anyhow::Result<u16> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid port '{}'", value))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerStatus {
+    Starting,
+    /// Listening on TCP, and on the configured Unix socket if any
+    Listening(SocketAddr),
+    /// Listening on the Unix socket only
+    ListeningOnSocket(PathBuf),
+    Failed(String),
+}
+
+/// A running API server that can be restarted on a new address
+pub struct ServerHandle {
+    config: BindConfig,
+    tls_fingerprint: Option<String>,
+    status: Arc<RwLock<ServerStatus>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+impl ServerHandle {
+    pub fn config(&self) -> &BindConfig {
+        &self.config
+    }
+
+    /// SHA-256 fingerprint of the certificate when serving HTTPS
+    pub fn tls_fingerprint(&self) -> Option<&str> {
+        self.tls_fingerprint.as_deref()
+    }
+
+    pub fn status(&self) -> ServerStatus {
+        self.status
+            .read()
+            .map(|status| status.clone())
+            .unwrap_or(ServerStatus::Starting)
+    }
+
+    /// Stop this server and start one on `config`. The new server binds once the old one
+    /// has let go of its socket, so restarting on the same port works.
+    pub fn restart(mut self, state: Arc<SharedState>, config: BindConfig) -> ServerHandle {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let mut previous = self.task;
+        spawn_server_after(state, config, async move {
+            if tokio::time::timeout(RESTART_GRACE_PERIOD, &mut previous)
+                .await
+                .is_err()
+            {
+                previous.abort();
+                let _ = previous.await;
+            }
+        })
+    }
+}
+
+/// Start the API server in the background. Bind failures are reported through
+/// [`ServerHandle::status`] instead of taking the task down.
+pub fn spawn_server(state: Arc<SharedState>, config: BindConfig) -> ServerHandle {
+    spawn_server_after(state, config, async {})
+}
+
+fn spawn_server_after(
+    state: Arc<SharedState>,
+    config: BindConfig,
+    ready: impl std::future::Future<Output = ()> + Send + 'static,
+) -> ServerHandle {
+    let status = Arc::new(RwLock::new(ServerStatus::Starting));
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    let identity = (config.tcp_enabled && config.tls.enabled)
+        .then(|| tls::load_identity(&config.tls, &config.host))
+        .transpose();
+    let tls_fingerprint = match &identity {
+        Ok(Some(identity)) => Some(identity.fingerprint.clone()),
+        _ => None,
+    };
+    let task_status = status.clone();
+    let task_config = config.clone();
+    let task = tokio::spawn(async move {
+        ready.await;
+        let result = match identity {
+            Ok(identity) => {
+                run_server(state, &task_config, identity, &task_status, shutdown_rx).await
+            }
+            Err(err) => Err(err.context("Failed to set up HTTPS")),
+        };
+        if let Err(err) = result {
+            tracing::error!("API server on {} failed: {:#}", task_config, err);
+            set_status(&task_status, ServerStatus::Failed(format!("{err:#}")));
+        }
+    });
+    ServerHandle {
+        config,
+        tls_fingerprint,
+        status,
+        shutdown: Some(shutdown_tx),
+        task,
+    }
+}
+
+fn set_status(status: &RwLock<ServerStatus>, value: ServerStatus) {
+    if let Ok(mut lock) = status.write() {
+        *lock = value;
+    }
+}
+
+async fn run_server(
+    state: Arc<SharedState>,
+    config: &BindConfig,
+    identity: Option<TlsIdentity>,
+    status: &RwLock<ServerStatus>,
+    shutdown: oneshot::Receiver<()>,
+) -> anyhow::Result<()> {
+    if !config.tcp_enabled && config.unix_socket.is_none() {
+        anyhow::bail!("Neither TCP nor a Unix socket is enabled");
+    }
+    let tcp = match config.tcp_enabled {
+        true => Some(
+            tokio::net::TcpListener::bind((config.host.as_str(), config.port))
+                .await
+                .map_err(|err| anyhow::anyhow!("Failed to bind {}: {}", config, err))?,
+        ),
+        false => None,
+    };
+    let unix = match &config.unix_socket {
+        Some(path) => Some(UnixSocket::bind(path)?),
+        None => None,
+    };
+
+    match &tcp {
+        Some(listener) => {
+            let addr = listener.local_addr()?;
+            tracing::info!("Listening on {}://{}", config.scheme(), addr);
+            set_status(status, ServerStatus::Listening(addr));
+        }
+        None => set_status(
+            status,
+            ServerStatus::ListeningOnSocket(config.unix_socket.clone().unwrap_or_default()),
+        ),
+    }
+
+    let shutdown = shutdown.map(|_| ()).shared();
+    let app = router(state);
+    let serve_tcp = async {
+        let Some(listener) = tcp else {
+            return Ok(());
+        };
+        let app = app
+            .clone()
+            .into_make_service_with_connect_info::<SocketAddr>();
+        match identity {
+            // `tap_io` gives the listener axum's `ConnectInfo<SocketAddr>` support
+            Some(identity) => {
+                axum::serve(TlsListener::new(listener, &identity)?.tap_io(|_| {}), app)
+                    .with_graceful_shutdown(shutdown.clone())
+                    .await
+            }
+            None => {
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(shutdown.clone())
+                    .await
+            }
+        }
+    };
+    let serve_unix = async {
+        match unix {
+            Some(socket) => socket.serve(app.clone(), shutdown.clone()).await,
+            None => Ok(()),
+        }
+    };
+    tokio::try_join!(serve_tcp, serve_unix)?;
+    Ok(())
+}
+
+/// A Unix domain socket only its owner can connect to, removed again on shutdown
+struct UnixSocket {
+    path: PathBuf,
+    #[cfg(unix)]
+    listener: tokio::net::UnixListener,
+}
+
+impl UnixSocket {
+    #[cfg(unix)]
+    fn bind(path: &Path) -> anyhow::Result<Self> {
+        use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+
+        let bind_error =
+            |err: std::io::Error| anyhow::anyhow!("Failed to bind {}: {}", path.display(), err);
+        if let Ok(meta) = std::fs::symlink_metadata(path) {
+            if !meta.file_type().is_socket() {
+                return Err(bind_error(std::io::Error::from(
+                    std::io::ErrorKind::AlreadyExists,
+                )));
+            }
+            // Only a socket left behind by a crash refuses connections
+            match std::os::unix::net::UnixStream::connect(path) {
+                Ok(_) => {
+                    return Err(bind_error(std::io::Error::from(
+                        std::io::ErrorKind::AddrInUse,
+                    )))
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
+                    std::fs::remove_file(path)?;
+                }
+                Err(err) => return Err(bind_error(err)),
+            }
+        }
+        let parent = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        std::fs::create_dir_all(parent)?;
+
+        // Bind inside an owner-only directory so the socket is never reachable with the
+        // umask's permissions, then link it into place without replacing anything there
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let private_dir = parent.join(format!(".{file_name}.{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&private_dir);
+        std::fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&private_dir)?;
+        let staged = private_dir.join("s");
+        let bound = tokio::net::UnixListener::bind(&staged).and_then(|listener| {
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+            std::fs::hard_link(&staged, path)?;
+            Ok(listener)
+        });
+        let _ = std::fs::remove_dir_all(&private_dir);
+        let listener = bound.map_err(bind_error)?;
+        tracing::info!("Listening on unix:{}", path.display());
+        Ok(Self {
+            path: path.to_path_buf(),
+            listener,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn bind(_path: &Path) -> anyhow::Result<Self> {
+        anyhow::bail!("Unix sockets are not supported on this platform")
+    }
+
+    #[cfg(unix)]
+    async fn serve(
+        self,
+        app: Router,
+        shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+    ) -> std::io::Result<()> {
+        let result = axum::serve(self.listener, app)
+            .with_graceful_shutdown(shutdown)
+            .await;
+        let _ = std::fs::remove_file(&self.path);
+        result
+    }
+
+    #[cfg(not(unix))]
+    async fn serve(
+        self,
+        _app: Router,
+        _shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn router(state: Arc<SharedState>) -> Router {
+    // Browsers may only call the router from allowlisted origins, read per request so
+    // edits apply without a restart. Other pages get no CORS headers and are blocked.
+    let cors_state = state.clone();
     let cors = CorsLayer::new()
-        .allow_origin(tower_http::cors::Any)
-        .allow_methods(tower_http::cors::Any)
-        .allow_headers(tower_http::cors::Any);
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| cors_state.is_cors_origin_allowed(origin))
+        }))
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers(AllowHeaders::mirror_request())
+        .max_age(Duration::from_secs(600));
 
-    let app = Router::new()
+    // Limits and the request log only cover proxied requests, not metrics scrapes
+    let proxy = Router::new()
         .route("/v1/chat/completions", post(handle_chat_completions))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            enforce_rate_limits,
+        ))
+        .route_layer(middleware::from_fn_with_state(state.clone(), log_requests));
+
+    Router::new()
+        .merge(proxy)
+        .route("/metrics", get(handle_metrics))
+        .route("/v1/router/status", get(handle_router_status))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_client_key,
+        ))
+        // Liveness needs no key so supervisors can probe it
+        .route("/healthz", get(handle_healthz))
+        // The admin API has its own token
+        .merge(admin::routes(state.clone()))
         .layer(TraceLayer::new_for_http())
         .layer(cors)
-        .with_state(state);
+        .with_state(state)
+}
+
+/// Normalize a browser origin such as `https://app.example.com`. Returns `None` if the
+/// entry is not a bare `http(s)://host[:port]`.
+pub fn normalize_cors_origin(origin: &str) -> Option<String> {
+    let origin = origin.trim().trim_end_matches('/').to_ascii_lowercase();
+    let host = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"))?;
+    let valid = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'));
+    valid.then_some(origin)
+}
+
+/// The client key a request was authenticated with
+#[derive(Debug, Clone)]
+pub struct ClientAccess(pub ClientKey);
+
+/// Once any client key has been issued, every request needs an active one as its
+/// `Authorization: Bearer` token. Revoking all keys does not reopen the server.
+async fn require_client_key(
+    State(state): State<Arc<SharedState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let keys = state.client_keys();
+    if keys.is_empty() {
+        return next.run(request).await;
+    }
+
+    let Some(key) = bearer_token(request.headers())
+        .and_then(|token| client_keys::find_active_key(&keys, token))
+        .cloned()
+    else {
+        return openai_error(
+            StatusCode::UNAUTHORIZED,
+            "invalid_request_error",
+            "invalid_api_key",
+            "Missing or invalid router API key. Create one in the Codex Router app.",
+        );
+    };
+
+    let key_id = key.id.clone();
+    request.extensions_mut().insert(ClientAccess(key));
+    let response = next.run(request).await;
+    state.record_client_request(&key_id, response.status().is_success());
+    response
+}
+
+/// Limits apply per client key, or per source IP for requests without one. A request
+/// keeps its concurrency slot until its response body, streamed or not, is finished.
+async fn enforce_rate_limits(
+    State(state): State<Arc<SharedState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (client, limits) = match request.extensions().get::<ClientAccess>() {
+        Some(ClientAccess(key)) => (ClientId::Key(key.id.clone()), key.limits),
+        None => match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(addr)) => (ClientId::Ip(addr.ip()), state.ip_rate_limits()),
+            None => return next.run(request).await,
+        },
+    };
+    if limits.is_unlimited() {
+        return next.run(request).await;
+    }
+
+    let permit = match state.rate_limiter.acquire(client, &limits, Local::now()) {
+        Ok(permit) => permit,
+        Err(limited) => return rate_limited(&limited),
+    };
+    let (parts, body) = next.run(request).await.into_parts();
+    let body = body.into_data_stream().map(move |chunk| {
+        let _permit = &permit;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+/// How the chat handler routed a request, passed to the request log in the response
+/// extensions
+#[derive(Debug, Clone, Default)]
+pub struct RouteOutcome {
+    pub model: String,
+    pub profile: Option<String>,
+    /// Upstream requests sent, one per profile tried
+    pub attempts: u32,
+    pub error: Option<String>,
+    pub prompt: Option<String>,
+}
+
+fn with_outcome(mut response: Response, outcome: RouteOutcome) -> Response {
+    response.extensions_mut().insert(outcome);
+    response
+}
+
+/// Record every authenticated request once its response body has finished, so
+/// streamed responses are logged with their full latency and token usage.
+async fn log_requests(
+    State(state): State<Arc<SharedState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = match request.extensions().get::<ClientAccess>() {
+        Some(ClientAccess(key)) => key.label.clone(),
+        None => match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(addr)) => addr.ip().to_string(),
+            None => "local".to_string(),
+        },
+    };
+    let started = Instant::now();
+    let (parts, body) = next.run(request).await.into_parts();
+    let outcome = parts
+        .extensions
+        .get::<RouteOutcome>()
+        .cloned()
+        .unwrap_or_default();
+    let mut pending = PendingLogEntry {
+        state,
+        started,
+        usage: UsageScanner::default(),
+        entry: RequestLogEntry {
+            timestamp: chrono::Utc::now(),
+            client,
+            model: Some(outcome.model).filter(|model| !model.is_empty()),
+            profile: outcome.profile,
+            attempts: outcome.attempts,
+            status: parts.status.as_u16(),
+            latency_ms: 0,
+            usage: None,
+            error: outcome.error,
+            prompt: outcome.prompt,
+        },
+    };
+    let body = body.into_data_stream().map(move |chunk| {
+        if let Ok(bytes) = &chunk {
+            pending.usage.feed(bytes);
+        }
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+/// Written to the request log when the response body is finished or dropped
+struct PendingLogEntry {
+    state: Arc<SharedState>,
+    started: Instant,
+    usage: UsageScanner,
+    entry: RequestLogEntry,
+}
+
+impl Drop for PendingLogEntry {
+    fn drop(&mut self) {
+        let mut entry = self.entry.clone();
+        entry.latency_ms = self.started.elapsed().as_millis() as u64;
+        entry.usage = self.usage.usage();
+        self.state.metrics.record_request(
+            entry.model.as_deref().unwrap_or_default(),
+            entry.profile.as_deref().unwrap_or_default(),
+            entry.status,
+        );
+        self.state.request_log.record(entry);
+    }
+}
+
+/// Prometheus metrics in the text exposition format
+async fn handle_metrics(State(state): State<Arc<SharedState>>) -> Response {
+    let profiles = state.profiles.read().unwrap().clone();
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.metrics.render(&profiles),
+    )
+        .into_response()
+}
+
+fn rate_limited(limited: &RateLimited) -> Response {
+    let mut response = openai_error(
+        StatusCode::TOO_MANY_REQUESTS,
+        "requests",
+        "rate_limit_exceeded",
+        &limited.message,
+    );
+    let seconds = limited.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+    response
+}
+
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+/// An error body in the shape OpenAI clients expect
+pub(crate) fn openai_error(
+    status: StatusCode,
+    error_type: &str,
+    code: &str,
+    message: &str,
+) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {
+                "message": message,
+                "type": error_type,
+                "param": null,
+                "code": code,
+            }
+        })),
+    )
+        .into_response()
+}
+
+async fn handle_healthz() -> Response {
+    Json(serde_json::json!({"status": "ok"})).into_response()
+}
+
+/// Capacity of the profiles a client may use, for dashboards and shell prompts
+#[derive(Debug, serde::Serialize)]
+pub struct RouterStatus {
+    /// Profile names in the order the next request would try them
+    pub routing_order: Vec<String>,
+    pub allow_credit_spending: bool,
+    pub allow_api_key_overflow: bool,
+    pub profiles: Vec<ProfileStatus>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ProfileStatus {
+    pub name: String,
+    pub email: Option<String>,
+    pub kind: &'static str,
+    /// The profile the Codex CLI is signed in with
+    pub current: bool,
+    pub valid: bool,
+    pub enabled: bool,
+    /// Why the profile's credentials cannot be used, if they cannot
+    pub problem: Option<String>,
+    /// Whether the profile is in the routing order
+    pub routable: bool,
+    pub quota: Option<QuotaStatus>,
+}
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 9876));
-    tracing::info!("Listening on {}", addr);
+#[derive(Debug, serde::Serialize)]
+pub struct QuotaStatus {
+    pub plan_type: String,
+    pub primary: Option<RateLimitWindow>,
+    pub secondary: Option<RateLimitWindow>,
+    pub fetched_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The last fetch failed, so these are the last known values
+    pub stale: bool,
+}
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+impl RouterStatus {
+    pub fn new(
+        profiles: Vec<ProfileSummary>,
+        allow_credit_spending: bool,
+        allow_api_key_overflow: bool,
+    ) -> Self {
+        let routing_order: Vec<String> = select_candidates(
+            profiles.clone(),
+            allow_credit_spending,
+            allow_api_key_overflow,
+        )
+        .into_iter()
+        .map(|p| p.name)
+        .collect();
+        let profiles = profiles
+            .into_iter()
+            .map(|p| ProfileStatus {
+                routable: routing_order.contains(&p.name),
+                problem: p.health.problem(),
+                kind: match p.auth_kind {
+                    AuthKind::ChatGpt => "chatgpt",
+                    AuthKind::ApiKey => "api_key",
+                },
+                current: p.is_current,
+                valid: p.is_valid,
+                enabled: p.meta.enabled,
+                quota: p.quota.map(|quota| QuotaStatus {
+                    plan_type: quota.plan_type,
+                    primary: quota.primary,
+                    secondary: quota.secondary,
+                    fetched_at: p.quota_fetched_at,
+                    stale: p.quota_stale,
+                }),
+                name: p.name,
+                email: p.email,
+            })
+            .collect();
+        Self {
+            routing_order,
+            allow_credit_spending,
+            allow_api_key_overflow,
+            profiles,
+        }
+    }
+}
+
+/// Profiles are limited to those the client's key may route to
+async fn handle_router_status(
+    State(state): State<Arc<SharedState>>,
+    client: Option<Extension<ClientAccess>>,
+) -> Response {
+    let mut profiles = state.profiles.read().unwrap().clone();
+    if let Some(Extension(ClientAccess(key))) = &client {
+        profiles.retain(|p| key.allows_profile(p));
+    }
+    Json(RouterStatus::new(
+        profiles,
+        state.allow_credit_spending(),
+        state.allow_api_key_overflow(),
+    ))
+    .into_response()
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -46,8 +804,33 @@ pub struct ChatRequest {
 
 pub async fn handle_chat_completions(
     State(state): State<Arc<SharedState>>,
+    client: Option<Extension<ClientAccess>>,
     Json(mut payload): Json<ChatRequest>,
 ) -> Response {
+    let received = Instant::now();
+    let client_key = client.map(|Extension(ClientAccess(key))| key);
+    let mut outcome = RouteOutcome {
+        model: payload.model.clone(),
+        prompt: state.prompt_logging().capture(&payload.messages),
+        ..RouteOutcome::default()
+    };
+    if let Some(key) = client_key
+        .as_ref()
+        .filter(|key| !key.allows_model(&payload.model))
+    {
+        outcome.error = Some("model not allowed for client key".to_string());
+        let response = openai_error(
+            StatusCode::FORBIDDEN,
+            "invalid_request_error",
+            "model_not_allowed",
+            &format!(
+                "The router API key '{}' may not use model '{}'.",
+                key.label, payload.model
+            ),
+        );
+        return with_outcome(response, outcome);
+    }
+
     // 1. Model Logic Adjustment
     if payload.model.to_lowercase().contains("mini") {
         let current_effort = payload.reasoning_effort.as_deref().unwrap_or("medium");
@@ -58,9 +841,24 @@ pub async fn handle_chat_completions(
     }
 
     // 2. Select Candidates
-    let profiles = state.profiles.read().unwrap().clone();
-    let profiles_missing_quota = profiles.iter().filter(|p| p.quota.is_none()).count();
-    let candidates = select_candidates(profiles);
+    let mut profiles = state.profiles.read().unwrap().clone();
+    if let Some(key) = &client_key {
+        profiles.retain(|p| key.allows_profile(p));
+    }
+    let profiles_disabled = profiles.iter().filter(|p| !p.meta.enabled).count();
+    let all_disabled = !profiles.is_empty() && profiles_disabled == profiles.len();
+    let profiles_missing_quota = profiles
+        .iter()
+        .filter(|p| p.meta.enabled && p.quota.is_none())
+        .count();
+    let allow_credit_spending = state.allow_credit_spending();
+    let profiles_with_credits = profiles.iter().filter(|p| has_available_credits(p)).count();
+    let allow_api_key_overflow = state.allow_api_key_overflow();
+    let profiles_with_api_keys = profiles
+        .iter()
+        .filter(|p| p.meta.enabled && p.auth_kind == AuthKind::ApiKey)
+        .count();
+    let candidates = select_candidates(profiles, allow_credit_spending, allow_api_key_overflow);
 
     if candidates.is_empty() {
         if profiles_missing_quota > 0 {
@@ -69,19 +867,39 @@ pub async fn handle_chat_completions(
                 "No routable profiles: some profiles have missing quota"
             );
         }
-        return (
+        outcome.error = Some("no routable profiles".to_string());
+        let response = (
             axum::http::StatusCode::SERVICE_UNAVAILABLE,
-            Json(if profiles_missing_quota > 0 {
+            Json(if all_disabled {
+                serde_json::json!({
+                    "error": "No available accounts with quota",
+                    "hint": "All profiles are disabled for routing. Enable one in the app.",
+                    "profiles_disabled": profiles_disabled,
+                })
+            } else if profiles_missing_quota > 0 {
                 serde_json::json!({
                     "error": "No available accounts with quota",
                     "hint": "Some profiles are missing quota. Refresh quotas (or re-login) and try again.",
                     "profiles_missing_quota": profiles_missing_quota,
                 })
+            } else if !allow_credit_spending && profiles_with_credits > 0 {
+                serde_json::json!({
+                    "error": "No available accounts with quota",
+                    "hint": "Some exhausted profiles have credits, but spending credits is disabled.",
+                    "profiles_with_credits": profiles_with_credits,
+                })
+            } else if !allow_api_key_overflow && profiles_with_api_keys > 0 {
+                serde_json::json!({
+                    "error": "No available accounts with quota",
+                    "hint": "API-key profiles could serve as paid overflow, but it is disabled.",
+                    "profiles_with_api_keys": profiles_with_api_keys,
+                })
             } else {
                 serde_json::json!({"error": "No available accounts with quota"})
             }),
         )
             .into_response();
+        return with_outcome(response, outcome);
     }
 
     // 3. Prepare Request for Upstream
@@ -140,7 +958,12 @@ pub async fn handle_chat_completions(
     };
 
     let body_json = serde_json::to_value(&responses_req).unwrap();
-    tracing::info!("Sending payload: {}", body_json);
+    tracing::debug!(
+        model = %responses_req.model,
+        messages = responses_req.input.len(),
+        "Prepared upstream payload"
+    );
+    let platform_body_json = without_item_ids(&body_json);
 
     // 4. Try Candidates
     let client = reqwest::Client::new();
@@ -156,46 +979,81 @@ pub async fn handle_chat_completions(
             }
         };
 
-        let access_token = match auth.tokens.as_ref().map(|t| t.access_token.clone()) {
-            Some(t) => t,
-            None => {
-                if let Some(key) = &auth.openai_api_key {
-                    key.clone()
-                } else {
+        let req = match AuthKind::of(&auth) {
+            AuthKind::ApiKey => {
+                let Some(api_key) = auth.openai_api_key.as_deref() else {
                     continue;
-                }
-            }
-        };
+                };
+                let url = format!("{}/responses", api::openai_base_url().trim_end_matches('/'));
+                tracing::info!("Using upstream URL: {}", url);
 
-        let base_url = std::env::var("CODEX_ROUTER_CHATGPT_BASE_URL")
-            .unwrap_or_else(|_| "https://chatgpt.com/backend-api".to_string());
+                client
+                    .post(&url)
+                    .header("Authorization", format!("Bearer {}", api_key))
+                    .json(&platform_body_json)
+            }
+            AuthKind::ChatGpt => {
+                let Some(tokens) = auth.tokens.as_ref() else {
+                    continue;
+                };
+                let base_url = std::env::var("CODEX_ROUTER_CHATGPT_BASE_URL")
+                    .unwrap_or_else(|_| "https://chatgpt.com/backend-api".to_string());
 
-        let url = format!("{}/codex/responses", base_url.trim_end_matches('/'));
-        tracing::info!("Using upstream URL: {}", url);
+                let url = format!("{}/codex/responses", base_url.trim_end_matches('/'));
+                tracing::info!("Using upstream URL: {}", url);
 
-        let mut req = client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .header("originator", "codex_cli_rs")
-            .header("User-Agent", "codex-cli")
-            .json(&body_json);
+                let mut req = client
+                    .post(&url)
+                    .header("Authorization", format!("Bearer {}", tokens.access_token))
+                    .header("originator", "codex_cli_rs")
+                    .header("User-Agent", "codex-cli")
+                    .json(&body_json);
 
-        if let Some(account_id) = auth::get_account_id(&auth) {
-            req = req.header("ChatGPT-Account-Id", account_id);
-        }
+                if let Some(account_id) = auth::get_account_id(&auth) {
+                    req = req.header("ChatGPT-Account-Id", account_id);
+                }
+                req
+            }
+        };
 
+        outcome.attempts += 1;
+        let sent = Instant::now();
         match req.send().await {
             Ok(resp) => {
+                state
+                    .metrics
+                    .observe_upstream_latency(&profile.name, sent.elapsed());
                 if resp.status().is_success() {
+                    state.record_proxied_request(&profile.name);
+                    // The worker owns meta.json writes; fails only when no worker runs
+                    let _ = state
+                        .admin
+                        .send(AppCommand::RecordProfileUse(profile.name.clone()));
                     let status = resp.status();
                     let headers = resp.headers().clone();
-                    let body = axum::body::Body::from_stream(resp.bytes_stream());
+                    let metrics = state.metrics.clone();
+                    let stream = metrics.start_stream();
+                    let profile_name = profile.name.clone();
+                    let mut awaiting_first_token = true;
+                    let body =
+                        axum::body::Body::from_stream(resp.bytes_stream().map(move |chunk| {
+                            let _stream = &stream;
+                            if awaiting_first_token
+                                && chunk.as_ref().is_ok_and(|bytes| has_token_delta(bytes))
+                            {
+                                awaiting_first_token = false;
+                                metrics
+                                    .observe_time_to_first_token(&profile_name, received.elapsed());
+                            }
+                            chunk
+                        }));
 
                     let mut builder = Response::builder().status(status);
                     for (key, value) in &headers {
                         builder = builder.header(key, value);
                     }
-                    return builder.body(body).unwrap_or_default();
+                    outcome.profile = Some(profile.name);
+                    return with_outcome(builder.body(body).unwrap_or_default(), outcome);
                 } else {
                     let status = resp.status();
                     let error_text = resp.text().await.unwrap_or_default();
@@ -205,37 +1063,55 @@ pub async fn handle_chat_completions(
                         status,
                         error_text
                     );
+                    outcome.error = Some(format!("{} returned {}", profile.name, status));
+                    state.metrics.record_failover(&profile.name);
                     continue;
                 }
             }
             Err(e) => {
                 tracing::warn!("Profile {} network error: {}, trying next", profile.name, e);
+                outcome.error = Some(format!("{}: {}", profile.name, e));
+                state.metrics.record_failover(&profile.name);
                 continue;
             }
         }
     }
 
-    (
+    let response = (
         axum::http::StatusCode::SERVICE_UNAVAILABLE,
         Json(serde_json::json!({"error": "All accounts failed or exhausted"})),
     )
-        .into_response()
+        .into_response();
+    with_outcome(response, outcome)
 }
 
-fn select_candidates(profiles: Vec<ProfileSummary>) -> Vec<ProfileSummary> {
-    let mut candidates: Vec<ProfileSummary> = profiles
+/// Order profiles for routing.
+///
+/// Disabled profiles and profiles that failed validation (including duplicates of
+/// another profile's account) are skipped. Profiles with rate-limit headroom come first, higher
+/// priority weights before lower ones. When `allow_credits` is set, profiles whose
+/// windows are exhausted but still have credits follow as a fallback. API-key profiles
+/// are billed per token, so they come last and only when `allow_api_keys` is set.
+fn select_candidates(
+    profiles: Vec<ProfileSummary>,
+    allow_credits: bool,
+    allow_api_keys: bool,
+) -> Vec<ProfileSummary> {
+    let (api_key_profiles, chatgpt_profiles): (Vec<ProfileSummary>, Vec<ProfileSummary>) = profiles
         .into_iter()
-        .filter(|p| {
+        .filter(|p| p.meta.enabled && p.health == ProfileHealth::Ok)
+        .partition(|p| p.auth_kind == AuthKind::ApiKey);
+    let (mut candidates, rest): (Vec<ProfileSummary>, Vec<ProfileSummary>) =
+        chatgpt_profiles.into_iter().partition(|p| {
             if let Some(quota) = &p.quota {
-                let used_tokens = quota.used_tokens.unwrap_or(0);
-                let total_tokens = quota.total_tokens.unwrap_or(100);
-
                 // Tier 1 constraint: Balance > 5% => Used <= 95%
-                let used_requests = quota.used_requests.unwrap_or(0);
-                let tier1_ok = used_requests <= 95;
+                let tier1_ok = quota.primary_used_percent().unwrap_or(0) <= 95;
 
                 // Tier 2 constraint: Not exhausted
-                let tier2_ok = used_tokens < total_tokens;
+                let tier2_ok = !quota
+                    .secondary
+                    .as_ref()
+                    .is_some_and(RateLimitWindow::is_exhausted);
 
                 tier1_ok && tier2_ok
             } else {
@@ -245,25 +1121,107 @@ fn select_candidates(profiles: Vec<ProfileSummary>) -> Vec<ProfileSummary> {
                 // Given "prioritize...", let's assume valid profiles have quota.
                 false
             }
-        })
-        .collect();
+        });
 
-    // Sort by Tier 2 (used_tokens) DESCENDING -> "Remaining Least"
+    // Sort by priority weight, then Tier 2 (secondary window usage) DESCENDING -> "Remaining Least"
     candidates.sort_by(|a, b| {
-        let used_a = a.quota.as_ref().and_then(|q| q.used_tokens).unwrap_or(0);
-        let used_b = b.quota.as_ref().and_then(|q| q.used_tokens).unwrap_or(0);
-        used_b.cmp(&used_a) // Descending
+        let used_a = secondary_used(a);
+        let used_b = secondary_used(b);
+        b.meta
+            .priority_weight
+            .cmp(&a.meta.priority_weight)
+            .then(used_b.cmp(&used_a)) // Descending
     });
 
+    if allow_credits {
+        let mut credit_candidates: Vec<ProfileSummary> =
+            rest.into_iter().filter(has_available_credits).collect();
+        // Unlimited accounts first, then the largest balance.
+        credit_candidates.sort_by(|a, b| credit_rank(b).total_cmp(&credit_rank(a)));
+        candidates.extend(credit_candidates);
+    }
+
+    if allow_api_keys {
+        // Without a quota the key was never accepted by the platform API
+        let mut api_key_candidates: Vec<ProfileSummary> = api_key_profiles
+            .into_iter()
+            .filter(|p| p.quota.is_some())
+            .collect();
+        api_key_candidates.sort_by_key(|p| std::cmp::Reverse(p.meta.priority_weight));
+        candidates.extend(api_key_candidates);
+    }
+
     candidates
 }
 
+/// Item ids refer to stored items on the platform API, and nothing is stored for
+/// proxied requests, so API-key profiles get the request without them
+fn without_item_ids(body: &serde_json::Value) -> serde_json::Value {
+    let mut body = body.clone();
+    if let Some(items) = body.get_mut("input").and_then(|input| input.as_array_mut()) {
+        for item in items {
+            if let Some(item) = item.as_object_mut() {
+                item.remove("id");
+            }
+        }
+    }
+    body
+}
+
+fn has_available_credits(profile: &ProfileSummary) -> bool {
+    profile
+        .quota
+        .as_ref()
+        .and_then(|q| q.credits.as_ref())
+        .is_some_and(CreditsInfo::is_available)
+}
+
+fn credit_rank(profile: &ProfileSummary) -> f64 {
+    let Some(credits) = profile.quota.as_ref().and_then(|q| q.credits.as_ref()) else {
+        return 0.0;
+    };
+    if credits.unlimited {
+        f64::INFINITY
+    } else {
+        credits.balance_value().unwrap_or(0.0)
+    }
+}
+
+fn secondary_used(profile: &ProfileSummary) -> u64 {
+    profile
+        .quota
+        .as_ref()
+        .and_then(|q| q.secondary_used_percent())
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::api::QuotaInfo;
+    use crate::test_support::{EnvGuard, ENV_LOCK};
+    use std::path::Path;
+
+    fn with_credits(mut profile: ProfileSummary, unlimited: bool, balance: &str) -> ProfileSummary {
+        if let Some(quota) = profile.quota.as_mut() {
+            quota.credits = Some(CreditsInfo {
+                has_credits: true,
+                unlimited,
+                balance: Some(balance.to_string()),
+            });
+        }
+        profile
+    }
 
-    fn mock_profile(name: &str, used_req: u64, used_tok: u64) -> ProfileSummary {
+    fn window(used_percent: u64) -> Option<RateLimitWindow> {
+        Some(RateLimitWindow {
+            used_percent: used_percent as f64,
+            window_seconds: None,
+            resets_at: None,
+        })
+    }
+
+    fn mock_profile(name: &str, primary_used: u64, secondary_used: u64) -> ProfileSummary {
         ProfileSummary {
             name: name.to_string(),
             email: None,
@@ -273,13 +1231,16 @@ mod tests {
                 account_id: "id".to_string(),
                 email: "email".to_string(),
                 plan_type: "plan".to_string(),
-                used_requests: Some(used_req),
-                total_requests: Some(100),
-                used_tokens: Some(used_tok),
-                total_tokens: Some(100), // Percent based
-                reset_date: None,
-                secondary_reset_date: None,
+                primary: window(primary_used),
+                secondary: window(secondary_used),
+                credits: None,
+                usage: None,
             }),
+            quota_fetched_at: None,
+            quota_stale: false,
+            meta: Default::default(),
+            health: Default::default(),
+            auth_kind: Default::default(),
         }
     }
 
@@ -289,7 +1250,7 @@ mod tests {
         let p1 = mock_profile("p1", 96, 10);
         let p2 = mock_profile("p2", 95, 10);
 
-        let candidates = select_candidates(vec![p1, p2]);
+        let candidates = select_candidates(vec![p1, p2], false, false);
         assert_eq!(candidates.len(), 1);
         assert_eq!(candidates[0].name, "p2");
     }
@@ -301,19 +1262,630 @@ mod tests {
         let p1 = mock_profile("p1", 50, 10);
         let p2 = mock_profile("p2", 50, 90);
 
-        let candidates = select_candidates(vec![p1, p2]);
+        let candidates = select_candidates(vec![p1, p2], false, false);
         assert_eq!(candidates.len(), 2);
         assert_eq!(candidates[0].name, "p2");
         assert_eq!(candidates[1].name, "p1");
     }
 
+    #[test]
+    fn test_select_candidates_honors_profile_meta() {
+        let mut disabled = mock_profile("disabled", 10, 95);
+        disabled.meta.enabled = false;
+        let mut preferred = mock_profile("preferred", 10, 10);
+        preferred.meta.priority_weight = 5;
+        let regular = mock_profile("regular", 10, 90);
+
+        let candidates = select_candidates(vec![disabled, regular, preferred], false, false);
+        let names: Vec<&str> = candidates.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["preferred", "regular"]);
+    }
+
     #[test]
     fn test_select_candidates_excludes_exhausted_tier2() {
         let p1 = mock_profile("p1", 50, 99);
         let p2 = mock_profile("p2", 50, 100); // Exhausted
 
-        let candidates = select_candidates(vec![p1, p2]);
+        let candidates = select_candidates(vec![p1, p2], false, false);
         assert_eq!(candidates.len(), 1);
         assert_eq!(candidates[0].name, "p1");
     }
+
+    #[test]
+    fn test_select_candidates_falls_back_to_credits() {
+        let p1 = mock_profile("p1", 50, 100);
+        let p2 = with_credits(mock_profile("p2", 99, 50), false, "5.00");
+        let p3 = with_credits(mock_profile("p3", 50, 100), false, "20.00");
+        let p4 = with_credits(mock_profile("p4", 50, 100), false, "0");
+        let p5 = mock_profile("p5", 10, 10);
+
+        let candidates = select_candidates(vec![p1, p2, p3, p4, p5], true, false);
+        let names: Vec<&str> = candidates.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["p5", "p3", "p2"]);
+    }
+
+    #[test]
+    fn test_select_candidates_prefers_unlimited_credits() {
+        let p1 = with_credits(mock_profile("p1", 50, 100), false, "100.00");
+        let p2 = with_credits(mock_profile("p2", 50, 100), true, "0");
+
+        let candidates = select_candidates(vec![p1, p2], true, false);
+        assert_eq!(candidates[0].name, "p2");
+        assert_eq!(candidates[1].name, "p1");
+    }
+
+    #[test]
+    fn test_select_candidates_skips_credits_when_forbidden() {
+        let p1 = with_credits(mock_profile("p1", 50, 100), false, "20.00");
+
+        let candidates = select_candidates(vec![p1], false, false);
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn fresh_state_does_not_route_to_credit_only_accounts() {
+        let state = SharedState::new();
+        assert!(!RouterState::default().allow_credit_spending);
+        let credits_only = with_credits(mock_profile("credits", 50, 100), false, "20.00");
+
+        let candidates = select_candidates(
+            vec![credits_only],
+            state.allow_credit_spending(),
+            state.allow_api_key_overflow(),
+        );
+        assert!(candidates.is_empty());
+    }
+
+    fn api_key_profile(name: &str) -> ProfileSummary {
+        let mut profile = mock_profile(name, 0, 0);
+        profile.auth_kind = AuthKind::ApiKey;
+        if let Some(quota) = profile.quota.as_mut() {
+            quota.plan_type = api::API_KEY_PLAN_TYPE.to_string();
+            quota.primary = None;
+            quota.secondary = None;
+        }
+        profile
+    }
+
+    #[test]
+    fn test_select_candidates_uses_api_keys_as_last_overflow() {
+        let exhausted = with_credits(mock_profile("exhausted", 50, 100), false, "5.00");
+        let regular = mock_profile("regular", 50, 10);
+        let mut unchecked = api_key_profile("unchecked");
+        unchecked.quota = None;
+        let paid = api_key_profile("paid");
+
+        let candidates = select_candidates(
+            vec![
+                paid.clone(),
+                unchecked.clone(),
+                exhausted.clone(),
+                regular.clone(),
+            ],
+            true,
+            true,
+        );
+        let names: Vec<&str> = candidates.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["regular", "exhausted", "paid"]);
+
+        let candidates = select_candidates(vec![paid, unchecked, exhausted, regular], true, false);
+        let names: Vec<&str> = candidates.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["regular", "exhausted"]);
+    }
+
+    #[test]
+    fn fresh_state_does_not_overflow_to_api_keys() {
+        let state = SharedState::new();
+        assert!(!RouterState::default().allow_api_key_overflow);
+
+        let candidates = select_candidates(
+            vec![api_key_profile("paid")],
+            state.allow_credit_spending(),
+            state.allow_api_key_overflow(),
+        );
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn router_status_reports_routing_order_and_problems() {
+        let mut broken = mock_profile("broken", 0, 0);
+        broken.health = ProfileHealth::MissingAuthFile;
+        let profiles = vec![
+            mock_profile("fresh", 10, 10),
+            mock_profile("busy", 50, 90),
+            mock_profile("exhausted", 100, 100),
+            broken,
+        ];
+
+        let status = RouterStatus::new(profiles, false, false);
+
+        assert_eq!(status.routing_order, vec!["busy", "fresh"]);
+        let routable: Vec<bool> = status.profiles.iter().map(|p| p.routable).collect();
+        assert_eq!(routable, vec![true, true, false, false]);
+        assert_eq!(
+            status.profiles[3].problem.as_deref(),
+            Some("auth.json is missing")
+        );
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(
+            json["profiles"][1]["quota"]["secondary"]["used_percent"],
+            90.0
+        );
+    }
+
+    #[test]
+    fn test_platform_body_drops_item_ids() {
+        let body = serde_json::json!({
+            "model": "gpt-5",
+            "input": [{"type": "message", "id": "msg_1", "role": "user", "content": []}],
+        });
+
+        let platform_body = without_item_ids(&body);
+
+        assert!(platform_body["input"][0].get("id").is_none());
+        assert_eq!(platform_body["input"][0]["role"], "user");
+        assert_eq!(platform_body["model"], "gpt-5");
+    }
+
+    #[test]
+    fn bind_override_prefers_args_over_env_and_settings() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let _host = EnvGuard::set("CODEX_ROUTER_HOST", Path::new("0.0.0.0"));
+        let _port = EnvGuard::set("CODEX_ROUTER_PORT", Path::new("9000"));
+
+        let args = ["--port=9100", "--verbose"].map(String::from);
+        let bind_override = BindOverride::from_env_and_args(args).unwrap();
+        assert_eq!(
+            bind_override,
+            BindOverride {
+                host: Some("0.0.0.0".to_string()),
+                port: Some(9100),
+            }
+        );
+
+        let config = BindConfig::resolve(&RouterState::default(), &bind_override);
+        assert_eq!(config.to_string(), "0.0.0.0:9100");
+        assert!(!config.is_loopback());
+        assert!(
+            BindConfig::resolve(&RouterState::default(), &BindOverride::default()).is_loopback()
+        );
+
+        assert!(BindOverride::from_env_and_args(["--port".to_string()]).is_err());
+        assert!(BindOverride::from_env_and_args(["--port=http".to_string()]).is_err());
+    }
+
+    async fn wait_for_status(handle: &ServerHandle) -> ServerStatus {
+        for _ in 0..100 {
+            let status = handle.status();
+            if status != ServerStatus::Starting {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        handle.status()
+    }
+
+    #[tokio::test]
+    async fn reports_bind_failures_and_restarts_on_the_same_port() {
+        let state = Arc::new(SharedState::new());
+        let config = BindConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            ..BindConfig::default()
+        };
+        let first = spawn_server(state.clone(), config);
+        let ServerStatus::Listening(addr) = wait_for_status(&first).await else {
+            panic!("server did not start");
+        };
+
+        let taken = BindConfig {
+            host: "127.0.0.1".to_string(),
+            port: addr.port(),
+            ..BindConfig::default()
+        };
+        let conflicting = spawn_server(state.clone(), taken.clone());
+        assert!(matches!(
+            wait_for_status(&conflicting).await,
+            ServerStatus::Failed(err) if err.contains(&taken.to_string())
+        ));
+
+        let restarted = first.restart(state, taken);
+        assert_eq!(
+            wait_for_status(&restarted).await,
+            ServerStatus::Listening(addr)
+        );
+    }
+
+    #[tokio::test]
+    async fn client_keys_gate_requests_once_issued() {
+        let state = Arc::new(SharedState::new());
+        let handle = spawn_server(
+            state.clone(),
+            BindConfig {
+                host: "127.0.0.1".to_string(),
+                port: 0,
+                ..BindConfig::default()
+            },
+        );
+        let ServerStatus::Listening(addr) = wait_for_status(&handle).await else {
+            panic!("server did not start");
+        };
+        let (key, record) =
+            client_keys::issue_key("ci", vec!["gpt-5".to_string()], vec![], chrono::Utc::now());
+        let key_id = record.id.clone();
+        state.set_client_keys(vec![record]);
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{addr}/v1/chat/completions");
+        let body = serde_json::json!({"model": "o3", "messages": []});
+
+        let missing = client.post(&url).json(&body).send().await.unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let error: serde_json::Value = missing.json().await.unwrap();
+        assert_eq!(error["error"]["code"], "invalid_api_key");
+
+        let wrong_model = client
+            .post(&url)
+            .bearer_auth(&key)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(wrong_model.status(), reqwest::StatusCode::FORBIDDEN);
+
+        let usage = state.client_key_usage();
+        assert_eq!(usage[&key_id].requests, 1);
+        assert_eq!(usage[&key_id].failed_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn logs_requests_with_redacted_prompts() {
+        let state = Arc::new(SharedState::new());
+        state.set_prompt_logging(crate::request_log::PromptLogging::Redacted);
+        let handle = spawn_server(
+            state.clone(),
+            BindConfig {
+                host: "127.0.0.1".to_string(),
+                port: 0,
+                ..BindConfig::default()
+            },
+        );
+        let ServerStatus::Listening(addr) = wait_for_status(&handle).await else {
+            panic!("server did not start");
+        };
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{addr}/v1/chat/completions"))
+            .json(&serde_json::json!({
+                "model": "gpt-5",
+                "messages": [{"role": "user", "content": "use sk-proj-abc123 please"}],
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        response.bytes().await.unwrap();
+
+        let mut entries = state.request_log.recent();
+        for _ in 0..50 {
+            if !entries.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            entries = state.request_log.recent();
+        }
+        let [entry] = entries.as_slice() else {
+            panic!("expected one entry, got {entries:?}");
+        };
+        assert_eq!(entry.client, "127.0.0.1");
+        assert_eq!(entry.model.as_deref(), Some("gpt-5"));
+        assert_eq!(entry.status, 503);
+        assert_eq!(entry.attempts, 0);
+        assert_eq!(entry.prompt.as_deref(), Some("user: use [REDACTED] please"));
+    }
+
+    #[tokio::test]
+    async fn exposes_prometheus_metrics() {
+        let state = Arc::new(SharedState::new());
+        let handle = spawn_server(
+            state.clone(),
+            BindConfig {
+                host: "127.0.0.1".to_string(),
+                port: 0,
+                ..BindConfig::default()
+            },
+        );
+        let ServerStatus::Listening(addr) = wait_for_status(&handle).await else {
+            panic!("server did not start");
+        };
+        let client = reqwest::Client::new();
+        client
+            .post(format!("http://{addr}/v1/chat/completions"))
+            .json(&serde_json::json!({"model": "gpt-5", "messages": []}))
+            .send()
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+
+        let expected = "codex_router_requests_total{model=\"gpt-5\",profile=\"\",status=\"503\"} 1";
+        let mut text = String::new();
+        for _ in 0..50 {
+            let response = client
+                .get(format!("http://{addr}/metrics"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::OK);
+            text = response.text().await.unwrap();
+            if text.contains(expected) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(text.contains(expected), "{text}");
+        assert!(text.contains("# TYPE codex_router_in_flight_streams gauge"));
+    }
+
+    #[tokio::test]
+    async fn health_is_open_while_status_needs_a_client_key() {
+        let state = Arc::new(SharedState::new());
+        let handle = spawn_server(
+            state.clone(),
+            BindConfig {
+                host: "127.0.0.1".to_string(),
+                port: 0,
+                ..BindConfig::default()
+            },
+        );
+        let ServerStatus::Listening(addr) = wait_for_status(&handle).await else {
+            panic!("server did not start");
+        };
+        state.update_profiles(vec![mock_profile("work", 10, 20)]);
+        let (key, record) = client_keys::issue_key("ci", vec![], vec![], chrono::Utc::now());
+        state.set_client_keys(vec![record]);
+        let client = reqwest::Client::new();
+
+        let health = client
+            .get(format!("http://{addr}/healthz"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(health.status(), reqwest::StatusCode::OK);
+
+        let url = format!("http://{addr}/v1/router/status");
+        let missing = client.get(&url).send().await.unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let status: serde_json::Value = client
+            .get(&url)
+            .bearer_auth(&key)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(status["routing_order"], serde_json::json!(["work"]));
+        assert_eq!(status["profiles"][0]["valid"], true);
+    }
+
+    #[tokio::test]
+    async fn rate_limits_clients_by_source_ip_with_retry_after() {
+        let state = Arc::new(SharedState::new());
+        state.set_ip_rate_limits(crate::rate_limit::RateLimits {
+            requests_per_minute: 1,
+            ..Default::default()
+        });
+        let handle = spawn_server(
+            state.clone(),
+            BindConfig {
+                host: "127.0.0.1".to_string(),
+                port: 0,
+                ..BindConfig::default()
+            },
+        );
+        let ServerStatus::Listening(addr) = wait_for_status(&handle).await else {
+            panic!("server did not start");
+        };
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{addr}/v1/chat/completions");
+        let body = serde_json::json!({"model": "gpt-5", "messages": []});
+
+        let first = client.post(&url).json(&body).send().await.unwrap();
+        assert_ne!(first.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+
+        let limited = client.post(&url).json(&body).send().await.unwrap();
+        assert_eq!(limited.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = limited.headers()[reqwest::header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=60).contains(&retry_after));
+        let error: serde_json::Value = limited.json().await.unwrap();
+        assert_eq!(error["error"]["code"], "rate_limit_exceeded");
+    }
+
+    #[tokio::test]
+    async fn cors_preflight_only_succeeds_for_allowed_origins() {
+        let state = Arc::new(SharedState::new());
+        state.set_cors_origins(vec!["https://app.example.com".to_string()]);
+        let handle = spawn_server(
+            state,
+            BindConfig {
+                host: "127.0.0.1".to_string(),
+                port: 0,
+                ..BindConfig::default()
+            },
+        );
+        let ServerStatus::Listening(addr) = wait_for_status(&handle).await else {
+            panic!("server did not start");
+        };
+
+        let client = reqwest::Client::new();
+        let preflight = |origin: &'static str| {
+            client
+                .request(
+                    reqwest::Method::OPTIONS,
+                    format!("http://{addr}/v1/chat/completions"),
+                )
+                .header("Origin", origin)
+                .header("Access-Control-Request-Method", "POST")
+                .header(
+                    "Access-Control-Request-Headers",
+                    "authorization, content-type",
+                )
+                .send()
+        };
+
+        let allowed = preflight("https://app.example.com").await.unwrap();
+        assert_eq!(
+            allowed.headers()["access-control-allow-origin"],
+            "https://app.example.com"
+        );
+        let blocked = preflight("https://evil.example").await.unwrap();
+        assert!(blocked
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none());
+
+        // The admin API deletes profiles
+        let delete = client
+            .request(
+                reqwest::Method::OPTIONS,
+                format!("http://{addr}/admin/profiles/old"),
+            )
+            .header("Origin", "https://app.example.com")
+            .header("Access-Control-Request-Method", "DELETE")
+            .send()
+            .await
+            .unwrap();
+        assert!(delete.status().is_success());
+        let methods = delete.headers()["access-control-allow-methods"]
+            .to_str()
+            .unwrap();
+        assert!(methods.contains("DELETE"));
+    }
+
+    #[tokio::test]
+    async fn serves_https_with_a_self_signed_certificate() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let settings = TlsSettings {
+            enabled: true,
+            cert_path: Some(temp_dir.path().join("cert.pem")),
+            key_path: Some(temp_dir.path().join("key.pem")),
+        };
+        tls::write_self_signed(
+            settings.cert_path.as_deref().unwrap(),
+            settings.key_path.as_deref().unwrap(),
+            "127.0.0.1",
+            chrono::Utc::now(),
+        )
+        .unwrap();
+
+        let handle = spawn_server(
+            Arc::new(SharedState::new()),
+            BindConfig {
+                host: "127.0.0.1".to_string(),
+                port: 0,
+                tls: settings.clone(),
+                ..BindConfig::default()
+            },
+        );
+        let ServerStatus::Listening(addr) = wait_for_status(&handle).await else {
+            panic!("server did not start: {:?}", handle.status());
+        };
+        assert!(handle.tls_fingerprint().is_some());
+
+        let cert_pem = std::fs::read(settings.cert_path.unwrap()).unwrap();
+        let client = reqwest::Client::builder()
+            .add_root_certificate(reqwest::Certificate::from_pem(&cert_pem).unwrap())
+            .build()
+            .unwrap();
+        let response = client
+            .post(format!(
+                "https://localhost:{}/v1/chat/completions",
+                addr.port()
+            ))
+            .json(&serde_json::json!({"model": "gpt-5", "messages": []}))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_client_error() || response.status().is_server_error());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn serves_on_an_owner_only_unix_socket_without_tcp() {
+        use std::os::unix::fs::PermissionsExt;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("api.sock");
+        let handle = spawn_server(
+            Arc::new(SharedState::new()),
+            BindConfig {
+                tcp_enabled: false,
+                unix_socket: Some(path.clone()),
+                ..BindConfig::default()
+            },
+        );
+        assert_eq!(
+            wait_for_status(&handle).await,
+            ServerStatus::ListeningOnSocket(path.clone())
+        );
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let body = r#"{"model":"gpt-5","messages":[]}"#;
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(
+                format!(
+                    "POST /v1/chat/completions HTTP/1.1\r\nHost: localhost\r\n\
+                     Content-Type: application/json\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 "), "{response}");
+
+        let restarted = handle.restart(
+            Arc::new(SharedState::new()),
+            BindConfig {
+                port: 0,
+                ..BindConfig::default()
+            },
+        );
+        assert!(matches!(
+            wait_for_status(&restarted).await,
+            ServerStatus::Listening(_)
+        ));
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_replaces_only_stale_sockets() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("api.sock");
+        // Dropping a listener leaves its socket file behind, as a crash would
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+        let socket = UnixSocket::bind(&path).unwrap();
+        let err = UnixSocket::bind(&path).err().unwrap();
+        assert!(err.to_string().contains("Failed to bind"), "{err}");
+        assert!(std::os::unix::net::UnixStream::connect(&path).is_ok());
+        drop(socket);
+
+        let entries = std::fs::read_dir(temp_dir.path()).unwrap().count();
+        assert_eq!(entries, 1);
+    }
 }
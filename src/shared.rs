@@ -1,15 +1,54 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::admin::AdminBridge;
+use crate::client_keys::{ClientKey, ClientKeyUsage};
+use crate::metrics::Metrics;
 use crate::profile::ProfileSummary;
+use crate::rate_limit::{RateLimiter, RateLimits};
+use crate::request_log::{PromptLogging, RequestLog};
+
+/// How long proxied request timestamps are kept for burn-rate forecasting
+const PROXIED_REQUEST_RETENTION_HOURS: i64 = 24;
 
 #[derive(Debug, Clone)]
 pub struct SharedState {
     pub profiles: Arc<RwLock<Vec<ProfileSummary>>>,
+    pub proxied_requests: Arc<RwLock<HashMap<String, Vec<DateTime<Utc>>>>>,
+    pub allow_credit_spending: Arc<AtomicBool>,
+    pub allow_api_key_overflow: Arc<AtomicBool>,
+    pub client_keys: Arc<RwLock<Vec<ClientKey>>>,
+    pub client_key_usage: Arc<RwLock<HashMap<String, ClientKeyUsage>>>,
+    /// Limits for requests made without a client key, per source IP
+    pub ip_rate_limits: Arc<RwLock<RateLimits>>,
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Normalized origins browsers may call the API from
+    pub cors_origins: Arc<RwLock<Vec<String>>>,
+    pub request_log: Arc<RequestLog>,
+    pub metrics: Arc<Metrics>,
+    pub prompt_logging: Arc<RwLock<PromptLogging>>,
+    pub admin: Arc<AdminBridge>,
 }
 
 impl SharedState {
     pub fn new() -> Self {
         Self {
             profiles: Arc::new(RwLock::new(Vec::new())),
+            proxied_requests: Arc::new(RwLock::new(HashMap::new())),
+            allow_credit_spending: Arc::new(AtomicBool::new(false)),
+            allow_api_key_overflow: Arc::new(AtomicBool::new(false)),
+            client_keys: Arc::new(RwLock::new(Vec::new())),
+            client_key_usage: Arc::new(RwLock::new(HashMap::new())),
+            ip_rate_limits: Arc::new(RwLock::new(RateLimits::default())),
+            rate_limiter: Arc::new(RateLimiter::default()),
+            cors_origins: Arc::new(RwLock::new(Vec::new())),
+            request_log: Arc::new(RequestLog::default()),
+            metrics: Arc::new(Metrics::default()),
+            prompt_logging: Arc::new(RwLock::new(PromptLogging::default())),
+            admin: Arc::new(AdminBridge::default()),
         }
     }
 
@@ -18,4 +57,119 @@ impl SharedState {
             *lock = profiles;
         }
     }
+
+    pub fn set_allow_credit_spending(&self, allowed: bool) {
+        self.allow_credit_spending.store(allowed, Ordering::Relaxed);
+    }
+
+    pub fn allow_credit_spending(&self) -> bool {
+        self.allow_credit_spending.load(Ordering::Relaxed)
+    }
+
+    pub fn set_allow_api_key_overflow(&self, allowed: bool) {
+        self.allow_api_key_overflow
+            .store(allowed, Ordering::Relaxed);
+    }
+
+    pub fn allow_api_key_overflow(&self) -> bool {
+        self.allow_api_key_overflow.load(Ordering::Relaxed)
+    }
+
+    pub fn set_client_keys(&self, keys: Vec<ClientKey>) {
+        if let Ok(mut lock) = self.client_keys.write() {
+            *lock = keys;
+        }
+    }
+
+    pub fn set_ip_rate_limits(&self, limits: RateLimits) {
+        if let Ok(mut lock) = self.ip_rate_limits.write() {
+            *lock = limits;
+        }
+    }
+
+    pub fn ip_rate_limits(&self) -> RateLimits {
+        self.ip_rate_limits
+            .read()
+            .map(|lock| *lock)
+            .unwrap_or_default()
+    }
+
+    pub fn set_cors_origins(&self, origins: Vec<String>) {
+        if let Ok(mut lock) = self.cors_origins.write() {
+            *lock = origins;
+        }
+    }
+
+    pub fn is_cors_origin_allowed(&self, origin: &str) -> bool {
+        self.cors_origins.read().is_ok_and(|lock| {
+            lock.iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+        })
+    }
+
+    pub fn set_prompt_logging(&self, logging: PromptLogging) {
+        if let Ok(mut lock) = self.prompt_logging.write() {
+            *lock = logging;
+        }
+    }
+
+    pub fn prompt_logging(&self) -> PromptLogging {
+        self.prompt_logging
+            .read()
+            .map(|lock| *lock)
+            .unwrap_or_default()
+    }
+
+    pub fn client_keys(&self) -> Vec<ClientKey> {
+        self.client_keys
+            .read()
+            .map(|lock| lock.clone())
+            .unwrap_or_default()
+    }
+
+    /// Record a request made with a client key
+    pub fn record_client_request(&self, key_id: &str, succeeded: bool) {
+        if let Ok(mut lock) = self.client_key_usage.write() {
+            let usage = lock.entry(key_id.to_string()).or_default();
+            usage.requests += 1;
+            if !succeeded {
+                usage.failed_requests += 1;
+            }
+            usage.last_used_at = Some(Utc::now());
+        }
+    }
+
+    pub fn client_key_usage(&self) -> HashMap<String, ClientKeyUsage> {
+        self.client_key_usage
+            .read()
+            .map(|lock| lock.clone())
+            .unwrap_or_default()
+    }
+
+    /// Record a request that was successfully served by a profile
+    pub fn record_proxied_request(&self, profile_name: &str) {
+        let now = Utc::now();
+        let cutoff = now - Duration::hours(PROXIED_REQUEST_RETENTION_HOURS);
+        if let Ok(mut lock) = self.proxied_requests.write() {
+            let requests = lock.entry(profile_name.to_string()).or_default();
+            requests.retain(|at| *at >= cutoff);
+            requests.push(now);
+        }
+    }
+
+    /// Keep proxied request counts attached to a renamed profile
+    pub fn rename_profile(&self, profile_name: &str, new_name: &str) {
+        if let Ok(mut lock) = self.proxied_requests.write() {
+            if let Some(requests) = lock.remove(profile_name) {
+                lock.insert(new_name.to_string(), requests);
+            }
+        }
+    }
+
+    pub fn proxied_requests(&self) -> HashMap<String, Vec<DateTime<Utc>>> {
+        self.proxied_requests
+            .read()
+            .map(|lock| lock.clone())
+            .unwrap_or_default()
+    }
 }
@@ -1,14 +1,77 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::path::PathBuf;
 
+use crate::atomic_file::write_atomic;
+use crate::client_keys::ClientKey;
 use crate::config::get_router_state_file;
+use crate::rate_limit::RateLimits;
+use crate::request_log::PromptLogging;
+use crate::server::{DEFAULT_HOST, DEFAULT_PORT};
+use crate::tls::TlsSettings;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RouterState {
     pub refresh_interval_seconds: u64,
     pub auto_refresh_enabled: bool,
     pub last_selected_profile: Option<String>,
+    /// Route to accounts with credits once their rate-limit windows are exhausted
+    #[serde(default = "default_allow_credit_spending")]
+    pub allow_credit_spending: bool,
+    /// Route to API-key profiles, billed per token, once no ChatGPT account can serve
+    #[serde(default = "default_allow_api_key_overflow")]
+    pub allow_api_key_overflow: bool,
+    /// Address the API server binds to; loopback keeps it off the network
+    #[serde(default = "default_server_host")]
+    pub server_host: String,
+    #[serde(default = "default_server_port")]
+    pub server_port: u16,
+    /// Keys clients must present once any has been issued; revoked keys are kept
+    #[serde(default)]
+    pub client_keys: Vec<ClientKey>,
+    /// Limits for requests made without a client key, per source IP
+    #[serde(default)]
+    pub ip_rate_limits: RateLimits,
+    /// Web origins allowed to call the API from a browser; empty blocks all
+    #[serde(default)]
+    pub cors_origins: Vec<String>,
+    #[serde(default)]
+    pub tls: TlsSettings,
+    /// Turning TCP off with the Unix socket on keeps the API to local processes
+    #[serde(default = "default_tcp_enabled")]
+    pub tcp_enabled: bool,
+    #[serde(default)]
+    pub unix_socket_enabled: bool,
+    /// Socket path; defaults to `api.sock` in the router config dir
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unix_socket_path: Option<PathBuf>,
+    /// Whether the request log keeps prompts
+    #[serde(default)]
+    pub prompt_logging: PromptLogging,
+    /// Serve the admin API, authenticated with the token in `admin.token`
+    #[serde(default)]
+    pub admin_api_enabled: bool,
+}
+
+fn default_allow_credit_spending() -> bool {
+    false
+}
+
+fn default_allow_api_key_overflow() -> bool {
+    false
+}
+
+fn default_tcp_enabled() -> bool {
+    true
+}
+
+fn default_server_host() -> String {
+    DEFAULT_HOST.to_string()
+}
+
+fn default_server_port() -> u16 {
+    DEFAULT_PORT
 }
 
 impl Default for RouterState {
@@ -17,6 +80,19 @@ impl Default for RouterState {
             refresh_interval_seconds: 600,
             auto_refresh_enabled: true,
             last_selected_profile: None,
+            allow_credit_spending: false,
+            allow_api_key_overflow: false,
+            server_host: default_server_host(),
+            server_port: default_server_port(),
+            client_keys: Vec::new(),
+            ip_rate_limits: RateLimits::default(),
+            cors_origins: Vec::new(),
+            tls: TlsSettings::default(),
+            tcp_enabled: true,
+            unix_socket_enabled: false,
+            unix_socket_path: None,
+            prompt_logging: PromptLogging::default(),
+            admin_api_enabled: false,
         }
     }
 }
@@ -41,7 +117,7 @@ pub fn save_state(state: &RouterState) -> Result<()> {
     }
 
     let contents = serde_json::to_string_pretty(state)?;
-    fs::write(&state_file, contents)?;
+    write_atomic(&state_file, contents.as_bytes())?;
     Ok(())
 }
 
@@ -62,6 +138,31 @@ mod tests {
         assert_eq!(state.refresh_interval_seconds, 600);
         assert!(state.auto_refresh_enabled);
         assert!(state.last_selected_profile.is_none());
+        assert!(!state.allow_credit_spending);
+        assert!(!state.allow_api_key_overflow);
+        assert_eq!(state.server_host, "127.0.0.1");
+        assert_eq!(state.server_port, 9876);
+    }
+
+    #[test]
+    fn loads_state_saved_before_credit_setting() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        let state_file = get_router_state_file().unwrap();
+        fs::create_dir_all(state_file.parent().unwrap()).unwrap();
+        fs::write(
+            &state_file,
+            r#"{"refresh_interval_seconds":300,"auto_refresh_enabled":true,"last_selected_profile":null}"#,
+        )
+        .unwrap();
+
+        let state = load_state().unwrap();
+
+        assert_eq!(state.refresh_interval_seconds, 300);
+        assert!(!state.allow_credit_spending);
+        assert!(state.tcp_enabled);
+        assert!(!state.unix_socket_enabled);
     }
 
     #[test]
@@ -74,6 +175,28 @@ mod tests {
             refresh_interval_seconds: 300,
             auto_refresh_enabled: false,
             last_selected_profile: Some("work".to_string()),
+            allow_credit_spending: false,
+            allow_api_key_overflow: false,
+            server_host: "0.0.0.0".to_string(),
+            server_port: 8080,
+            client_keys: vec![
+                crate::client_keys::issue_key("ci", vec![], vec![], chrono::Utc::now()).1,
+            ],
+            ip_rate_limits: RateLimits {
+                requests_per_minute: 30,
+                ..RateLimits::default()
+            },
+            cors_origins: vec!["http://localhost:3000".to_string()],
+            tls: TlsSettings {
+                enabled: true,
+                cert_path: Some("/etc/router/cert.pem".into()),
+                key_path: Some("/etc/router/key.pem".into()),
+            },
+            tcp_enabled: false,
+            unix_socket_enabled: true,
+            unix_socket_path: Some("/tmp/router.sock".into()),
+            prompt_logging: PromptLogging::Redacted,
+            admin_api_enabled: true,
         };
 
         save_state(&original).unwrap();
@@ -0,0 +1,314 @@
+//! HTTPS for the API server: a configured certificate/key pair, or a self-signed
+//! certificate generated on first use and kept in the router config dir.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, Utc};
+use rcgen::{CertificateParams, DnType, ExtendedKeyUsagePurpose, KeyPair};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+use crate::atomic_file::write_atomic;
+use crate::config::{get_tls_cert_file, get_tls_key_file};
+
+/// Clients that have not finished the handshake by then are dropped
+const HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const SELF_SIGNED_VALIDITY_DAYS: i64 = 3650;
+const SELF_SIGNED_COMMON_NAME: &str = "Codex Router";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// PEM certificate chain; with `key_path` unset too, a self-signed one is used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert_path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_path: Option<PathBuf>,
+}
+
+/// A certificate ready to serve, with its SHA-256 fingerprint for clients to pin
+#[derive(Clone)]
+pub struct TlsIdentity {
+    pub server_config: Arc<rustls::ServerConfig>,
+    pub fingerprint: String,
+}
+
+/// Load the configured certificate, or the self-signed one. The self-signed
+/// certificate is created if missing and replaced when it does not cover `host`.
+pub fn load_identity(settings: &TlsSettings, host: &str) -> Result<TlsIdentity> {
+    let (cert_path, key_path) = match (&settings.cert_path, &settings.key_path) {
+        (Some(cert), Some(key)) => (cert.clone(), key.clone()),
+        (None, None) => {
+            let (cert, key) = (get_tls_cert_file()?, get_tls_key_file()?);
+            if !cert.exists() || !key.exists() {
+                write_self_signed(&cert, &key, host, Utc::now())?;
+            } else if !read_certificates(&cert).is_ok_and(|certs| covers_host(&certs[0], host)) {
+                tracing::warn!(
+                    "Self-signed certificate does not cover {}; replacing it",
+                    host
+                );
+                write_self_signed(&cert, &key, host, Utc::now())?;
+            }
+            (cert, key)
+        }
+        _ => anyhow::bail!("Set both a certificate and a key file, or neither for self-signed"),
+    };
+
+    let certs = read_certificates(&cert_path)?;
+    let leaf = &certs[0];
+    let fingerprint = fingerprint(leaf);
+    if !covers_host(leaf, host) {
+        tracing::warn!("Certificate in {:?} is not valid for {}", cert_path, host);
+    }
+
+    let key_pem = fs::read(&key_path).with_context(|| format!("Failed to read {:?}", key_path))?;
+    let key = PrivateKeyDer::from_pem_slice(&key_pem)
+        .with_context(|| format!("No private key found in {:?}", key_path))?;
+
+    let mut server_config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_no_client_auth()
+    .with_single_cert(certs, key)
+    .context("Certificate and key do not match")?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(TlsIdentity {
+        server_config: Arc::new(server_config),
+        fingerprint,
+    })
+}
+
+/// SHA-256 of the certificate, as colon-separated hex like browsers show it
+pub fn fingerprint(cert: &[u8]) -> String {
+    Sha256::digest(cert)
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Accepts TCP connections and completes TLS handshakes in the background, so one
+/// slow client cannot hold up the others
+pub struct TlsListener {
+    local_addr: SocketAddr,
+    streams: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    accept_task: JoinHandle<()>,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, identity: &TlsIdentity) -> std::io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let acceptor = TlsAcceptor::from(identity.server_config.clone());
+        let (tx, streams) = mpsc::channel(32);
+        let accept_task = tokio::spawn(async move {
+            loop {
+                let (tcp, addr) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        tracing::warn!("Failed to accept connection: {}", err);
+                        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                        continue;
+                    }
+                };
+                let acceptor = acceptor.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(tcp)).await {
+                        Ok(Ok(stream)) => {
+                            let _ = tx.send((stream, addr)).await;
+                        }
+                        Ok(Err(err)) => {
+                            tracing::debug!("TLS handshake with {} failed: {}", addr, err)
+                        }
+                        Err(_) => tracing::debug!("TLS handshake with {} timed out", addr),
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            local_addr,
+            streams,
+            accept_task,
+        })
+    }
+}
+
+impl Drop for TlsListener {
+    fn drop(&mut self) {
+        // Releases the port, so a restart can bind it again
+        self.accept_task.abort();
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.streams.recv().await {
+            Some(accepted) => accepted,
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+pub(crate) fn write_self_signed(
+    cert_path: &Path,
+    key_path: &Path,
+    host: &str,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let (cert_pem, key_pem) = self_signed_certificate(host, now)?;
+    if let Some(parent) = cert_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    write_atomic(key_path, key_pem.as_bytes())?;
+    write_atomic(cert_path, cert_pem.as_bytes())?;
+    tracing::info!("Generated a self-signed certificate at {:?}", cert_path);
+    Ok(())
+}
+
+/// A P-256 certificate for `localhost`, the loopback addresses and `host`. Returns the
+/// certificate and its PKCS#8 key, both PEM.
+fn self_signed_certificate(host: &str, now: DateTime<Utc>) -> Result<(String, String)> {
+    let key_pair = KeyPair::generate_for(&rcgen::PKCS_ECDSA_P256_SHA256)
+        .context("Failed to generate a TLS key")?;
+    let mut params = CertificateParams::new(subject_alt_names(host))
+        .context("Invalid host name for the TLS certificate")?;
+    params
+        .distinguished_name
+        .push(DnType::CommonName, SELF_SIGNED_COMMON_NAME);
+    let date_time = |time: DateTime<Utc>| {
+        rcgen::date_time_ymd(time.year(), time.month() as u8, time.day() as u8)
+    };
+    params.not_before = date_time(now - Duration::days(1));
+    params.not_after = date_time(now + Duration::days(SELF_SIGNED_VALIDITY_DAYS));
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+    let certificate = params
+        .self_signed(&key_pair)
+        .context("Failed to sign the TLS certificate")?;
+    Ok((certificate.pem(), key_pair.serialize_pem()))
+}
+
+fn subject_alt_names(host: &str) -> Vec<String> {
+    let mut names = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    let host = match host.trim().parse::<IpAddr>() {
+        Ok(ip) if ip.is_unspecified() => return names,
+        Ok(ip) => ip.to_string(),
+        Err(_) => host.trim().to_ascii_lowercase(),
+    };
+    if !host.is_empty() && !names.contains(&host) {
+        names.push(host);
+    }
+    names
+}
+
+/// Whether clients connecting to `host` will accept `cert`. Hosts that clients never
+/// connect to by name, like the unspecified address, always count as covered.
+fn covers_host(cert: &CertificateDer<'_>, host: &str) -> bool {
+    let host = host.trim();
+    if host.is_empty() || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified()) {
+        return true;
+    }
+    let Ok(name) = ServerName::try_from(host) else {
+        return true;
+    };
+    webpki::EndEntityCert::try_from(cert)
+        .is_ok_and(|cert| cert.verify_is_valid_for_subject_name(&name).is_ok())
+}
+
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let pem = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let certs = CertificateDer::pem_slice_iter(&pem)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Invalid certificate in {:?}", path))?;
+    if certs.is_empty() {
+        anyhow::bail!("No certificate found in {:?}", path);
+    }
+    Ok(certs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{EnvGuard, ENV_LOCK};
+
+    #[test]
+    fn self_signed_certificate_is_created_once_and_reused() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        let settings = TlsSettings {
+            enabled: true,
+            ..TlsSettings::default()
+        };
+
+        let first = load_identity(&settings, "192.168.1.20").unwrap();
+        let second = load_identity(&settings, "192.168.1.20").unwrap();
+        assert_eq!(first.fingerprint, second.fingerprint);
+        assert_eq!(first.fingerprint.split(':').count(), 32);
+
+        let cert_pem = fs::read(get_tls_cert_file().unwrap()).unwrap();
+        let cert = CertificateDer::from_pem_slice(&cert_pem).unwrap();
+        assert_eq!(fingerprint(&cert), first.fingerprint);
+
+        let only_cert = TlsSettings {
+            enabled: true,
+            cert_path: Some(get_tls_cert_file().unwrap()),
+            key_path: None,
+        };
+        assert!(load_identity(&only_cert, "localhost").is_err());
+    }
+
+    #[test]
+    fn self_signed_certificate_is_replaced_for_a_new_host() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        let settings = TlsSettings {
+            enabled: true,
+            ..TlsSettings::default()
+        };
+
+        let loopback = load_identity(&settings, "127.0.0.1").unwrap();
+        // Covered by every self-signed certificate
+        for host in ["localhost", "::1", "0.0.0.0"] {
+            let same = load_identity(&settings, host).unwrap();
+            assert_eq!(same.fingerprint, loopback.fingerprint);
+        }
+
+        let lan = load_identity(&settings, "router.lan").unwrap();
+        assert_ne!(lan.fingerprint, loopback.fingerprint);
+        let cert_pem = fs::read(get_tls_cert_file().unwrap()).unwrap();
+        let cert = CertificateDer::from_pem_slice(&cert_pem).unwrap();
+        assert!(covers_host(&cert, "ROUTER.lan"));
+        assert!(covers_host(&cert, "localhost"));
+        assert!(!covers_host(&cert, "192.168.1.20"));
+        assert_eq!(
+            load_identity(&settings, "router.lan").unwrap().fingerprint,
+            lan.fingerprint
+        );
+    }
+}
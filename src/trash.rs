@@ -0,0 +1,187 @@
+//! Deleted profiles are moved here instead of being removed, so they can be restored
+//! until the retention period runs out.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::atomic_file::write_atomic;
+use crate::config::{get_profiles_dir, get_trash_dir};
+
+/// Trashed profiles older than this are purged permanently
+pub const TRASH_RETENTION_DAYS: i64 = 30;
+
+/// Written into each trashed profile directory, removed again on restore
+const TRASH_MARKER_FILE: &str = ".trash.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    /// Directory name inside the trash
+    pub id: String,
+    pub profile_name: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl TrashEntry {
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.deleted_at + Duration::days(TRASH_RETENTION_DAYS)
+    }
+}
+
+/// Move a profile directory into the trash
+pub fn trash_profile(profile_name: &str, now: DateTime<Utc>) -> Result<TrashEntry> {
+    let profile_dir = get_profiles_dir()?.join(profile_name);
+    if !profile_dir.exists() {
+        anyhow::bail!("Profile '{}' not found.", profile_name);
+    }
+
+    let trash_dir = get_trash_dir()?;
+    fs::create_dir_all(&trash_dir)?;
+    let mut id = format!("{}-{}", now.timestamp_millis(), profile_name);
+    let mut attempt = 1;
+    while trash_dir.join(&id).exists() {
+        attempt += 1;
+        id = format!("{}-{}-{attempt}", now.timestamp_millis(), profile_name);
+    }
+
+    let entry = TrashEntry {
+        id,
+        profile_name: profile_name.to_string(),
+        deleted_at: now,
+    };
+    let entry_dir = trash_dir.join(&entry.id);
+    fs::rename(&profile_dir, &entry_dir)
+        .with_context(|| format!("Failed to move profile '{}' to the trash", profile_name))?;
+    let marker = serde_json::to_string_pretty(&entry)?;
+    if let Err(err) = write_atomic(&entry_dir.join(TRASH_MARKER_FILE), marker.as_bytes()) {
+        let _ = fs::rename(&entry_dir, &profile_dir);
+        return Err(err);
+    }
+    Ok(entry)
+}
+
+/// Trashed profiles, most recently deleted first
+pub fn list_trash() -> Result<Vec<TrashEntry>> {
+    let trash_dir = get_trash_dir()?;
+    if !trash_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for dir_entry in fs::read_dir(&trash_dir)? {
+        let path = dir_entry?.path();
+        if let Some(entry) = read_marker(&path) {
+            entries.push(entry);
+        }
+    }
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.deleted_at));
+    Ok(entries)
+}
+
+fn read_marker(entry_dir: &Path) -> Option<TrashEntry> {
+    let contents = fs::read_to_string(entry_dir.join(TRASH_MARKER_FILE)).ok()?;
+    let mut entry: TrashEntry = serde_json::from_str(&contents).ok()?;
+    entry.id = entry_dir.file_name()?.to_string_lossy().to_string();
+    Some(entry)
+}
+
+fn find_entry(id: &str) -> Result<TrashEntry> {
+    let entry_dir = get_trash_dir()?.join(id);
+    read_marker(&entry_dir).with_context(|| format!("Trash entry '{}' not found.", id))
+}
+
+/// Move a trashed profile back. Returns the restored profile name, which gets a
+/// numeric suffix if a profile with the original name was created in the meantime.
+pub fn restore_profile(id: &str) -> Result<String> {
+    let entry = find_entry(id)?;
+    let profiles_dir = get_profiles_dir()?;
+    fs::create_dir_all(&profiles_dir)?;
+
+    let mut name = entry.profile_name.clone();
+    let mut attempt = 1;
+    while profiles_dir.join(&name).exists() {
+        attempt += 1;
+        name = format!("{}-{attempt}", entry.profile_name);
+    }
+
+    // Move first: if that fails the entry must keep its marker to stay in the trash
+    let entry_dir = get_trash_dir()?.join(&entry.id);
+    let restored_dir = profiles_dir.join(&name);
+    fs::rename(&entry_dir, &restored_dir)
+        .with_context(|| format!("Failed to restore profile '{}'", entry.profile_name))?;
+    if let Err(err) = fs::remove_file(restored_dir.join(TRASH_MARKER_FILE)) {
+        tracing::warn!(profile = %name, error = %err, "Failed to remove trash marker");
+    }
+    Ok(name)
+}
+
+/// Permanently delete a trashed profile
+pub fn purge_entry(id: &str) -> Result<()> {
+    let entry = find_entry(id)?;
+    fs::remove_dir_all(get_trash_dir()?.join(&entry.id))?;
+    Ok(())
+}
+
+/// Permanently delete trashed profiles past the retention period. Returns how many were removed.
+pub fn purge_expired(now: DateTime<Utc>) -> Result<usize> {
+    let mut purged = 0;
+    for entry in list_trash()? {
+        if entry.expires_at() <= now {
+            purge_entry(&entry.id)?;
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{EnvGuard, ENV_LOCK};
+
+    fn write_profile(root: &Path, name: &str) {
+        let dir = root.join("profiles").join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("auth.json"), "{}").unwrap();
+    }
+
+    #[test]
+    fn trashes_and_restores_profiles() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        write_profile(temp_dir.path(), "work");
+
+        let entry = trash_profile("work", Utc::now()).unwrap();
+        assert!(!temp_dir.path().join("profiles/work").exists());
+        assert_eq!(list_trash().unwrap(), vec![entry.clone()]);
+
+        // A new profile took the name while the old one was in the trash
+        write_profile(temp_dir.path(), "work");
+        let restored = restore_profile(&entry.id).unwrap();
+
+        assert_eq!(restored, "work-2");
+        let restored_dir = temp_dir.path().join("profiles/work-2");
+        assert!(restored_dir.join("auth.json").exists());
+        assert!(!restored_dir.join(TRASH_MARKER_FILE).exists());
+        assert!(list_trash().unwrap().is_empty());
+    }
+
+    #[test]
+    fn purges_entries_past_retention() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        write_profile(temp_dir.path(), "old");
+        write_profile(temp_dir.path(), "recent");
+
+        let now = Utc::now();
+        trash_profile("old", now - Duration::days(TRASH_RETENTION_DAYS + 1)).unwrap();
+        let recent = trash_profile("recent", now - Duration::days(1)).unwrap();
+
+        assert_eq!(purge_expired(now).unwrap(), 1);
+        assert_eq!(list_trash().unwrap(), vec![recent]);
+    }
+}
@@ -1,8 +1,12 @@
+use std::collections::HashMap;
 use std::sync::mpsc::Sender;
 
+use chrono::Local;
+
 use tray_icon::menu::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem};
 use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
 
+use crate::forecast::ProfileForecast;
 use crate::profile::ProfileSummary;
 
 #[derive(Debug, Clone)]
@@ -27,8 +31,12 @@ pub struct TrayHandle {
 }
 
 impl TrayHandle {
-    pub fn update_profiles(&self, profiles: &[ProfileSummary]) {
-        let menu = build_menu(profiles);
+    pub fn update_profiles(
+        &self,
+        profiles: &[ProfileSummary],
+        forecasts: &HashMap<String, ProfileForecast>,
+    ) {
+        let menu = build_menu(profiles, forecasts);
         self.tray_icon.set_menu(Some(Box::new(menu)));
     }
 }
@@ -41,21 +49,34 @@ fn menu_id_for_profile(name: &str) -> String {
     format!("{PROFILE_PREFIX}{name}")
 }
 
-fn menu_label_for_profile(profile: &ProfileSummary) -> String {
+fn menu_label_for_profile(profile: &ProfileSummary, forecast: Option<&ProfileForecast>) -> String {
+    let name = profile.meta.label(&profile.name);
     let base = if profile.is_current {
-        format!("* {}", profile.name)
+        format!("* {}", name)
     } else {
-        profile.name.clone()
+        name.to_string()
     };
 
-    if let Some(email) = &profile.email {
+    let label = if let Some(email) = &profile.email {
         format!("{} ({})", base, email)
     } else {
         base
+    };
+
+    match forecast.and_then(ProfileForecast::exhausts_at) {
+        Some(at) => format!(
+            "{} - runs out {}",
+            label,
+            at.with_timezone(&Local).format("%a %H:%M")
+        ),
+        None => label,
     }
 }
 
-fn menu_entries(profiles: &[ProfileSummary]) -> Vec<MenuEntry> {
+fn menu_entries(
+    profiles: &[ProfileSummary],
+    forecasts: &HashMap<String, ProfileForecast>,
+) -> Vec<MenuEntry> {
     let mut entries = Vec::new();
     entries.push(MenuEntry::Item {
         id: OPEN_ID.to_string(),
@@ -73,7 +94,7 @@ fn menu_entries(profiles: &[ProfileSummary]) -> Vec<MenuEntry> {
         for profile in sorted {
             entries.push(MenuEntry::Item {
                 id: menu_id_for_profile(&profile.name),
-                label: menu_label_for_profile(profile),
+                label: menu_label_for_profile(profile, forecasts.get(&profile.name)),
             });
         }
     }
@@ -102,9 +123,9 @@ fn tray_event_from_menu_id(id: &MenuId) -> Option<TrayEvent> {
     None
 }
 
-fn build_menu(profiles: &[ProfileSummary]) -> Menu {
+fn build_menu(profiles: &[ProfileSummary], forecasts: &HashMap<String, ProfileForecast>) -> Menu {
     let menu = Menu::new();
-    for entry in menu_entries(profiles) {
+    for entry in menu_entries(profiles, forecasts) {
         match entry {
             MenuEntry::Item { id, label } => {
                 let item = MenuItem::with_id(id, label, true, None);
@@ -120,7 +141,7 @@ fn build_menu(profiles: &[ProfileSummary]) -> Menu {
 }
 
 pub fn start_tray(sender: Sender<TrayEvent>) -> TrayHandle {
-    let menu = build_menu(&[]);
+    let menu = build_menu(&[], &HashMap::new());
     let tray_icon = TrayIconBuilder::new()
         .with_menu(Box::new(menu))
         .with_tooltip("Codex Router")
@@ -179,6 +200,11 @@ mod tests {
                 is_current: false,
                 is_valid: true,
                 quota: None,
+                quota_fetched_at: None,
+                quota_stale: false,
+                meta: Default::default(),
+                health: Default::default(),
+                auth_kind: Default::default(),
             },
             ProfileSummary {
                 name: "alpha".to_string(),
@@ -186,10 +212,15 @@ mod tests {
                 is_current: true,
                 is_valid: true,
                 quota: None,
+                quota_fetched_at: None,
+                quota_stale: false,
+                meta: Default::default(),
+                health: Default::default(),
+                auth_kind: Default::default(),
             },
         ];
 
-        let entries = menu_entries(&profiles);
+        let entries = menu_entries(&profiles, &HashMap::new());
         let mut ids = Vec::new();
         let mut labels = Vec::new();
 
@@ -207,4 +238,31 @@ mod tests {
         assert!(labels.iter().any(|label| label.starts_with("* alpha")));
         assert!(labels.iter().any(|label| label.contains("beta")));
     }
+
+    #[test]
+    fn menu_label_includes_projected_exhaustion() {
+        use crate::forecast::WindowForecast;
+
+        let profile = ProfileSummary {
+            name: "alpha".to_string(),
+            email: None,
+            is_current: false,
+            is_valid: true,
+            quota: None,
+            quota_fetched_at: None,
+            quota_stale: false,
+            meta: Default::default(),
+            health: Default::default(),
+            auth_kind: Default::default(),
+        };
+        let forecast = ProfileForecast {
+            primary: WindowForecast::ExhaustsAt(chrono::Utc::now()),
+            secondary: WindowForecast::SurvivesUntilReset,
+            primary_rate: Some(10.0),
+            secondary_rate: Some(1.0),
+        };
+
+        assert_eq!(menu_label_for_profile(&profile, None), "alpha");
+        assert!(menu_label_for_profile(&profile, Some(&forecast)).starts_with("alpha - runs out "));
+    }
 }
@@ -5,103 +5,215 @@ use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Arc;
 use std::thread::JoinHandle;
 
-use crate::app_state::{AppCommand, AppEvent};
+use crate::app_state::{AppCommand, AppEvent, OfficialAuthChange};
 use crate::config;
 use crate::login_output;
-use crate::{api, auth, oauth, profile};
+use crate::quota_cache::{self, QuotaCache};
+use crate::{
+    api, atomic_file, auth, bundle, history, oauth, profile, profile_meta, secret_store, trash,
+};
+
+/// Result of fetching a profile's quota, including one token refresh attempt
+enum QuotaFetch {
+    Loaded(Box<api::QuotaInfo>),
+    /// The access token expired and could not be refreshed
+    LoginExpired,
+    Failed(anyhow::Error),
+}
+
+fn fetch_quota_with_refresh(
+    runtime: &tokio::runtime::Runtime,
+    evt_tx: &Sender<AppEvent>,
+    name: &str,
+    mut auth: auth::AuthDotJson,
+) -> QuotaFetch {
+    let err = match runtime.block_on(api::fetch_quota(&auth)) {
+        Ok(quota) => return QuotaFetch::Loaded(Box::new(quota)),
+        Err(err) => err,
+    };
+    if !matches!(
+        err.downcast_ref::<api::AuthError>(),
+        Some(api::AuthError::Expired)
+    ) {
+        return QuotaFetch::Failed(err);
+    }
+    let Some(refresh_token) = auth.tokens.as_ref().map(|t| t.refresh_token.clone()) else {
+        return QuotaFetch::LoginExpired;
+    };
+
+    tracing::info!(profile = %name, "Access token expired, attempting refresh");
+    let refresh_result = runtime.block_on(api::refresh_token(&refresh_token));
+    let _ = evt_tx.send(AppEvent::TokenRefreshed {
+        name: name.to_string(),
+        success: refresh_result.is_ok(),
+    });
+    let refresh_response = match refresh_result {
+        Ok(refresh_response) => refresh_response,
+        Err(refresh_err) => {
+            tracing::warn!(profile = %name, error = %refresh_err, "Token refresh failed");
+            return QuotaFetch::LoginExpired;
+        }
+    };
+
+    // Update auth with new tokens
+    if let Some(ref mut tokens) = auth.tokens {
+        if let Some(new_access) = refresh_response.access_token {
+            tokens.access_token = new_access;
+        }
+        if let Some(new_refresh) = refresh_response.refresh_token {
+            tokens.refresh_token = new_refresh;
+        }
+        // Note: id_token update requires parsing, skip for now
+    }
+    auth.last_refresh = Some(chrono::Utc::now());
+
+    // Save updated auth to profile
+    if let Err(save_err) = profile::save_profile_auth(name, &auth) {
+        tracing::warn!(profile = %name, error = %save_err, "Failed to save refreshed tokens");
+    }
+
+    // Retry quota fetch with new tokens
+    match runtime.block_on(api::fetch_quota(&auth)) {
+        Ok(quota) => {
+            tracing::info!(profile = %name, "Token refresh successful");
+            QuotaFetch::Loaded(Box::new(quota))
+        }
+        Err(retry_err) => {
+            tracing::warn!(
+                profile = %name,
+                error = %retry_err,
+                "Quota fetch failed after token refresh"
+            );
+            QuotaFetch::LoginExpired
+        }
+    }
+}
 
 fn load_profiles_with_quota(
     runtime: &tokio::runtime::Runtime,
+    evt_tx: &Sender<AppEvent>,
+    quota_cache: &mut QuotaCache,
 ) -> anyhow::Result<Vec<profile::ProfileSummary>> {
     let mut profiles = profile::list_profiles_data()?;
+    let mut samples = Vec::new();
     for profile_summary in &mut profiles {
-        let mut auth = match profile::load_profile_auth(&profile_summary.name) {
+        let now = chrono::Utc::now();
+        if !quota_cache.should_fetch(&profile_summary.name, now) {
+            quota_cache.apply_to(profile_summary, now);
+            continue;
+        }
+        let auth = match profile::load_profile_auth(&profile_summary.name) {
             Ok(auth) => auth,
             Err(_) => continue,
         };
-        match runtime.block_on(api::fetch_quota(&auth)) {
-            Ok(quota) => {
-                profile_summary.quota = Some(quota);
+        match fetch_quota_with_refresh(runtime, evt_tx, &profile_summary.name, auth) {
+            QuotaFetch::Loaded(quota) => {
+                samples.push((profile_summary.name.clone(), (*quota).clone()));
+                quota_cache.record_success(&profile_summary.name, *quota, now);
+                quota_cache.apply_to(profile_summary, now);
             }
-            Err(err) => {
-                if let Some(api::AuthError::Expired) = err.downcast_ref::<api::AuthError>() {
-                    // Attempt to refresh the token
-                    if let Some(ref tokens) = auth.tokens {
-                        tracing::info!(
-                            profile = %profile_summary.name,
-                            "Access token expired, attempting refresh"
-                        );
-                        match runtime.block_on(api::refresh_token(&tokens.refresh_token)) {
-                            Ok(refresh_response) => {
-                                // Update auth with new tokens
-                                if let Some(ref mut tokens) = auth.tokens {
-                                    if let Some(new_access) = refresh_response.access_token {
-                                        tokens.access_token = new_access;
-                                    }
-                                    if let Some(new_refresh) = refresh_response.refresh_token {
-                                        tokens.refresh_token = new_refresh;
-                                    }
-                                    // Note: id_token update requires parsing, skip for now
-                                }
-                                auth.last_refresh = Some(chrono::Utc::now());
-
-                                // Save updated auth to profile
-                                if let Err(save_err) =
-                                    profile::save_profile_auth(&profile_summary.name, &auth)
-                                {
-                                    tracing::warn!(
-                                        profile = %profile_summary.name,
-                                        error = %save_err,
-                                        "Failed to save refreshed tokens"
-                                    );
-                                }
-
-                                // Retry quota fetch with new tokens
-                                match runtime.block_on(api::fetch_quota(&auth)) {
-                                    Ok(quota) => {
-                                        profile_summary.quota = Some(quota);
-                                        tracing::info!(
-                                            profile = %profile_summary.name,
-                                            "Token refresh successful"
-                                        );
-                                    }
-                                    Err(retry_err) => {
-                                        tracing::warn!(
-                                            profile = %profile_summary.name,
-                                            error = %retry_err,
-                                            "Quota fetch failed after token refresh"
-                                        );
-                                        profile_summary.is_valid = false;
-                                    }
-                                }
-                            }
-                            Err(refresh_err) => {
-                                tracing::warn!(
-                                    profile = %profile_summary.name,
-                                    error = %refresh_err,
-                                    "Token refresh failed"
-                                );
-                                profile_summary.is_valid = false;
-                            }
-                        }
-                    } else {
-                        profile_summary.is_valid = false;
-                    }
-                }
+            QuotaFetch::LoginExpired => {
+                profile_summary.is_valid = false;
+            }
+            QuotaFetch::Failed(err) => {
+                tracing::warn!(
+                    profile = %profile_summary.name,
+                    error = %err,
+                    "Quota fetch failed, keeping last known quota"
+                );
+                quota_cache.record_failure(&profile_summary.name, &err.to_string(), now);
+                quota_cache.apply_to(profile_summary, now);
             }
         }
     }
+    if let Err(err) = history::record_quotas(&samples) {
+        tracing::warn!(error = %err, "Failed to record quota history");
+    }
     Ok(profiles)
 }
 
+fn list_profiles_with_cached_quota(
+    quota_cache: &QuotaCache,
+) -> anyhow::Result<Vec<profile::ProfileSummary>> {
+    let mut profiles = profile::list_profiles_data()?;
+    let now = chrono::Utc::now();
+    for profile_summary in &mut profiles {
+        quota_cache.apply_to(profile_summary, now);
+    }
+    Ok(profiles)
+}
+
+fn send_vault_status(evt_tx: &Sender<AppEvent>, message: Option<String>) {
+    match secret_store::status() {
+        Ok(status) => {
+            let _ = evt_tx.send(AppEvent::VaultUpdated { status, message });
+        }
+        Err(err) => {
+            let _ = evt_tx.send(AppEvent::Error(err.to_string()));
+        }
+    }
+}
+
+fn send_profiles(evt_tx: &Sender<AppEvent>, quota_cache: &QuotaCache) {
+    match list_profiles_with_cached_quota(quota_cache) {
+        Ok(profiles) => {
+            let _ = evt_tx.send(AppEvent::ProfilesLoaded(profiles));
+        }
+        Err(err) => {
+            let _ = evt_tx.send(AppEvent::Error(err.to_string()));
+        }
+    }
+}
+
+/// Sends `CommandFinished` when a tracked command is done, however its handler exits
+struct CommandFinished {
+    id: u64,
+    evt_tx: Sender<AppEvent>,
+}
+
+impl Drop for CommandFinished {
+    fn drop(&mut self) {
+        let _ = self.evt_tx.send(AppEvent::CommandFinished(self.id));
+    }
+}
+
+fn save_quota_cache(quota_cache: &QuotaCache) {
+    if let Err(err) = quota_cache::save_cache(quota_cache) {
+        tracing::warn!(error = %err, "Failed to save quota cache");
+    }
+}
+
+fn record_quota_history(name: &str, quota: &api::QuotaInfo) {
+    if let Err(err) = history::record_quota(name, quota) {
+        tracing::warn!(profile = %name, error = %err, "Failed to record quota history");
+    }
+}
+
+fn send_quota_history(evt_tx: &Sender<AppEvent>) {
+    match history::load_history() {
+        Ok(history) => {
+            let _ = evt_tx.send(AppEvent::QuotaHistoryLoaded(history));
+        }
+        Err(err) => {
+            tracing::warn!(error = %err, "Failed to load quota history");
+        }
+    }
+}
+
+fn send_trash(evt_tx: &Sender<AppEvent>) {
+    match trash::list_trash() {
+        Ok(entries) => {
+            let _ = evt_tx.send(AppEvent::TrashLoaded(entries));
+        }
+        Err(err) => {
+            tracing::warn!(error = %err, "Failed to list trashed profiles");
+        }
+    }
+}
+
 fn finalize_login(new_auth: auth::AuthDotJson) -> anyhow::Result<()> {
     let outcome = profile::save_auth_as_profile_without_switch(&new_auth)?;
-
-    let name = match &outcome {
-        profile::SaveProfileOutcome::Created { name }
-        | profile::SaveProfileOutcome::Updated { name }
-        | profile::SaveProfileOutcome::AlreadyExists { name } => name.clone(),
-    };
+    let name = outcome.name().to_string();
 
     // Set current profile in our isolated config if not already set
     let current_profile_file = config::get_current_profile_file()?;
@@ -113,29 +225,93 @@ fn finalize_login(new_auth: auth::AuthDotJson) -> anyhow::Result<()> {
         if let Some(parent) = current_profile_file.parent() {
             fs::create_dir_all(parent)?;
         }
-        fs::write(&current_profile_file, &name)?;
+        atomic_file::write_atomic(&current_profile_file, name.as_bytes())?;
     }
 
     // Save auth to our isolated config directory
-    let auth_file = config::get_auth_file()?;
-    if let Some(parent) = auth_file.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    fs::write(auth_file, serde_json::to_string_pretty(&new_auth)?)?;
+    auth::save_auth(&new_auth)?;
 
     Ok(())
 }
 
+/// Reconcile with the official Codex auth.json. When the CLI uses a saved account the
+/// current profile follows it; otherwise the credentials are returned so the user can
+/// import them.
+fn check_official_auth(
+    runtime: &tokio::runtime::Runtime,
+) -> anyhow::Result<Option<OfficialAuthChange>> {
+    let Some(official) = auth::load_official_auth()? else {
+        return Ok(None);
+    };
+
+    let status = profile::match_auth_to_profile(&official)?;
+    let name = match &status {
+        profile::AuthMatch::Unchanged { name } => name,
+        profile::AuthMatch::Refreshed { name } => {
+            // Newer tokens for a known account need no confirmation
+            tracing::info!(profile = %name, "Codex CLI refreshed its tokens, updating the profile");
+            profile::save_profile_auth(name, &official)?;
+            name
+        }
+        profile::AuthMatch::New { .. } => {
+            return Ok(Some(OfficialAuthChange {
+                email: auth::get_email(&official),
+                status,
+            }))
+        }
+    };
+    if profile::get_current_profile()?.as_deref() != Some(name.as_str()) {
+        tracing::info!(profile = %name, "Codex CLI switched accounts, following it");
+        runtime.block_on(profile::switch_profile(name))?;
+    }
+    Ok(None)
+}
+
+/// Save the official Codex credentials as a profile and make it the current one.
+fn import_official_auth(
+    runtime: &tokio::runtime::Runtime,
+) -> anyhow::Result<profile::SaveProfileOutcome> {
+    let official =
+        auth::load_official_auth()?.ok_or_else(|| anyhow::anyhow!("Codex CLI is not logged in"))?;
+    let outcome = profile::save_auth_as_profile_without_switch(&official)?;
+    runtime.block_on(profile::switch_profile(outcome.name()))?;
+    Ok(outcome)
+}
+
 pub fn start_worker(cmd_rx: Receiver<AppCommand>, evt_tx: Sender<AppEvent>) -> JoinHandle<()> {
     std::thread::spawn(move || {
         let runtime = tokio::runtime::Runtime::new().expect("failed to create runtime");
         let login_cancel_flag: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let mut quota_cache = quota_cache::load_cache().unwrap_or_else(|err| {
+            tracing::warn!(error = %err, "Failed to load quota cache");
+            QuotaCache::default()
+        });
+        if let Err(err) = secret_store::unlock_from_env() {
+            tracing::warn!(error = %err, "Failed to unlock credential vault from environment");
+        }
 
         while let Ok(command) = cmd_rx.recv() {
+            let (command, _finished) = match command {
+                AppCommand::Tracked { id, command } => {
+                    let _ = evt_tx.send(AppEvent::CommandStarted(id));
+                    let finished = CommandFinished {
+                        id,
+                        evt_tx: evt_tx.clone(),
+                    };
+                    (*command, Some(finished))
+                }
+                command => (command, None),
+            };
             match command {
-                AppCommand::LoadProfiles => match profile::list_profiles_data() {
+                AppCommand::LoadProfiles => match list_profiles_with_cached_quota(&quota_cache) {
                     Ok(profiles) => {
                         let _ = evt_tx.send(AppEvent::ProfilesLoaded(profiles));
+                        send_quota_history(&evt_tx);
+                        send_vault_status(&evt_tx, None);
+                        if let Err(err) = trash::purge_expired(chrono::Utc::now()) {
+                            tracing::warn!(error = %err, "Failed to purge expired trash");
+                        }
+                        send_trash(&evt_tx);
                     }
                     Err(err) => {
                         let _ = evt_tx.send(AppEvent::Error(err.to_string()));
@@ -165,137 +341,301 @@ pub fn start_worker(cmd_rx: Receiver<AppCommand>, evt_tx: Sender<AppEvent>) -> J
                         let _ = evt_tx.send(AppEvent::Error(err.to_string()));
                     }
                 },
+                AppCommand::AddApiKeyProfile { name, api_key } => {
+                    // Check the key before saving it, so a typo doesn't become a broken profile
+                    let api_key = api_key.trim().to_string();
+                    let result = runtime
+                        .block_on(api::fetch_quota(&auth::api_key_auth(&api_key)))
+                        .map_err(|err| match err.downcast_ref::<api::AuthError>() {
+                            Some(api::AuthError::Expired) => {
+                                anyhow::anyhow!("The API key was rejected.")
+                            }
+                            None => err,
+                        })
+                        .and_then(|quota| {
+                            Ok((profile::create_api_key_profile(&name, &api_key)?, quota))
+                        });
+                    match result {
+                        Ok((name, quota)) => {
+                            quota_cache.record_success(&name, quota, chrono::Utc::now());
+                            save_quota_cache(&quota_cache);
+                            let outcome = profile::SaveProfileOutcome::Created { name };
+                            let _ = evt_tx.send(AppEvent::ProfileSaved(outcome));
+                            send_profiles(&evt_tx, &quota_cache);
+                        }
+                        Err(err) => {
+                            let _ = evt_tx.send(AppEvent::Error(format!(
+                                "Failed to add API key profile: {err}"
+                            )));
+                        }
+                    }
+                }
                 AppCommand::DeleteProfile(name) => match profile::delete_profile(&name) {
-                    Ok(()) => {
+                    Ok(entry) => {
+                        // The cached quota stays so a restored profile shows it right away
+                        let _ = evt_tx.send(AppEvent::ProfileTrashed(entry));
                         if let Ok(profiles) = profile::list_profiles_data() {
                             let _ = evt_tx.send(AppEvent::ProfilesLoaded(profiles));
                         }
+                        send_trash(&evt_tx);
+                    }
+                    Err(err) => {
+                        let _ = evt_tx.send(AppEvent::Error(err.to_string()));
+                    }
+                },
+                AppCommand::RestoreProfile(id) => match trash::restore_profile(&id) {
+                    Ok(name) => {
+                        let _ = evt_tx.send(AppEvent::ProfileRestored { name });
+                        send_profiles(&evt_tx, &quota_cache);
+                        send_trash(&evt_tx);
                     }
                     Err(err) => {
                         let _ = evt_tx.send(AppEvent::Error(err.to_string()));
                     }
                 },
+                AppCommand::MergeDuplicateProfiles => match profile::merge_duplicate_profiles() {
+                    Ok(merges) => {
+                        for name in merges.iter().flat_map(|merge| &merge.merged) {
+                            quota_cache.remove(name);
+                        }
+                        save_quota_cache(&quota_cache);
+                        let _ = evt_tx.send(AppEvent::DuplicatesMerged(merges));
+                        send_profiles(&evt_tx, &quota_cache);
+                        send_trash(&evt_tx);
+                    }
+                    Err(err) => {
+                        let _ = evt_tx.send(AppEvent::Error(format!(
+                            "Failed to merge duplicate profiles: {err}"
+                        )));
+                    }
+                },
+                AppCommand::ListWorkspaces(name) => {
+                    let result = profile::load_profile_auth(&name).and_then(|auth| {
+                        let workspaces = runtime.block_on(api::fetch_workspaces(&auth))?;
+                        Ok((auth::get_account_id(&auth), workspaces))
+                    });
+                    match result {
+                        Ok((own_account_id, workspaces)) => {
+                            let _ = evt_tx.send(AppEvent::WorkspacesLoaded {
+                                profile: name,
+                                own_account_id,
+                                workspaces,
+                            });
+                        }
+                        Err(err) => {
+                            let _ = evt_tx.send(AppEvent::Error(format!(
+                                "Failed to list workspaces for {name}: {err}"
+                            )));
+                        }
+                    }
+                }
+                AppCommand::AddWorkspaceProfile { parent, workspace } => {
+                    match profile::create_workspace_profile(
+                        &parent,
+                        &workspace.account_id,
+                        &workspace.label(),
+                    ) {
+                        Ok(name) => {
+                            let outcome = profile::SaveProfileOutcome::Created { name };
+                            let _ = evt_tx.send(AppEvent::ProfileSaved(outcome));
+                            send_profiles(&evt_tx, &quota_cache);
+                        }
+                        Err(err) => {
+                            let _ = evt_tx.send(AppEvent::Error(format!(
+                                "Failed to add workspace {}: {err}",
+                                workspace.label()
+                            )));
+                        }
+                    }
+                }
+                AppCommand::PurgeTrashEntry(id) => match trash::purge_entry(&id) {
+                    Ok(()) => send_trash(&evt_tx),
+                    Err(err) => {
+                        let _ = evt_tx.send(AppEvent::Error(err.to_string()));
+                    }
+                },
+                AppCommand::UpdateProfileMeta { name, mut meta } => {
+                    // The edited copy may predate the proxy's latest use of the profile
+                    if let Ok(saved) = profile_meta::load_meta(&name) {
+                        meta.last_used_at = meta.last_used_at.max(saved.last_used_at);
+                    }
+                    match profile_meta::save_meta(&name, &meta) {
+                        Ok(()) => send_profiles(&evt_tx, &quota_cache),
+                        Err(err) => {
+                            let _ = evt_tx.send(AppEvent::Error(format!(
+                                "Failed to update profile {name}: {err}"
+                            )));
+                        }
+                    }
+                }
+                AppCommand::RecordProfileUse(name) => {
+                    if let Err(err) = profile_meta::touch_last_used(&name, chrono::Utc::now()) {
+                        tracing::warn!(profile = %name, error = %err, "Failed to record profile use");
+                    }
+                }
+                AppCommand::RenameProfile { name, new_name } => {
+                    match profile::rename_profile(&name, &new_name) {
+                        Ok(new_name) => {
+                            quota_cache.rename(&name, &new_name);
+                            save_quota_cache(&quota_cache);
+                            if let Err(err) = history::rename_profile(&name, &new_name) {
+                                tracing::warn!(error = %err, "Failed to rename quota history");
+                            }
+                            let _ = evt_tx.send(AppEvent::ProfileRenamed {
+                                old_name: name,
+                                new_name,
+                            });
+                            send_profiles(&evt_tx, &quota_cache);
+                        }
+                        Err(err) => {
+                            let _ = evt_tx.send(AppEvent::Error(format!(
+                                "Failed to rename profile {name}: {err}"
+                            )));
+                        }
+                    }
+                }
                 AppCommand::FetchQuota => {
-                    match load_profiles_with_quota(&runtime) {
+                    let result = load_profiles_with_quota(&runtime, &evt_tx, &mut quota_cache);
+                    save_quota_cache(&quota_cache);
+                    match result {
                         Ok(profiles) => {
                             let _ = evt_tx.send(AppEvent::ProfilesLoaded(profiles));
+                            send_quota_history(&evt_tx);
                         }
                         Err(err) => {
                             let _ = evt_tx.send(AppEvent::Error(err.to_string()));
                         }
                     };
                 }
-                AppCommand::FetchProfileQuota(name) => match profile::load_profile_auth(&name) {
-                    Ok(mut auth) => match runtime.block_on(api::fetch_quota(&auth)) {
-                        Ok(quota) => {
-                            let _ = evt_tx.send(AppEvent::ProfileQuotaLoaded { name, quota });
+                AppCommand::FetchProfileQuota { name, force } => {
+                    let now = chrono::Utc::now();
+                    if !force && !quota_cache.should_fetch(&name, now) {
+                        if let Some(entry) = quota_cache.entry(&name) {
+                            if let Some(cached) = &entry.last_good {
+                                let _ = evt_tx.send(AppEvent::ProfileQuotaLoaded {
+                                    name: name.clone(),
+                                    quota: cached.quota.clone(),
+                                    fetched_at: cached.fetched_at,
+                                    stale: entry.is_stale(now),
+                                });
+                            } else if let Some(retry_at) = entry.retry_at {
+                                // Still backing off with nothing cached: repeat the last error
+                                let _ = evt_tx.send(AppEvent::ProfileQuotaFailed {
+                                    name: name.clone(),
+                                    error: entry
+                                        .last_error
+                                        .clone()
+                                        .unwrap_or_else(|| "Quota fetch failed".to_string()),
+                                    retry_at,
+                                });
+                            }
                         }
+                        continue;
+                    }
+                    let auth = match profile::load_profile_auth(&name) {
+                        Ok(auth) => auth,
                         Err(err) => {
-                            if let Some(api::AuthError::Expired) =
-                                err.downcast_ref::<api::AuthError>()
+                            let _ = evt_tx.send(AppEvent::Error(format!(
+                                "Failed to load profile {}: {}",
+                                name, err
+                            )));
+                            continue;
+                        }
+                    };
+                    match fetch_quota_with_refresh(&runtime, &evt_tx, &name, auth) {
+                        QuotaFetch::Loaded(quota) => {
+                            record_quota_history(&name, &quota);
+                            quota_cache.record_success(&name, (*quota).clone(), now);
+                            save_quota_cache(&quota_cache);
+                            let _ = evt_tx.send(AppEvent::ProfileQuotaLoaded {
+                                name,
+                                quota: *quota,
+                                fetched_at: now,
+                                stale: false,
+                            });
+                            send_quota_history(&evt_tx);
+                        }
+                        QuotaFetch::LoginExpired => {
+                            if let Ok(mut profiles) = list_profiles_with_cached_quota(&quota_cache)
                             {
-                                if let Some(ref tokens) = auth.tokens {
-                                    tracing::info!(
-                                        profile = %name,
-                                        "Access token expired, attempting refresh"
-                                    );
-                                    match runtime.block_on(api::refresh_token(&tokens.refresh_token))
-                                    {
-                                        Ok(refresh_response) => {
-                                            // Update auth with new tokens
-                                            if let Some(ref mut tokens) = auth.tokens {
-                                                if let Some(new_access) = refresh_response.access_token {
-                                                    tokens.access_token = new_access;
-                                                }
-                                                if let Some(new_refresh) = refresh_response.refresh_token
-                                                {
-                                                    tokens.refresh_token = new_refresh;
-                                                }
-                                                // Note: id_token update requires parsing, skip for now
-                                            }
-                                            auth.last_refresh = Some(chrono::Utc::now());
-
-                                            // Save updated auth to profile
-                                            if let Err(save_err) =
-                                                profile::save_profile_auth(&name, &auth)
-                                            {
-                                                tracing::warn!(
-                                                    profile = %name,
-                                                    error = %save_err,
-                                                    "Failed to save refreshed tokens"
-                                                );
-                                            }
-
-                                            // Retry quota fetch with new tokens
-                                            match runtime.block_on(api::fetch_quota(&auth)) {
-                                                Ok(quota) => {
-                                                    let _ = evt_tx.send(AppEvent::ProfileQuotaLoaded {
-                                                        name,
-                                                        quota,
-                                                    });
-                                                }
-                                                Err(retry_err) => {
-                                                    tracing::warn!(
-                                                        profile = %name,
-                                                        error = %retry_err,
-                                                        "Quota fetch failed after token refresh"
-                                                    );
-                                                    if let Ok(mut profiles) =
-                                                        profile::list_profiles_data()
-                                                    {
-                                                        if let Some(profile) = profiles
-                                                            .iter_mut()
-                                                            .find(|p| p.name == name)
-                                                        {
-                                                            profile.is_valid = false;
-                                                            profile.quota = None;
-                                                        }
-                                                        let _ = evt_tx.send(AppEvent::ProfilesLoaded(
-                                                            profiles,
-                                                        ));
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        Err(refresh_err) => {
-                                            tracing::warn!(
-                                                profile = %name,
-                                                error = %refresh_err,
-                                                "Token refresh failed"
-                                            );
-                                            if let Ok(mut profiles) = profile::list_profiles_data() {
-                                                if let Some(profile) =
-                                                    profiles.iter_mut().find(|p| p.name == name)
-                                                {
-                                                    profile.is_valid = false;
-                                                    profile.quota = None;
-                                                }
-                                                let _ = evt_tx.send(AppEvent::ProfilesLoaded(profiles));
-                                            }
-                                        }
-                                    }
-                                } else if let Ok(mut profiles) = profile::list_profiles_data() {
-                                    if let Some(profile) =
-                                        profiles.iter_mut().find(|p| p.name == name)
-                                    {
-                                        profile.is_valid = false;
-                                        profile.quota = None;
-                                    }
-                                    let _ = evt_tx.send(AppEvent::ProfilesLoaded(profiles));
+                                if let Some(profile) = profiles.iter_mut().find(|p| p.name == name)
+                                {
+                                    profile.is_valid = false;
+                                    profile.quota = None;
+                                    profile.quota_fetched_at = None;
+                                    profile.quota_stale = false;
                                 }
-                            } else {
-                                let _ = evt_tx.send(AppEvent::Error(format!(
-                                    "Failed to fetch quota for {}: {}",
-                                    name, err
-                                )));
+                                let _ = evt_tx.send(AppEvent::ProfilesLoaded(profiles));
                             }
                         }
-                    },
+                        QuotaFetch::Failed(err) => {
+                            let error = err.to_string();
+                            let retry_at = quota_cache.record_failure(&name, &error, now);
+                            save_quota_cache(&quota_cache);
+                            let _ = evt_tx.send(AppEvent::ProfileQuotaFailed {
+                                name,
+                                error,
+                                retry_at,
+                            });
+                        }
+                    }
+                }
+                AppCommand::ExportProfiles {
+                    names,
+                    path,
+                    passphrase,
+                } => match bundle::export_profiles(&names, &path, &passphrase) {
+                    Ok(count) => {
+                        let _ = evt_tx.send(AppEvent::ProfilesExported { count, path });
+                    }
                     Err(err) => {
-                        let _ = evt_tx.send(AppEvent::Error(format!(
-                            "Failed to load profile {}: {}",
-                            name, err
-                        )));
+                        let _ = evt_tx
+                            .send(AppEvent::Error(format!("Failed to export profiles: {err}")));
+                    }
+                },
+                AppCommand::ImportProfiles { path, passphrase } => {
+                    match bundle::import_bundle(&path, &passphrase) {
+                        Ok(outcomes) => {
+                            let _ = evt_tx.send(AppEvent::ProfilesImported(outcomes));
+                            send_profiles(&evt_tx, &quota_cache);
+                        }
+                        Err(err) => {
+                            let _ = evt_tx
+                                .send(AppEvent::Error(format!("Failed to import profiles: {err}")));
+                        }
+                    }
+                }
+                AppCommand::UnlockVault(passphrase) => match secret_store::unlock(&passphrase) {
+                    Ok(()) => {
+                        send_vault_status(&evt_tx, Some("Vault unlocked".to_string()));
+                        send_profiles(&evt_tx, &quota_cache);
+                    }
+                    Err(err) => {
+                        let _ = evt_tx.send(AppEvent::Error(err.to_string()));
+                    }
+                },
+                AppCommand::LockVault => match secret_store::lock() {
+                    Ok(()) => send_vault_status(&evt_tx, Some("Vault locked".to_string())),
+                    Err(err) => {
+                        let _ = evt_tx.send(AppEvent::Error(err.to_string()));
+                    }
+                },
+                AppCommand::EnableVault(passphrase) => match secret_store::enable(&passphrase) {
+                    Ok(count) => {
+                        send_vault_status(&evt_tx, Some(format!("Encrypted {count} profile(s)")));
+                        send_profiles(&evt_tx, &quota_cache);
+                    }
+                    Err(err) => {
+                        let _ = evt_tx.send(AppEvent::Error(err.to_string()));
+                    }
+                },
+                AppCommand::DisableVault => match secret_store::disable() {
+                    Ok(count) => {
+                        send_vault_status(&evt_tx, Some(format!("Decrypted {count} profile(s)")));
+                        send_profiles(&evt_tx, &quota_cache);
+                    }
+                    Err(err) => {
+                        let _ = evt_tx.send(AppEvent::Error(err.to_string()));
                     }
                 },
                 AppCommand::RunLogin => {
@@ -357,6 +697,10 @@ pub fn start_worker(cmd_rx: Receiver<AppCommand>, evt_tx: Sender<AppEvent>) -> J
                                                 // Fetch quota only for this profile
                                                 match runtime.block_on(api::fetch_quota(&auth)) {
                                                     Ok(quota) => {
+                                                        record_quota_history(
+                                                            &current_profile.name,
+                                                            &quota,
+                                                        );
                                                         current_profile.quota = Some(quota.clone());
                                                         let _ = evt_tx
                                                             .send(AppEvent::QuotaLoaded(quota));
@@ -368,6 +712,7 @@ pub fn start_worker(cmd_rx: Receiver<AppCommand>, evt_tx: Sender<AppEvent>) -> J
                                             }
                                         }
                                         let _ = evt_tx.send(AppEvent::ProfilesLoaded(profiles));
+                                        send_quota_history(&evt_tx);
                                     }
                                     Err(err) => {
                                         let _ = evt_tx.send(AppEvent::Error(err.to_string()));
@@ -390,6 +735,30 @@ pub fn start_worker(cmd_rx: Receiver<AppCommand>, evt_tx: Sender<AppEvent>) -> J
                 AppCommand::OpenLoginUrl(url) => {
                     let _ = Command::new("open").arg(url).status();
                 }
+                AppCommand::CheckOfficialAuth => match check_official_auth(&runtime) {
+                    Ok(change) => {
+                        let _ = evt_tx.send(AppEvent::OfficialAuthChanged(change));
+                        send_profiles(&evt_tx, &quota_cache);
+                    }
+                    Err(err) => {
+                        tracing::warn!(error = %err, "Failed to check official Codex auth");
+                    }
+                },
+                AppCommand::ImportOfficialAuth => match import_official_auth(&runtime) {
+                    Ok(outcome) => {
+                        let _ = evt_tx.send(AppEvent::ProfileSaved(outcome));
+                        let _ = evt_tx.send(AppEvent::OfficialAuthChanged(None));
+                        send_profiles(&evt_tx, &quota_cache);
+                    }
+                    Err(err) => {
+                        let _ = evt_tx.send(AppEvent::Error(format!(
+                            "Failed to import Codex CLI login: {err}"
+                        )));
+                    }
+                },
+                AppCommand::Tracked { .. } => {
+                    tracing::warn!("Ignoring a nested tracked command");
+                }
                 AppCommand::Shutdown => break,
             }
         }
@@ -496,6 +865,120 @@ mod tests {
         // But since we removed get_official... usage, we are good.)
     }
 
+    fn account_auth(account_id: &str, access_token: &str) -> auth::AuthDotJson {
+        auth::AuthDotJson {
+            openai_api_key: None,
+            tokens: Some(auth::TokenData {
+                id_token: None,
+                access_token: access_token.to_string(),
+                refresh_token: "refresh".to_string(),
+                account_id: Some(account_id.to_string()),
+            }),
+            last_refresh: None,
+        }
+    }
+
+    #[test]
+    fn check_official_auth_follows_cli_account_and_reports_new_ones() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _codex_home_guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        let _home_guard = EnvGuard::set("HOME", temp_dir.path());
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let profiles_dir = temp_dir.path().join("profiles");
+        for (name, account_id) in [("alpha", "acct_a"), ("beta", "acct_b")] {
+            fs::create_dir_all(profiles_dir.join(name)).unwrap();
+            fs::write(
+                profiles_dir.join(name).join("auth.json"),
+                serde_json::to_string(&account_auth(account_id, "access")).unwrap(),
+            )
+            .unwrap();
+        }
+        fs::write(temp_dir.path().join(".current_profile"), "alpha").unwrap();
+        let official_file = temp_dir.path().join(".codex").join("auth.json");
+        fs::create_dir_all(official_file.parent().unwrap()).unwrap();
+
+        fs::write(
+            &official_file,
+            serde_json::to_string(&account_auth("acct_b", "access")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(check_official_auth(&runtime).unwrap(), None);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(".current_profile")).unwrap(),
+            "beta"
+        );
+
+        fs::write(
+            &official_file,
+            serde_json::to_string(&account_auth("acct_b", "rotated")).unwrap(),
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join(".current_profile"), "alpha").unwrap();
+        assert_eq!(check_official_auth(&runtime).unwrap(), None);
+        assert_eq!(
+            profile::load_profile_auth("beta")
+                .unwrap()
+                .tokens
+                .unwrap()
+                .access_token,
+            "rotated"
+        );
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(".current_profile")).unwrap(),
+            "beta"
+        );
+
+        fs::write(
+            &official_file,
+            serde_json::to_string(&account_auth("acct_c", "access")).unwrap(),
+        )
+        .unwrap();
+        let change = check_official_auth(&runtime).unwrap().unwrap();
+        assert!(matches!(change.status, profile::AuthMatch::New { .. }));
+
+        let outcome = import_official_auth(&runtime).unwrap();
+        assert!(matches!(
+            outcome,
+            profile::SaveProfileOutcome::Created { .. }
+        ));
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(".current_profile")).unwrap(),
+            outcome.name()
+        );
+        assert_eq!(check_official_auth(&runtime).unwrap(), None);
+    }
+
+    #[test]
+    fn check_official_auth_recognizes_workspace_profiles() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _codex_home_guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        let _home_guard = EnvGuard::set("HOME", temp_dir.path());
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let profiles_dir = temp_dir.path().join("profiles");
+        fs::create_dir_all(profiles_dir.join("work")).unwrap();
+        fs::write(
+            profiles_dir.join("work").join("auth.json"),
+            serde_json::to_string(&account_auth("acct_personal", "access")).unwrap(),
+        )
+        .unwrap();
+        let workspace = profile::create_workspace_profile("work", "acct_team", "Team").unwrap();
+
+        runtime
+            .block_on(profile::switch_profile(&workspace))
+            .unwrap();
+
+        assert_eq!(check_official_auth(&runtime).unwrap(), None);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(".current_profile")).unwrap(),
+            workspace
+        );
+        assert!(!profiles_dir.join(&workspace).join("auth.json").exists());
+    }
+
     #[test]
     #[ignore = "test was designed for CLI-based login, now uses async HTTP flow"]
     fn run_login_populates_profile_quotas() {
@@ -728,23 +1211,79 @@ mod tests {
         let handle = start_worker(cmd_rx, evt_tx);
 
         cmd_tx
-            .send(AppCommand::FetchProfileQuota("alpha".to_string()))
+            .send(AppCommand::FetchProfileQuota {
+                name: "alpha".to_string(),
+                force: false,
+            })
             .unwrap();
 
         let event = evt_rx.recv_timeout(Duration::from_secs(5)).unwrap();
         match event {
-            AppEvent::ProfileQuotaLoaded { name, quota } => {
+            AppEvent::ProfileQuotaLoaded {
+                name, quota, stale, ..
+            } => {
                 assert_eq!(name, "alpha");
-                assert_eq!(quota.used_requests, Some(50));
+                assert!(!stale);
+                assert_eq!(quota.primary_used_percent(), Some(50));
             }
             _ => panic!("unexpected event: {:?}", event),
         }
 
+        let history = history::load_history().unwrap();
+        assert_eq!(history.samples("alpha").len(), 1);
+        assert_eq!(history.samples("alpha")[0].primary_used, Some(50));
+
         cmd_tx.send(AppCommand::Shutdown).unwrap();
         handle.join().unwrap();
         server.join().unwrap();
     }
 
+    #[test]
+    fn fetch_profile_quota_reports_backoff_unless_forced() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        let mut cache = QuotaCache::default();
+        let retry_at = cache.record_failure("alpha", "timeout", chrono::Utc::now());
+        quota_cache::save_cache(&cache).unwrap();
+
+        let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
+        let (evt_tx, evt_rx) = std::sync::mpsc::channel();
+        let handle = start_worker(cmd_rx, evt_tx);
+        cmd_tx
+            .send(AppCommand::FetchProfileQuota {
+                name: "alpha".to_string(),
+                force: false,
+            })
+            .unwrap();
+        match evt_rx.recv_timeout(Duration::from_secs(5)).unwrap() {
+            AppEvent::ProfileQuotaFailed {
+                name,
+                error,
+                retry_at: reported,
+            } => {
+                assert_eq!(name, "alpha");
+                assert_eq!(error, "timeout");
+                assert_eq!(reported, retry_at);
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+
+        // A forced refresh tries the fetch, which fails here on the missing profile
+        cmd_tx
+            .send(AppCommand::FetchProfileQuota {
+                name: "alpha".to_string(),
+                force: true,
+            })
+            .unwrap();
+        let event = evt_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(event, AppEvent::Error(ref message) if message.contains("alpha")));
+
+        cmd_tx.send(AppCommand::Shutdown).unwrap();
+        handle.join().unwrap();
+    }
+
     #[test]
     fn fetch_profile_quota_marks_profile_invalid_when_token_expired() {
         let _lock = ENV_LOCK.lock().unwrap();
@@ -836,9 +1375,17 @@ mod tests {
         let handle = start_worker(cmd_rx, evt_tx);
 
         cmd_tx
-            .send(AppCommand::FetchProfileQuota("expired".to_string()))
+            .send(AppCommand::FetchProfileQuota {
+                name: "expired".to_string(),
+                force: false,
+            })
             .unwrap();
 
+        let event = evt_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(
+            event,
+            AppEvent::TokenRefreshed { ref name, success: false } if name == "expired"
+        ));
         let event = evt_rx.recv_timeout(Duration::from_secs(5)).unwrap();
         match event {
             AppEvent::ProfilesLoaded(profiles) => {
@@ -900,7 +1447,9 @@ mod tests {
         .unwrap();
 
         let runtime = tokio::runtime::Runtime::new().unwrap();
-        let profiles = load_profiles_with_quota(&runtime).unwrap();
+        let (evt_tx, _evt_rx) = std::sync::mpsc::channel();
+        let profiles =
+            load_profiles_with_quota(&runtime, &evt_tx, &mut QuotaCache::default()).unwrap();
 
         assert_eq!(profiles.len(), 1);
         assert_eq!(profiles[0].name, "expired_profile");
@@ -910,7 +1459,84 @@ mod tests {
         server_thread.join().unwrap();
     }
     #[test]
-    fn test_delete_profile_removes_profile() {
+    fn load_profiles_keeps_last_good_quota_when_fetch_fails() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _base_url_guard =
+            StringEnvGuard::set("CODEX_ROUTER_CHATGPT_BASE_URL", format!("http://{addr}"));
+
+        // Both usage paths are tried before giving up.
+        let server_thread = thread::spawn(move || {
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let body = "upstream unavailable";
+                let response = format!(
+                    "HTTP/1.1 503 Service Unavailable\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let auth = auth::AuthDotJson {
+            openai_api_key: None,
+            tokens: Some(auth::TokenData {
+                id_token: None,
+                access_token: "access".to_string(),
+                refresh_token: "refresh".to_string(),
+                account_id: Some("acct_123".to_string()),
+            }),
+            last_refresh: None,
+        };
+        let profiles_dir = temp_dir.path().join("profiles");
+        fs::create_dir_all(profiles_dir.join("work")).unwrap();
+        fs::write(
+            profiles_dir.join("work").join("auth.json"),
+            serde_json::to_string_pretty(&auth).unwrap(),
+        )
+        .unwrap();
+
+        let cached = api::QuotaInfo {
+            account_id: "acct_123".to_string(),
+            email: String::new(),
+            plan_type: "pro".to_string(),
+            primary: Some(api::RateLimitWindow {
+                used_percent: 42.0,
+                window_seconds: None,
+                resets_at: None,
+            }),
+            secondary: None,
+            credits: None,
+            usage: None,
+        };
+        let fetched_at = chrono::Utc::now() - chrono::Duration::minutes(5);
+        let mut quota_cache = QuotaCache::default();
+        quota_cache.record_success("work", cached.clone(), fetched_at);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (evt_tx, _evt_rx) = std::sync::mpsc::channel();
+        let profiles = load_profiles_with_quota(&runtime, &evt_tx, &mut quota_cache).unwrap();
+
+        assert!(profiles[0].is_valid);
+        assert_eq!(profiles[0].quota, Some(cached));
+        assert_eq!(profiles[0].quota_fetched_at, Some(fetched_at));
+        assert!(profiles[0].quota_stale);
+        let entry = quota_cache.entry("work").unwrap();
+        assert_eq!(entry.consecutive_failures, 1);
+        assert!(!quota_cache.should_fetch("work", chrono::Utc::now()));
+
+        server_thread.join().unwrap();
+    }
+
+    #[test]
+    fn test_delete_profile_moves_profile_to_trash() {
         let _lock = ENV_LOCK.lock().unwrap();
         let temp_dir = tempfile::tempdir().unwrap();
         let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
@@ -918,6 +1544,9 @@ mod tests {
         let profiles_dir = temp_dir.path().join("profiles");
         fs::create_dir_all(profiles_dir.join("to_delete")).unwrap();
         fs::write(profiles_dir.join("to_delete").join("auth.json"), "{}").unwrap();
+        let mut cache = QuotaCache::default();
+        cache.record_failure("to_delete", "boom", chrono::Utc::now());
+        quota_cache::save_cache(&cache).unwrap();
 
         let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
         let (evt_tx, evt_rx) = std::sync::mpsc::channel();
@@ -927,6 +1556,12 @@ mod tests {
             .send(AppCommand::DeleteProfile("to_delete".to_string()))
             .unwrap();
 
+        let entry = match evt_rx.recv_timeout(Duration::from_secs(1)).unwrap() {
+            AppEvent::ProfileTrashed(entry) => entry,
+            _ => panic!("unexpected event"),
+        };
+        assert_eq!(entry.profile_name, "to_delete");
+
         // Expect ProfilesLoaded event
         let event = evt_rx.recv_timeout(Duration::from_secs(1)).unwrap();
         match event {
@@ -937,6 +1572,123 @@ mod tests {
         }
 
         assert!(!profiles_dir.join("to_delete").exists());
+        assert_eq!(trash::list_trash().unwrap(), vec![entry]);
+        // Kept for a later restore
+        assert!(quota_cache::load_cache()
+            .unwrap()
+            .entry("to_delete")
+            .is_some());
+
+        cmd_tx.send(AppCommand::Shutdown).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn tracked_commands_are_bracketed_by_their_id() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
+        let (evt_tx, evt_rx) = std::sync::mpsc::channel();
+        let handle = start_worker(cmd_rx, evt_tx);
+
+        cmd_tx
+            .send(AppCommand::Tracked {
+                id: 7,
+                command: Box::new(AppCommand::DeleteProfile("missing".to_string())),
+            })
+            .unwrap();
+
+        let next = || evt_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(matches!(next(), AppEvent::CommandStarted(7)));
+        assert!(matches!(next(), AppEvent::Error(_)));
+        assert!(matches!(next(), AppEvent::CommandFinished(7)));
+
+        cmd_tx.send(AppCommand::Shutdown).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn profile_use_survives_a_stale_meta_edit() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+        fs::create_dir_all(temp_dir.path().join("profiles").join("work")).unwrap();
+
+        let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
+        let (evt_tx, evt_rx) = std::sync::mpsc::channel();
+        let handle = start_worker(cmd_rx, evt_tx);
+
+        cmd_tx
+            .send(AppCommand::RecordProfileUse("work".to_string()))
+            .unwrap();
+        // Edited in the UI before the use was recorded
+        let meta = profile_meta::ProfileMeta {
+            notes: "edited".to_string(),
+            ..profile_meta::ProfileMeta::default()
+        };
+        cmd_tx
+            .send(AppCommand::UpdateProfileMeta {
+                name: "work".to_string(),
+                meta,
+            })
+            .unwrap();
+        assert!(matches!(
+            evt_rx.recv_timeout(Duration::from_secs(1)).unwrap(),
+            AppEvent::ProfilesLoaded(_)
+        ));
+
+        let saved = profile_meta::load_meta("work").unwrap();
+        assert_eq!(saved.notes, "edited");
+        assert!(saved.last_used_at.is_some());
+
+        cmd_tx.send(AppCommand::Shutdown).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn rename_profile_carries_quota_cache_over() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::set("CODEX_HOME", temp_dir.path());
+
+        let profiles_dir = temp_dir.path().join("profiles");
+        fs::create_dir_all(profiles_dir.join("john-2")).unwrap();
+        fs::write(profiles_dir.join("john-2").join("auth.json"), "{}").unwrap();
+        let mut cache = QuotaCache::default();
+        cache.record_failure("john-2", "boom", chrono::Utc::now());
+        quota_cache::save_cache(&cache).unwrap();
+
+        let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
+        let (evt_tx, evt_rx) = std::sync::mpsc::channel();
+        let handle = start_worker(cmd_rx, evt_tx);
+
+        cmd_tx
+            .send(AppCommand::RenameProfile {
+                name: "john-2".to_string(),
+                new_name: "John".to_string(),
+            })
+            .unwrap();
+
+        match evt_rx.recv_timeout(Duration::from_secs(1)).unwrap() {
+            AppEvent::ProfileRenamed { old_name, new_name } => {
+                assert_eq!(old_name, "john-2");
+                assert_eq!(new_name, "john");
+            }
+            _ => panic!("unexpected event"),
+        }
+        match evt_rx.recv_timeout(Duration::from_secs(1)).unwrap() {
+            AppEvent::ProfilesLoaded(profiles) => {
+                assert_eq!(profiles.len(), 1);
+                assert_eq!(profiles[0].name, "john");
+            }
+            _ => panic!("unexpected event"),
+        }
+
+        let cache = quota_cache::load_cache().unwrap();
+        assert!(cache.entry("john-2").is_none());
+        assert_eq!(cache.entry("john").unwrap().consecutive_failures, 1);
 
         cmd_tx.send(AppCommand::Shutdown).unwrap();
         handle.join().unwrap();
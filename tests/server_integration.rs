@@ -1,7 +1,7 @@
 use axum::extract::{Json, State};
 use axum::response::IntoResponse;
 use codex_router::{
-    api::QuotaInfo,
+    api::{QuotaInfo, RateLimitWindow},
     profile::ProfileSummary,
     server::{handle_chat_completions, ChatRequest},
     shared::SharedState,
@@ -9,7 +9,7 @@ use codex_router::{
 use std::collections::HashMap;
 use std::fs;
 use std::sync::Mutex;
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
 use tempfile::TempDir;
 use wiremock::matchers::{header, method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
@@ -83,9 +83,8 @@ async fn test_auto_switching_on_failure() {
         mock_profile_summary("p2", 90),
     ];
 
-    let state = Arc::new(SharedState {
-        profiles: Arc::new(RwLock::new(profiles)),
-    });
+    let state = Arc::new(SharedState::new());
+    state.update_profiles(profiles);
 
     // 4. Invoke Handler
     let req = ChatRequest {
@@ -95,11 +94,14 @@ async fn test_auto_switching_on_failure() {
         extra: HashMap::new(),
     };
 
-    let response = handle_chat_completions(State(state), Json(req)).await;
+    let response = handle_chat_completions(State(state.clone()), None, Json(req)).await;
 
     // 5. Assert
     let status = response.into_response().status();
     assert_eq!(status, 200);
+    let proxied = state.proxied_requests();
+    assert_eq!(proxied.get("p1").map(Vec::len), Some(1));
+    assert!(!proxied.contains_key("p2"));
 }
 
 fn create_profile(codex_home: &std::path::Path, name: &str, token: &str) {
@@ -126,12 +128,23 @@ fn mock_profile_summary(name: &str, used_tokens: u64) -> ProfileSummary {
             account_id: format!("acct_{}", name),
             email: format!("{}@example.com", name),
             plan_type: "plus".to_string(),
-            used_requests: Some(0),
-            total_requests: Some(100),
-            used_tokens: Some(used_tokens),
-            total_tokens: Some(100),
-            reset_date: None,
-            secondary_reset_date: None,
+            primary: Some(RateLimitWindow {
+                used_percent: 0.0,
+                window_seconds: None,
+                resets_at: None,
+            }),
+            secondary: Some(RateLimitWindow {
+                used_percent: used_tokens as f64,
+                window_seconds: None,
+                resets_at: None,
+            }),
+            credits: None,
+            usage: None,
         }),
+        quota_fetched_at: None,
+        quota_stale: false,
+        meta: Default::default(),
+        health: Default::default(),
+        auth_kind: Default::default(),
     }
 }